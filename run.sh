@@ -8,12 +8,66 @@
 #   ./run.sh --start-port 3000         # Ports 3000-6000
 #   ./run.sh --end-port 8080           # Ports 2000-8080
 #   ./run.sh --ports 3000,8000,8080    # Specific ports only
+#   ./run.sh --ports 3000-3010,8080    # Ranges and single ports mixed
+#   PORT_KILL_PORTS=3000,5173 ./run.sh # Ports from the environment when --ports isn't given
+#   ./run.sh -s 3000 -e 9000 --exclude-ports 5432,6379 # Range minus Postgres and Redis
+#   ./run.sh --reserve 5432,6379       # Alert when these are free, never kill what holds them
+#   ./run.sh --console --exclude-pids 4242,4243 # Never list or kill these PIDs
+#   ./run.sh --ports-file .ports       # Ports listed in a file (one per line)
+#   ./run.sh --discover                # Ports from .port-kill.toml or docker-compose.yml
 #   ./run.sh --console                 # Run in console mode
-#   ./run.sh --verbose                 # Enable verbose logging
+#   ./run.sh --console --interval 0.5  # Scan every half second (default 2s console, 5s tray)
+#   ./run.sh -vv                       # Debug logging (-v info is the default, -vvv trace)
 #   ./run.sh --docker                  # Enable Docker container monitoring
 #   ./run.sh --docker --ports 3000,3001 # Monitor specific ports with Docker
+#   ./run.sh --console --docker --docker-lazy # Look up containers only for shown or killed processes
+#   ./run.sh --docker --container-runtime podman # Resolve containers with podman instead of docker
+#   ./run.sh --kill-container 'web-*'  # Stop all containers whose name matches
+#   ./run.sh --kill-compose-project shop # Stop every container of the shop Compose project
+#   ./run.sh --kill-port 3000 --require-name node # Kill port 3000 only if node holds it
+#   ./run.sh --kill-port 3000 --restart # Kill port 3000, then relaunch the same command line
+#   ./run.sh --kill --ports 3000       # Kill whatever holds port 3000 and exit
+#   ./run.sh --kill --dry-run --ports 3000 # Only report what --kill would do
+#   ./run.sh --kill --yes --ports 3000 # Kill without asking for confirmation first
+#   ./run.sh --kill --pids 4242,4243   # Kill these PIDs with the usual escalation, no port scan
+#   ./run.sh --watch-only              # Observe ports without ever killing anything
+#   ./run.sh --kill --signal INT --ports 3000 # Send SIGINT first instead of SIGTERM
+#   ./run.sh --kill --kill-timeout 10000 --no-sigkill # Wait 10s for a clean exit, never SIGKILL
+#   ./run.sh --kill --kill-tree --ports 3000 # Also kill child workers still holding the port
+#   ./run.sh --kill --kill-retries 3 --ports 3000 # Retry signals that fail transiently (EPERM)
+#   ./run.sh --kill --no-verify --ports 3000 # Skip re-checking each port right before killing
+#   ./run.sh --log-file ~/port-kill-history.jsonl # Append a JSON line for every kill
+#   ./run.sh --config ~/work/port-kill.toml # Read defaults from another config file
 #   ./run.sh --show-pid                # Show process IDs in output
 #   ./run.sh --console --show-pid      # Console mode with PIDs shown
+#   ./run.sh --console --sort name     # List by process name instead of port (also: pid, age)
+#   ./run.sh --warn-count 3 --warn-color FFAA00 # Turn the icon orange from 3 processes up
+#   ./run.sh --no-default-ignores      # Also show noisy system processes (rapportd, ControlCenter, ...)
+#   ./run.sh --ignore Docker,rapportd  # Never show or kill matching processes (--only keeps just matches)
+#   ./run.sh --cmd-contains "manage.py runserver" # Match the full command line (--cmd-ignore to leave out)
+#   ./run.sh --name-regex '^node$'     # Only show and kill processes named exactly node
+#   ./run.sh --include-system          # Allow killing root-owned processes (--ignore-root hides them)
+#   ./run.sh --console --notify        # Desktop notification when something grabs a monitored port
+#   ./run.sh --console --protocol both # Look for TCP listeners and bound UDP sockets
+#   ./run.sh --console --host 127.0.0.1 # Only sockets bound to 127.0.0.1 or the wildcard address
+#   ./run.sh --console --udp           # Only bound UDP sockets (--tcp and --both also work)
+#   ./run.sh --console --unix-sockets /tmp/myapp # Include Unix sockets under /tmp/myapp
+#   ./run.sh --console --include-established # Also list clients connected to the ports
+#   ./run.sh --console --heatmap       # One character per port, redrawn in place
+#   ./run.sh --console --table         # Aligned PORT/PID/NAME/COMMAND/DOCKER table, colored on a terminal
+#   ./run.sh --console --socket /tmp/port-kill.sock # Serve updates to editor extensions
+#   ./run.sh --console --serve 127.0.0.1:7878 # GET /processes and POST /kill/{pid} over HTTP
+#   ./run.sh --console --metrics-addr 127.0.0.1:9184 # Prometheus metrics on GET /metrics
+#   ./run.sh --console --auto-kill --once --strict # Kill once, fail if any port is still busy
+#   ./run.sh --json --auto-kill --once > data.json # JSON data on stdout, summary on stderr
+#   ./run.sh --format csv --once > ports.csv # One CSV row per process (tsv too)
+#   ./run.sh --console --quiet         # Print only when the detected processes change (also with --json)
+#   ./run.sh --auto-kill --once --fail-fast # Stop at the first process that fails to die
+#   ./run.sh --console --snapshot-every 30 --output-dir snapshots # Full snapshot every 30 scans
+#   ./run.sh --wait-for-free 3000 --timeout 30 # Block until port 3000 is released
+#   ./run.sh --wait-for-listen 3000 --timeout 30 # Block until something listens on 3000
+#   ./run.sh --discover --print-config json # Show the merged settings and exit
+#   ./run.sh --install-service --ports 3000-3010 # Run headless at login (--uninstall-service removes it)
 
 echo "🚀 Starting Port Kill..."
 echo "📊 Status bar icon should appear shortly"
@@ -1,60 +1,87 @@
 use crate::{
+    compose,
+    container::ContainerCli,
+    error::PortKillError,
+    notify::Notifier,
+    policy::{self, SharedPolicy},
     process_monitor::ProcessMonitor,
-    tray_menu::TrayMenu,
-    types::{ProcessUpdate, StatusBarInfo},
-    cli::Args,
+    restart,
+    tray_menu::{AboutInfo, IconStyle, MenuAction, TrayMenu},
+    types::{Endpoint, ProcessInfo, ProcessUpdate, SocketState, StatusBarInfo},
+    cli::{Args, SortKey},
 };
 use std::collections::HashMap;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossbeam_channel::{bounded, Receiver};
-use log::{error, info};
+use log::{debug, error, info, warn};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use std::sync::Mutex as StdMutex;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
 use tray_icon::{
-    menu::MenuEvent,
+    menu::{MenuEvent, MenuId},
     TrayIcon, TrayIconBuilder,
 };
 use winit::event_loop::EventLoop;
 
+/// Time between tray scans unless `--interval` says otherwise.
+const TRAY_SCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A kill click that only goes through if the same item is clicked again before `expires_at`.
+struct PendingConfirmation {
+    id: MenuId,
+    expires_at: Instant,
+}
 
 pub struct PortKillApp {
-    tray_icon: Arc<StdMutex<Option<TrayIcon>>>,
+    tray_icon: Rc<RefCell<Option<TrayIcon>>>,
     menu_event_receiver: Receiver<MenuEvent>,
-    process_monitor: Arc<Mutex<ProcessMonitor>>,
-    update_receiver: Receiver<ProcessUpdate>,
     tray_menu: TrayMenu,
+    policy: SharedPolicy,
+    /// Performs every kill, the same way the console does
+    monitor: Arc<ProcessMonitor>,
+    /// Drives the monitor's kills from the threads they run on
+    runtime: Arc<Runtime>,
+    container_cli: ContainerCli,
     args: Args,
 }
 
 impl PortKillApp {
     pub fn new(args: Args) -> Result<Self> {
         // Create channels for communication
-        let (update_sender, update_receiver) = bounded(100);
         let (menu_sender, menu_event_receiver) = bounded(100);
 
-        // Create process monitor with configurable ports
-        let process_monitor = Arc::new(Mutex::new(ProcessMonitor::new(update_sender, args.get_ports_to_monitor(), args.docker)?));
+        // The tray scans and kills through the monitor but runs its own loop, so never reads
+        // monitoring updates
+        let (update_sender, _update_receiver) = bounded(1);
+        let monitor = ProcessMonitor::from_args(update_sender, &args)?;
+
+        // Ignore/protect rules consulted on every scan and kill
+        let policy = monitor.policy();
 
         // Create tray menu
-        let tray_menu = TrayMenu::new(menu_sender)?;
+        let tray_menu = TrayMenu::new(menu_sender, AboutInfo::from_args(&args, monitor.backend()), IconStyle::from_args(&args))?;
 
         Ok(Self {
-            tray_icon: Arc::new(StdMutex::new(None)),
+            tray_icon: Rc::new(RefCell::new(None)),
             menu_event_receiver,
-            process_monitor,
-            update_receiver,
             tray_menu,
+            policy,
+            monitor: Arc::new(monitor),
+            runtime: Arc::new(Runtime::new().context("Failed to start the runtime for kills")?),
+            container_cli: ContainerCli::from_args(&args),
             args,
         })
     }
 
-    pub fn run(mut self) -> Result<()> {
+    pub fn run(self) -> Result<()> {
         info!("Starting Port Kill application...");
 
         // Create event loop first (before any NSApplication initialization)
-        let event_loop = EventLoop::new()?;
+        let event_loop = EventLoop::new()
+            .context("Failed to create event loop (no display available? try --console)")?;
         
         // Now create the tray icon after the event loop is created
         info!("Creating tray icon...");
@@ -67,15 +94,16 @@ impl PortKillApp {
         info!("Tray icon created successfully!");
         
         // Store the tray icon
-        if let Ok(mut tray_icon_guard) = self.tray_icon.lock() {
-            *tray_icon_guard = Some(tray_icon);
-        }
+        *self.tray_icon.borrow_mut() = Some(tray_icon);
         
-        // For now, let's manually check for processes every 5 seconds in the event loop
+        // Check for processes on every scan interval from within the event loop, starting
+        // with the first iteration so the icon is accurate right after launch
         let tray_icon = self.tray_icon.clone();
-        let mut last_check = std::time::Instant::now();
-        let mut last_process_count = 0;
-        let mut last_menu_update = std::time::Instant::now();
+        let mut last_check: Option<Instant> = None;
+        let scan_interval = self.args.scan_interval().unwrap_or(TRAY_SCAN_INTERVAL);
+        // Processes the menu was last built from, so it is only rebuilt when they change
+        let mut menu_processes = ProcessUpdate::empty().processes;
+        let mut last_menu_update: Option<Instant> = None;
         let is_killing_processes = Arc::new(AtomicBool::new(false));
 
         // Give the tray icon time to appear
@@ -86,42 +114,218 @@ impl PortKillApp {
         // Set up menu event handling
         let menu_event_receiver = self.menu_event_receiver.clone();
         
+        // Processes from the latest scan, used to resolve per-process menu clicks
+        let mut current_processes = ProcessUpdate::empty().processes;
+        let confirm_window = self.args.confirm_window.map(Duration::from_secs);
+        let mut pending_confirmation: Option<PendingConfirmation> = None;
+        // Alerts when a reserved port is free
+        let mut notifier = Notifier::reserved_only().with_reserved_ports(self.args.reserve.clone());
+        
         // Run the event loop
-        event_loop.run(move |_event, _elwt| {
+        event_loop.run(move |_event, elwt| {
+            // Revert the confirmation label once the window has passed
+            if pending_confirmation.as_ref().is_some_and(|p| p.expires_at <= Instant::now()) {
+                info!("Kill confirmation expired");
+                pending_confirmation = None;
+                Self::rebuild_menu(&tray_icon, &current_processes, self.args.show_pid, self.args.sort, None, &self.tray_menu.about);
+            }
+            
             // Handle menu events (simplified to avoid crashes)
-            if let Ok(_event) = menu_event_receiver.try_recv() {
-                info!("Menu event received, starting process killing...");
-                is_killing_processes.store(true, Ordering::Relaxed);
+            if let Ok(event) = menu_event_receiver.try_recv() {
+                let mut action = TrayMenu::action_for(&event.id, &current_processes);
+                if self.args.watch_only && action.is_kill() {
+                    action = MenuAction::KillDisabled;
+                }
+                
+                // With a confirmation window, the first click on a kill item only arms it
+                let is_kill = action.is_kill();
+                if let (Some(window), true) = (confirm_window, is_kill) {
+                    let confirmed = pending_confirmation
+                        .as_ref()
+                        .is_some_and(|p| p.id == event.id && p.expires_at > Instant::now());
+                    
+                    if confirmed {
+                        pending_confirmation = None;
+                    } else {
+                        info!("Waiting for a confirming click on {:?}", event.id);
+                        pending_confirmation = Some(PendingConfirmation {
+                            id: event.id.clone(),
+                            expires_at: Instant::now() + window,
+                        });
+                        action = MenuAction::AwaitingConfirmation;
+                    }
+                    
+                    let confirming = pending_confirmation.as_ref().map(|p| &p.id);
+                    Self::rebuild_menu(&tray_icon, &current_processes, self.args.show_pid, self.args.sort, confirming, &self.tray_menu.about);
+                }
                 
-                // Spawn a detached thread to kill processes
-                let ports_to_kill = self.args.get_ports_to_monitor();
-                let is_killing_clone = is_killing_processes.clone();
-                std::thread::spawn(move || {
-                    // Add a longer delay to ensure the menu system is stable
-                    std::thread::sleep(std::time::Duration::from_millis(500));
-                    info!("Starting process killing...");
-                    match PortKillApp::kill_all_processes(&ports_to_kill) {
-                        Ok(_) => {
-                            info!("Process killing completed successfully");
-                            // Reset the flag after a delay to allow menu updates again
-                            std::thread::sleep(std::time::Duration::from_secs(2));
+                match action {
+                    MenuAction::KillAll => {
+                        info!("Kill All selected, starting process killing...");
+                        is_killing_processes.store(true, Ordering::Relaxed);
+                        
+                        // Spawn a detached thread to kill processes
+                        let monitor = self.monitor.clone();
+                        let runtime = self.runtime.clone();
+                        let is_killing_clone = is_killing_processes.clone();
+                        std::thread::spawn(move || {
+                            // Add a longer delay to ensure the menu system is stable
+                            std::thread::sleep(std::time::Duration::from_millis(500));
+                            info!("Starting process killing...");
+                            match runtime.block_on(monitor.kill_all_processes()) {
+                                Ok(_) => {
+                                    info!("Process killing completed successfully");
+                                    // Reset the flag after a delay to allow menu updates again
+                                    std::thread::sleep(std::time::Duration::from_secs(2));
+                                    is_killing_clone.store(false, Ordering::Relaxed);
+                                }
+                                Err(e) => {
+                                    error!("Failed to kill all processes: {}", e);
+                                    is_killing_clone.store(false, Ordering::Relaxed);
+                                }
+                            }
+                        });
+                    }
+                    MenuAction::KillPid(pid) | MenuAction::KillAndRestart(pid) if current_processes
+                        .values()
+                        .any(|p| p.pid == pid && policy::read(&self.policy).is_protected(p)) =>
+                    {
+                        warn!("PID {} holds a protected port or runs as root, not killing", pid);
+                    }
+                    MenuAction::KillPid(pid) | MenuAction::KillAndRestart(pid) if policy::read(&self.policy)
+                        .is_only_connected(current_processes.values().filter(|p| p.pid == pid)) =>
+                    {
+                        warn!("PID {} is only connected to a monitored port (pass --kill-established to kill it), not killing", pid);
+                    }
+                    MenuAction::KillPid(pid) => {
+                        info!("Kill selected for PID {}", pid);
+                        is_killing_processes.store(true, Ordering::Relaxed);
+                        
+                        let process_info = Self::scanned_process(&current_processes, pid);
+                        let monitor = self.monitor.clone();
+                        let runtime = self.runtime.clone();
+                        let is_killing_clone = is_killing_processes.clone();
+                        std::thread::spawn(move || {
+                            std::thread::sleep(std::time::Duration::from_millis(500));
+                            let result = match process_info {
+                                Some(process_info) => runtime.block_on(monitor.kill_scanned(&process_info)),
+                                None => runtime.block_on(monitor.kill_process(pid)),
+                            };
+                            if let Err(e) = result {
+                                error!("Failed to kill process {}: {}", pid, e);
+                            }
                             is_killing_clone.store(false, Ordering::Relaxed);
-                        }
-                        Err(e) => {
-                            error!("Failed to kill all processes: {}", e);
+                        });
+                    }
+                    MenuAction::KillAndRestart(pid) => {
+                        info!("Kill & Restart selected for PID {}", pid);
+                        is_killing_processes.store(true, Ordering::Relaxed);
+
+                        let process_info = Self::scanned_process(&current_processes, pid);
+                        let monitor = self.monitor.clone();
+                        let runtime = self.runtime.clone();
+                        let is_killing_clone = is_killing_processes.clone();
+                        std::thread::spawn(move || {
+                            std::thread::sleep(std::time::Duration::from_millis(500));
+                            if let Some(process_info) = process_info {
+                                match runtime.block_on(monitor.kill_scanned(&process_info)) {
+                                    Ok(()) if monitor.is_dry_run() => {
+                                        info!("Dry run: would restart {}", restart::describe(&process_info).unwrap_or_default())
+                                    }
+                                    Ok(()) => {
+                                        if let Err(e) = restart::respawn(&process_info) {
+                                            error!("{:#}", e);
+                                        }
+                                    }
+                                    Err(e) => error!("Failed to kill process {}: {}", pid, e),
+                                }
+                            }
                             is_killing_clone.store(false, Ordering::Relaxed);
-                        }
+                        });
+                    }
+                    MenuAction::KillPort(port) => {
+                        info!("Kill selected for port {}", port);
+                        is_killing_processes.store(true, Ordering::Relaxed);
+
+                        let monitor = self.monitor.clone();
+                        let runtime = self.runtime.clone();
+                        let is_killing_clone = is_killing_processes.clone();
+                        std::thread::spawn(move || {
+                            std::thread::sleep(std::time::Duration::from_millis(500));
+                            // The menu may be seconds old by now, so the monitor looks at the port again
+                            match runtime.block_on(monitor.kill_port(port)) {
+                                Ok(process_info) => info!("Killed {} (PID {}) on port {}", process_info.name, process_info.pid, port),
+                                Err(PortKillError::NoProcess(_)) => info!("Port {} is already free", port),
+                                Err(e @ PortKillError::Refused { .. }) => warn!("{}", e),
+                                Err(e) => error!("Failed to kill the process on port {}: {}", port, e),
+                            }
+                            is_killing_clone.store(false, Ordering::Relaxed);
+                        });
+                    }
+                    MenuAction::KillComposeProject(project) => {
+                        info!("Kill selected for Compose project {}", project);
+                        is_killing_processes.store(true, Ordering::Relaxed);
+
+                        let monitor = self.monitor.clone();
+                        let runtime = self.runtime.clone();
+                        let is_killing_clone = is_killing_processes.clone();
+                        std::thread::spawn(move || {
+                            match runtime.block_on(monitor.kill_compose_project(&project)) {
+                                Ok(results) if results.is_empty() => warn!("No running containers in Compose project {}", project),
+                                Ok(results) => {
+                                    for (container_name, result) in &results {
+                                        if let Err(e) = result {
+                                            error!("Failed to stop container {}: {}", container_name, e);
+                                        }
+                                    }
+                                    let stopped = results.iter().filter(|(_, result)| result.is_ok()).count();
+                                    info!("Stopped {} of {} containers of Compose project {}", stopped, results.len(), project);
+                                }
+                                Err(e) => error!("Failed to stop Compose project {}: {}", project, e),
+                            }
+                            is_killing_clone.store(false, Ordering::Relaxed);
+                        });
+                    }
+                    MenuAction::Quit => {
+                        info!("Quit selected, exiting...");
+                        // Remove the icon from the status bar now rather than when the loop unwinds
+                        tray_icon.borrow_mut().take();
+                        elwt.exit();
+                        return;
+                    }
+                    MenuAction::AwaitingConfirmation => {}
+                    MenuAction::KillDisabled => {
+                        warn!("Watch-only mode, ignoring kill request from {:?}", event.id);
+                    }
+                    MenuAction::Ignore => {
+                        warn!("Ignoring menu event with unknown or stale id: {:?}", event.id);
                     }
-                });
+                }
             }
             
-            // Check for processes every 5 seconds (less frequent to avoid crashes)
-            if last_check.elapsed() >= std::time::Duration::from_secs(5) {
-                last_check = std::time::Instant::now();
+            // Check for processes on every scan interval
+            if last_check.is_none_or(|checked| checked.elapsed() >= scan_interval) {
+                last_check = Some(Instant::now());
                 
-                // Get detailed process information
-                let (process_count, processes) = Self::get_processes_on_ports(&self.args.get_ports_to_monitor());
-                let status_info = StatusBarInfo::from_process_count(process_count);
+                // A failed scan keeps showing what the last one found
+                let mut processes = match self.runtime.block_on(self.monitor.scan_once()) {
+                    Ok(report) => {
+                        debug!("Scanned {} ports with {} in {:?}", report.scanned_ports, report.backend, report.elapsed);
+                        report.processes
+                    }
+                    Err(e) => {
+                        error!("Failed to scan processes: {}", e);
+                        current_processes.clone()
+                    }
+                };
+                let process_count = processes.len();
+                if self.args.docker {
+                    Self::add_compose_projects(&mut processes, &self.container_cli);
+                }
+                let update = ProcessUpdate::diff(&current_processes, processes.clone());
+                let status_info = StatusBarInfo::from_update(&update).with_free_reserved(&update.free_ports(&self.args.reserve));
+                notifier.notify(&update);
+                notifier.end_scan();
                 println!("🔄 Port Status: {} - {}", status_info.text, status_info.tooltip);
                 
                 // Print detected processes
@@ -139,15 +343,16 @@ impl PortKillApp {
                 }
                 
                 // Update tooltip, icon, and menu with better error handling
-                if let Ok(tray_icon_guard) = tray_icon.lock() {
-                    if let Some(ref icon) = *tray_icon_guard {
-                        // Update tooltip
-                        if let Err(e) = icon.set_tooltip(Some(&status_info.tooltip)) {
+                {
+                    if let Some(ref icon) = *tray_icon.borrow() {
+                        // Update tooltip, listing where each process runs from
+                        let tooltip = status_info.clone().with_process_context(&processes).tooltip;
+                        if let Err(e) = icon.set_tooltip(Some(&tooltip)) {
                             error!("Failed to update tooltip: {}", e);
                         }
                         
                         // Update icon with new status
-                        if let Ok(new_icon) = TrayMenu::create_icon(&status_info.text) {
+                        if let Ok(new_icon) = TrayMenu::create_icon(&status_info, &self.tray_menu.icon_style) {
                             if let Err(e) = icon.set_icon(Some(new_icon)) {
                                 error!("Failed to update icon: {}", e);
                             }
@@ -155,12 +360,13 @@ impl PortKillApp {
                         
                         // Update menu with current processes (with cooldown to prevent crashes)
                         if !is_killing_processes.load(Ordering::Relaxed) && 
-                           process_count != last_process_count && 
-                           last_menu_update.elapsed() >= std::time::Duration::from_secs(3) {
+                           ProcessUpdate::is_change(&menu_processes, &processes) && 
+                           last_menu_update.is_none_or(|updated| updated.elapsed() >= std::time::Duration::from_secs(3)) {
                             
                             // Only update menu if we have processes to show
                             if process_count > 0 {
-                                match TrayMenu::create_menu(&processes, self.args.show_pid) {
+                                let confirming = pending_confirmation.as_ref().map(|p| &p.id);
+                                match TrayMenu::create_menu(&processes, self.args.show_pid, self.args.sort, confirming, &self.tray_menu.about) {
                                     Ok(new_menu) => {
                                         icon.set_menu(Some(Box::new(new_menu)));
                                     }
@@ -170,7 +376,7 @@ impl PortKillApp {
                                 }
                             } else {
                                 // Create empty menu when no processes
-                                match TrayMenu::create_menu(&HashMap::new(), self.args.show_pid) {
+                                match TrayMenu::create_menu(&HashMap::new(), self.args.show_pid, self.args.sort, None, &self.tray_menu.about) {
                                     Ok(empty_menu) => {
                                         icon.set_menu(Some(Box::new(empty_menu)));
                                     }
@@ -179,148 +385,71 @@ impl PortKillApp {
                                     }
                                 }
                             }
-                            last_process_count = process_count;
-                            last_menu_update = std::time::Instant::now();
+                            menu_processes = processes.clone();
+                            last_menu_update = Some(Instant::now());
                         }
                     }
                 }
+                
+                current_processes = processes;
             }
         })?;
 
         Ok(())
     }
 
-    fn get_processes_on_ports(ports: &[u16]) -> (usize, HashMap<u16, crate::types::ProcessInfo>) {
-        // Build port range string for lsof
-        let port_range = if ports.len() <= 10 {
-            // For small number of ports, list them individually
-            ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",")
-        } else {
-            // For large ranges, use range format
-            format!("{}-{}", ports.first().unwrap_or(&0), ports.last().unwrap_or(&0))
-        };
-        
-        // Use lsof to get detailed process information
-        let output = std::process::Command::new("lsof")
-            .args(&["-i", &format!(":{}", port_range), "-sTCP:LISTEN", "-P", "-n"])
-            .output();
-            
-        match output {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let mut processes = HashMap::new();
-                
-                for line in stdout.lines().skip(1) { // Skip header
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 9 {
-                        if let (Ok(pid), Ok(port)) = (parts[1].parse::<i32>(), parts[8].split(':').last().unwrap_or("0").parse::<u16>()) {
-                            let command = parts[0].to_string();
-                            let name = parts[0].to_string();
-                            
-                            processes.insert(port, crate::types::ProcessInfo {
-                                pid,
-                                port,
-                                command,
-                                name,
-                                container_id: None,
-                                container_name: None,
-                            });
-                        }
-                    }
-                }
-                
-                (processes.len(), processes)
+    fn rebuild_menu(
+        tray_icon: &Rc<RefCell<Option<TrayIcon>>>,
+        processes: &HashMap<Endpoint, ProcessInfo>,
+        show_pid: bool,
+        sort: SortKey,
+        confirming: Option<&MenuId>,
+        about: &AboutInfo,
+    ) {
+        if let Some(ref icon) = *tray_icon.borrow() {
+            match TrayMenu::create_menu(processes, show_pid, sort, confirming, about) {
+                Ok(new_menu) => icon.set_menu(Some(Box::new(new_menu))),
+                Err(e) => error!("Failed to create menu: {}", e),
             }
-            Err(_) => (0, HashMap::new())
         }
     }
 
-    fn kill_all_processes(ports: &[u16]) -> Result<()> {
-        // Build port range string for lsof
-        let port_range = if ports.len() <= 10 {
-            // For small number of ports, list them individually
-            ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",")
+    /// Best-effort check for whether a GUI session is available to host the tray icon.
+    pub fn is_headless() -> bool {
+        let env_set = |name: &str| std::env::var(name).map(|v| !v.is_empty()).unwrap_or(false);
+
+        if cfg!(target_os = "macos") {
+            // Over SSH there is no WindowServer session to attach the status item to
+            env_set("SSH_CONNECTION") || env_set("SSH_TTY")
+        } else if cfg!(target_os = "linux") {
+            !env_set("DISPLAY") && !env_set("WAYLAND_DISPLAY")
         } else {
-            // For large ranges, use range format
-            format!("{}-{}", ports.first().unwrap_or(&0), ports.last().unwrap_or(&0))
-        };
-        
-        info!("Killing all processes on ports {}...", port_range);
-        
-        // Get all PIDs on the monitored ports
-        let output = match std::process::Command::new("lsof")
-            .args(&["-ti", &format!(":{}", port_range), "-sTCP:LISTEN"])
-            .output() {
-            Ok(output) => output,
-            Err(e) => {
-                error!("Failed to run lsof command: {}", e);
-                return Err(anyhow::anyhow!("Failed to run lsof: {}", e));
-            }
-        };
-            
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let pids: Vec<&str> = stdout.lines().filter(|line| !line.trim().is_empty()).collect();
-        
-        if pids.is_empty() {
-            info!("No processes found to kill");
-            return Ok(());
+            false
         }
-        
-        info!("Found {} processes to kill", pids.len());
-        
-        for pid_str in pids {
-            if let Ok(pid) = pid_str.parse::<i32>() {
-                info!("Attempting to kill process PID: {}", pid);
-                match Self::kill_process(pid) {
-                    Ok(_) => info!("Successfully killed process PID: {}", pid),
-                    Err(e) => error!("Failed to kill process {}: {}", pid, e),
-                }
-            }
-        }
-        
-        info!("Finished killing all processes");
-        Ok(())
     }
 
-    fn kill_process(pid: i32) -> Result<()> {
-        use nix::sys::signal::{kill, Signal};
-        use nix::unistd::Pid;
-        
-        info!("Killing process PID: {} with SIGTERM", pid);
-        
-        // First try SIGTERM (graceful termination)
-        match kill(Pid::from_raw(pid), Signal::SIGTERM) {
-            Ok(_) => info!("SIGTERM sent to PID: {}", pid),
-            Err(e) => {
-                error!("Failed to send SIGTERM to PID {}: {}", pid, e);
-                return Err(anyhow::anyhow!("Failed to send SIGTERM: {}", e));
-            }
-        }
-        
-        // Wait a bit for graceful termination
-        std::thread::sleep(std::time::Duration::from_millis(500));
-        
-        // Check if process is still running
-        let still_running = std::process::Command::new("ps")
-            .args(&["-p", &pid.to_string()])
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false);
-            
-        if still_running {
-            // Process still running, send SIGKILL
-            info!("Process {} still running, sending SIGKILL", pid);
-            match kill(Pid::from_raw(pid), Signal::SIGKILL) {
-                Ok(_) => info!("SIGKILL sent to PID: {}", pid),
-                Err(e) => {
-                    error!("Failed to send SIGKILL to PID {}: {}", pid, e);
-                    return Err(anyhow::anyhow!("Failed to send SIGKILL: {}", e));
+    /// Label processes with the Compose project publishing their port, for those the scan's
+    /// container lookup by PID didn't place, e.g. with Docker Desktop or `--docker-lazy`.
+    fn add_compose_projects(processes: &mut HashMap<Endpoint, ProcessInfo>, container_cli: &ContainerCli) {
+        match compose::published_ports(container_cli) {
+            Ok(projects) => {
+                for (endpoint, process_info) in processes.iter_mut() {
+                    if process_info.compose_project.is_none() {
+                        process_info.compose_project = projects.get(endpoint).cloned();
+                    }
                 }
             }
-        } else {
-            info!("Process {} terminated gracefully", pid);
+            Err(e) => debug!("Failed to list Compose projects: {}", e),
         }
-        
-        Ok(())
+    }
+
+    /// The scanned entry to kill `pid` by, preferring a listening socket over a connection
+    /// since the PID may hold both.
+    fn scanned_process(processes: &HashMap<Endpoint, ProcessInfo>, pid: i32) -> Option<ProcessInfo> {
+        processes
+            .values()
+            .filter(|p| p.pid == pid)
+            .max_by_key(|p| p.state == SocketState::Listen)
+            .cloned()
     }
 }
@@ -1,7 +1,29 @@
-use clap::Parser;
+use crate::discover;
+use crate::tray_menu::HexColor;
+use crate::types::{ProcessInfo, Protocol};
+use crate::config::{self, ConfigFile};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use nix::sys::signal::Signal;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
-#[derive(Parser, Debug)]
+/// Environment variable that must match `--require-token` for destructive actions to run
+pub const CONFIRM_TOKEN_ENV: &str = "PORT_KILL_CONFIRM";
+
+/// Environment variable read like `--ports` when no ports or port range are given on the
+/// command line, e.g. from a direnv `.envrc`
+pub const PORTS_ENV: &str = "PORT_KILL_PORTS";
+
+#[derive(Parser, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
 #[command(
     name = "port-kill",
     about = "A lightweight macOS status bar app that monitors and manages development processes",
@@ -9,6 +31,10 @@ use std::collections::HashSet;
     long_about = "Monitors development processes running on specified ports and allows you to kill them from the status bar."
 )]
 pub struct Args {
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
     /// Starting port for range scanning (inclusive)
     #[arg(short, long, default_value = "2000")]
     pub start_port: u16,
@@ -17,37 +43,574 @@ pub struct Args {
     #[arg(short, long, default_value = "6000")]
     pub end_port: u16,
 
-    /// Specific ports to monitor (comma-separated, overrides start/end port range)
+    /// Specific ports or inclusive ranges to monitor (comma-separated, e.g. 3000-3010,8080),
+    /// overriding the start/end port range
     #[arg(short, long, value_delimiter = ',')]
-    pub ports: Option<Vec<u16>>,
+    pub ports: Option<Vec<PortRange>>,
+
+    /// Ports to leave alone even though they fall in the range or list being monitored
+    /// (comma-separated), e.g. a database that must never be killed
+    #[arg(long, value_delimiter = ',', value_name = "PORTS")]
+    pub exclude_ports: Vec<u16>,
+
+    /// Ports that should always be in use (comma-separated), e.g. a database: alert when one
+    /// is free instead, and never kill what holds it, not even with Kill All
+    #[arg(long, value_delimiter = ',', value_name = "PORTS")]
+    pub reserve: Vec<u16>,
+
+    /// File listing ports to monitor, one per line (ranges like 3000-3010 and # comments allowed)
+    #[arg(long, value_name = "PATH")]
+    pub ports_file: Option<PathBuf>,
+
+    /// Without explicit ports, monitor the ports declared in .port-kill.toml or the Compose file in the current directory
+    #[arg(long)]
+    pub discover: bool,
+
+    /// Which protocols to look for listeners on
+    #[arg(long, value_enum, default_value_t = ProtocolSelection::Tcp)]
+    pub protocol: ProtocolSelection,
+
+    /// Only monitor sockets bound to this address, e.g. 127.0.0.1; wildcard (0.0.0.0, ::)
+    /// listeners are reachable on it too and always count
+    #[arg(long, value_name = "ADDR")]
+    pub host: Option<IpAddr>,
+
+    /// Look for TCP listeners (shorthand for --protocol tcp; with --udp, for --protocol both)
+    #[arg(long, conflicts_with = "protocol")]
+    #[serde(skip)]
+    pub tcp: bool,
+
+    /// Look for bound UDP sockets (shorthand for --protocol udp)
+    #[arg(long, conflicts_with = "protocol")]
+    #[serde(skip)]
+    pub udp: bool,
+
+    /// Look for both TCP listeners and UDP sockets (shorthand for --protocol both)
+    #[arg(long, conflicts_with_all = ["protocol", "tcp", "udp"])]
+    #[serde(skip)]
+    pub both: bool,
+
+    /// Also list processes listening on Unix sockets whose path matches this glob or starts
+    /// with this prefix, e.g. `/tmp/myapp` or `/tmp/*.sock`
+    #[arg(long, value_name = "PATH")]
+    pub unix_sockets: Option<String>,
+
+    /// Also list processes with an established TCP connection to a monitored port, such as
+    /// the clients of a dev server, tagged ESTABLISHED. They are only killed with
+    /// --kill-established
+    #[arg(long)]
+    pub include_established: bool,
+
+    /// Let kills, including killing everything, terminate the clients --include-established lists
+    #[arg(long, requires = "include_established")]
+    pub kill_established: bool,
 
     /// Run in console mode instead of status bar mode
     #[arg(short, long)]
     pub console: bool,
 
-    /// Enable verbose logging
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Fall back to console mode automatically when no display is available
+    #[arg(long)]
+    pub auto_mode: bool,
+
+    /// Increase logging verbosity (-v info, the default level; -vv debug; -vvv trace)
+    #[arg(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
 
     /// Enable Docker container monitoring (includes containers in process detection)
     #[arg(short, long)]
     pub docker: bool,
 
+    /// With --docker, look up container details only for processes being shown or killed
+    #[arg(long, requires = "docker")]
+    pub docker_lazy: bool,
+
+    /// Stop every running container whose name matches this glob or substring, then exit
+    #[arg(long, value_name = "PATTERN")]
+    pub kill_container: Option<String>,
+
+    /// Stop every running container of this Docker Compose project, then exit
+    #[arg(long, value_name = "PROJECT", conflicts_with = "kill_container")]
+    pub kill_compose_project: Option<String>,
+
+    /// Kill whatever is listening on this port, then exit
+    #[arg(long, value_name = "PORT")]
+    pub kill_port: Option<u16>,
+
+    /// With --kill-port, only kill if the process name is exactly this, or matches it as a glob
+    #[arg(long, value_name = "NAME", requires = "kill_port")]
+    pub require_name: Option<String>,
+
+    /// With --kill-port, relaunch the killed process with its original command line and
+    /// working directory once it has exited
+    #[arg(long, requires = "kill_port")]
+    pub restart: bool,
+
+    /// Docker context to run container lookups against (passed as `docker --context`, or
+    /// `podman --connection` with Podman)
+    #[arg(long, value_name = "NAME")]
+    pub docker_context: Option<String>,
+
+    /// Container CLI used for container lookups and stops; auto uses docker or podman,
+    /// whichever is installed
+    #[arg(long, value_enum, value_name = "RUNTIME", default_value_t = ContainerRuntime::Auto)]
+    pub container_runtime: ContainerRuntime,
+
     /// Show process IDs (PIDs) in the display output
     #[arg(short = 'P', long)]
     pub show_pid: bool,
+
+    /// Order of the console list and tray menu (age lists the longest-running first)
+    #[arg(long, value_enum, default_value_t = SortKey::Port)]
+    pub sort: SortKey,
+
+    /// Tray icon color when no processes are found, as hex RRGGBB
+    #[arg(long, value_name = "RRGGBB", default_value = "00FF00")]
+    pub idle_color: HexColor,
+
+    /// Tray icon color when processes are found, below --warn-count
+    #[arg(long, value_name = "RRGGBB", default_value = "FF0000")]
+    pub busy_color: HexColor,
+
+    /// Tray icon color once --warn-count or more processes are found
+    #[arg(long, value_name = "RRGGBB", default_value = "FFA500")]
+    pub warn_color: HexColor,
+
+    /// How many processes switch the tray icon to --warn-color and --table rows to red
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub warn_count: usize,
+
+    /// Seconds between scans, fractions allowed (default: 2 in console mode, 5 in the tray)
+    #[arg(long, value_name = "SECONDS")]
+    pub interval: Option<f64>,
+
+    /// Require a second click within this many seconds before a tray kill goes through
+    #[arg(long, value_name = "SECONDS")]
+    pub confirm_window: Option<u64>,
+
+    /// Only show and kill processes whose name or command matches one of these (substring or glob)
+    #[arg(long, value_name = "PATTERN", value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// Never show or kill processes whose name or command matches one of these; applied after --only
+    #[arg(long, value_name = "PATTERN", value_delimiter = ',')]
+    pub ignore: Vec<String>,
+
+    /// Only show and kill processes whose name or command matches this regular expression, e.g. `^node$`
+    #[arg(long, value_name = "REGEX")]
+    pub name_regex: Option<String>,
+
+    /// Never show or kill processes whose name or command matches this regular expression
+    #[arg(long, value_name = "REGEX")]
+    pub ignore_regex: Option<String>,
+
+    /// Only show and kill processes whose full command line contains this text, e.g.
+    /// `manage.py runserver` (repeatable; any one must match)
+    #[arg(long, value_name = "STR")]
+    pub cmd_contains: Vec<String>,
+
+    /// Never show or kill processes whose full command line contains this text (repeatable)
+    #[arg(long, value_name = "STR")]
+    pub cmd_ignore: Vec<String>,
+
+    /// Hide processes owned by root instead of listing them as protected
+    #[arg(long, conflicts_with = "include_system")]
+    pub ignore_root: bool,
+
+    /// Allow killing processes owned by root, which are otherwise listed but never killed
+    #[arg(long)]
+    pub include_system: bool,
+
+    /// Show known-noisy system processes (rapportd, ControlCenter, ...) instead of hiding them
+    #[arg(long)]
+    pub no_default_ignores: bool,
+
+    /// Also list monitored ports that are free (console mode only)
+    #[arg(long)]
+    pub show_free: bool,
+
+    /// Show the monitored ports as a grid refreshed in place, `#` occupied and `.` free (console mode only)
+    #[arg(long, conflicts_with = "json")]
+    pub heatmap: bool,
+
+    /// List processes in an aligned table, colored when stdout is a terminal and NO_COLOR is
+    /// unset (console mode only)
+    #[arg(long, conflicts_with_all = ["json", "heatmap"])]
+    pub table: bool,
+
+    /// Print each update as a JSON line on stdout and send human-readable output to stderr (implies --console)
+    #[arg(long)]
+    pub json: bool,
+
+    /// How updates are printed on stdout: csv and tsv print a header row, then a row per
+    /// process on each update; like --json, anything else goes to stderr (implies --console
+    /// unless text)
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Only print an update when the processes differ from the last printed ones, also for
+    /// --json (console mode only)
+    #[arg(long)]
+    pub quiet: bool,
+
+    /// Write a full JSON snapshot of the detected processes every N scans (console mode only)
+    #[arg(long, value_name = "N", requires = "output_dir")]
+    pub snapshot_every: Option<u32>,
+
+    /// Directory that --snapshot-every writes snapshot-<timestamp>.json files into
+    #[arg(long, value_name = "PATH", requires = "snapshot_every")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Serve a length-prefixed JSON message stream on this Unix socket (console mode only)
+    #[arg(long, value_name = "PATH")]
+    pub socket: Option<PathBuf>,
+
+    /// Serve GET /processes and POST /kill/{pid} over HTTP on this address, e.g. 127.0.0.1:7878
+    /// (console mode only). With --require-token, kills must send the token in an
+    /// X-Port-Kill-Confirm header
+    #[arg(long, value_name = "ADDR:PORT")]
+    pub serve: Option<SocketAddr>,
+
+    /// Serve Prometheus metrics as GET /metrics on this address, e.g. 127.0.0.1:9184 (console mode only)
+    #[arg(long, value_name = "ADDR:PORT")]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Automatically kill processes as soon as they are detected (console mode only)
+    #[arg(long)]
+    pub auto_kill: bool,
+
+    /// Signal sent first when killing a process, by name (TERM, INT, HUP, KILL, ...) or number
+    #[arg(long, value_name = "NAME", default_value = "TERM")]
+    pub signal: String,
+
+    /// Milliseconds a process gets to exit after --signal before it is sent SIGKILL
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub kill_timeout: u64,
+
+    /// Also kill every descendant of a killed process, for dev servers whose workers keep
+    /// holding the port after the parent exits
+    #[arg(long)]
+    pub kill_tree: bool,
+
+    /// Resend a signal up to this many times, with exponential backoff, when sending it fails
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub kill_retries: u32,
+
+    /// Append a JSON line describing every kill (time, port, PID, signal, outcome) to this file
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Never escalate to SIGKILL; report processes that outlive --kill-timeout as failed kills
+    #[arg(long)]
+    pub no_sigkill: bool,
+
+    /// Skip checking, just before each kill, that the process still holds the port it was
+    /// found on. Saves a scan per kill, but a PID reused since the scan could be killed instead
+    #[arg(long)]
+    pub no_verify: bool,
+
+    /// Show a desktop notification when a process starts listening on a monitored port
+    #[arg(long)]
+    pub notify: bool,
+
+    /// Only observe: disable every way of killing processes or stopping containers, for
+    /// shared machines where killing is forbidden
+    #[arg(long)]
+    pub watch_only: bool,
+
+    /// Report which processes would be killed without sending any signal or stopping any container
+    #[arg(long, conflicts_with = "strict")]
+    pub dry_run: bool,
+
+    /// Stop a kill-all at the first process that fails to die instead of attempting the rest
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Kill whatever holds the monitored ports and exit (0 if everything found was killed or
+    /// nothing was found, 2 if some kills failed, 1 on other errors)
+    #[arg(long, conflicts_with_all = ["once", "auto_kill"])]
+    pub kill: bool,
+
+    /// Never list or kill these PIDs (comma-separated). port-kill itself and the shell that
+    /// started it are always left alone
+    #[arg(long, value_delimiter = ',', value_name = "PIDS")]
+    pub exclude_pids: Vec<i32>,
+
+    /// With --kill, kill these PIDs (comma-separated) instead of scanning the monitored ports
+    #[arg(long, value_delimiter = ',', value_name = "PIDS", requires = "kill")]
+    pub pids: Option<Vec<i32>>,
+
+    /// Kill all without first listing the processes and asking `[y/N]` (console mode only)
+    #[arg(short = 'y', long, visible_alias = "force")]
+    pub yes: bool,
+
+    /// Scan a single time and exit instead of monitoring continuously (console mode only)
+    #[arg(long)]
+    pub once: bool,
+
+    /// With --auto-kill --once, re-scan after killing and fail if any monitored port is still occupied
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Block until this port is free, then exit (non-zero if --timeout elapses first)
+    #[arg(long, value_name = "PORT")]
+    pub wait_for_free: Option<u16>,
+
+    /// Block until something is listening on this port, then exit (non-zero if --timeout elapses first)
+    #[arg(long, value_name = "PORT", conflicts_with = "wait_for_free")]
+    pub wait_for_listen: Option<u16>,
+
+    /// Give up waiting after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+
+    /// Refuse destructive actions unless the PORT_KILL_CONFIRM environment variable matches this value
+    #[arg(long, value_name = "VALUE")]
+    #[serde(skip)]
+    pub require_token: Option<String>,
+
+    /// Read defaults from this TOML file instead of ~/.config/port-kill/config.toml; flags
+    /// given on the command line override it
+    #[arg(long, value_name = "PATH")]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+
+    /// Start port-kill at login in console mode with the other flags given here, as a launchd
+    /// agent on macOS or a systemd user unit on Linux, then exit
+    #[arg(long, conflicts_with = "uninstall_service")]
+    #[serde(skip)]
+    pub install_service: bool,
+
+    /// Stop and remove the service --install-service set up, then exit
+    #[arg(long)]
+    #[serde(skip)]
+    pub uninstall_service: bool,
+
+    /// Print the effective settings after merging every source, then exit without scanning
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "toml")]
+    #[serde(skip)]
+    pub print_config: Option<ConfigFormat>,
+}
+
+/// Output format for `--print-config`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    Docker,
+    Podman,
+    Auto,
+}
+
+/// How console updates are printed (`--format`).
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// The same JSON lines as `--json`
+    Json,
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    /// The field separator of the delimited formats.
+    pub fn delimiter(self) -> Option<char> {
+        match self {
+            OutputFormat::Csv => Some(','),
+            OutputFormat::Tsv => Some('\t'),
+            OutputFormat::Text | OutputFormat::Json => None,
+        }
+    }
+}
+
+/// The order processes are listed in (`--sort`). Ties, such as one PID holding several
+/// ports, fall back to port order.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Port,
+    Pid,
+    Name,
+    /// Oldest first; processes whose start is unknown come last
+    Age,
+}
+
+impl SortKey {
+    /// `processes` in this order.
+    pub fn sorted<'a>(self, processes: impl IntoIterator<Item = &'a ProcessInfo>) -> Vec<&'a ProcessInfo> {
+        let mut processes: Vec<&ProcessInfo> = processes.into_iter().collect();
+        match self {
+            SortKey::Port => processes.sort_by_key(|p| p.endpoint()),
+            SortKey::Pid => processes.sort_by_key(|p| (p.pid, p.endpoint())),
+            SortKey::Name => processes.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.endpoint().cmp(&b.endpoint()))),
+            SortKey::Age => processes.sort_by_key(|p| (p.started_at.is_none(), p.started_at, p.endpoint())),
+        }
+        processes
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtocolSelection {
+    Tcp,
+    Udp,
+    Both,
+}
+
+impl ProtocolSelection {
+    pub fn protocols(self) -> &'static [Protocol] {
+        match self {
+            ProtocolSelection::Tcp => &[Protocol::Tcp],
+            ProtocolSelection::Udp => &[Protocol::Udp],
+            ProtocolSelection::Both => &[Protocol::Tcp, Protocol::Udp],
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Show what is listening on a single port
+    WhatsOn {
+        /// Port to inspect
+        port: u16,
+
+        /// Print the shell commands that would free the port, without running them
+        #[arg(long)]
+        suggest: bool,
+    },
 }
 
 impl Args {
-    /// Get the list of ports to monitor
+    /// Parse the command line over the defaults in the config file. Like [`Parser::parse`],
+    /// exits with a usage message if the command line itself is invalid.
+    pub fn parse_with_config() -> Result<Self, String> {
+        Self::parse_from_with_config(std::env::args_os(), config::default_path())
+    }
+
+    /// Parse `argv` over the config file named by `--config`, or `default_config` if that
+    /// exists. See [`crate::config`] for the precedence rules.
+    pub fn parse_from_with_config<I, T>(argv: I, default_config: Option<PathBuf>) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command().get_matches_from(argv);
+        let mut args = Self::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+
+        if let Some(path) = args.config.clone().or(default_config.filter(|path| path.is_file())) {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+            let config = ConfigFile::parse(&content)
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e.trim()))?;
+            config.apply_to(&mut args, &matches);
+        }
+        args.apply_ports_env(&matches, std::env::var(PORTS_ENV).ok())?;
+        Ok(args)
+    }
+
+    /// Take the ports from `value`, the contents of [`PORTS_ENV`], unless ports or a port
+    /// range were given on the command line. The variable beats the config file, as it is
+    /// usually set per project while the file holds personal defaults.
+    fn apply_ports_env(&mut self, matches: &ArgMatches, value: Option<String>) -> Result<(), String> {
+        let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if ["ports", "start_port", "end_port"].into_iter().any(from_command_line) {
+            return Ok(());
+        }
+        let Some(value) = value.filter(|value| !value.trim().is_empty()) else {
+            return Ok(());
+        };
+
+        let ports = value
+            .split(',')
+            .map(str::parse::<PortRange>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid {}: {}", PORTS_ENV, e))?;
+        self.ports = Some(ports);
+        Ok(())
+    }
+
+    /// Merge port sources that need I/O into the parsed arguments. Call once after parsing.
+    pub fn resolve(&mut self) -> Result<(), String> {
+        self.protocol = match (self.tcp, self.udp, self.both) {
+            (true, true, _) | (_, _, true) => ProtocolSelection::Both,
+            (false, true, _) => ProtocolSelection::Udp,
+            (true, false, _) => ProtocolSelection::Tcp,
+            (false, false, false) => self.protocol,
+        };
+
+        if let Some(ref path) = self.ports_file {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read ports file {}: {}", path.display(), e))?;
+            let file_ports = parse_ports_file(&content)
+                .map_err(|e| format!("Invalid ports file {}: {}", path.display(), e))?;
+
+            // Ports from the file are unioned with any given via --ports
+            self.ports.get_or_insert_with(Vec::new).extend(file_ports.into_iter().map(PortRange::from));
+        }
+
+        // Explicit ports always win over discovery
+        if self.discover && self.ports.is_none() {
+            let dir = std::env::current_dir().map_err(|e| format!("Failed to read current directory: {}", e))?;
+            let discovered = discover::discover_ports(&dir)?;
+            if discovered.is_empty() {
+                return Err(format!(
+                    "--discover found no ports in {} (looked for {} and {})",
+                    dir.display(),
+                    discover::PROJECT_CONFIG_FILE,
+                    discover::COMPOSE_FILES.join(", ")
+                ));
+            }
+            self.ports = Some(discovered.into_iter().map(PortRange::from).collect());
+        }
+
+        Ok(())
+    }
+
+    /// The settings in effect once [`Self::resolve`] has merged every source, for
+    /// `--print-config`. Keys are the long flag names; unset options are left out, and the
+    /// `--require-token` secret is never printed.
+    pub fn render_config(&self, format: ConfigFormat) -> Result<String, String> {
+        match format {
+            ConfigFormat::Toml => toml::to_string(self).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Get the list of ports to monitor: `--ports` (or [`PORTS_ENV`], which fills it in when
+    /// the flag isn't given), else the start/end port range, minus `--exclude-ports`, plus
+    /// any `--reserve` ports outside of those
     pub fn get_ports_to_monitor(&self) -> Vec<u16> {
-        if let Some(ref specific_ports) = self.ports {
+        let ports: Vec<u16> = if let Some(specific_ports) = self.specific_ports() {
             // Use specific ports if provided
-            specific_ports.clone()
+            specific_ports
         } else {
             // Use port range
             (self.start_port..=self.end_port).collect()
+        };
+        let mut ports: Vec<u16> = ports.into_iter().filter(|port| !self.exclude_ports.contains(port)).collect();
+        if !self.reserve.is_empty() {
+            ports.extend(&self.reserve);
+            ports.sort_unstable();
+            ports.dedup();
         }
+        ports
+    }
+
+    /// The ports `--ports` names with every range expanded, sorted and deduplicated, or
+    /// `None` when the start/end port range applies.
+    fn specific_ports(&self) -> Option<Vec<u16>> {
+        let mut ports: Vec<u16> = self.ports.as_ref()?.iter().flat_map(PortRange::ports).collect();
+        ports.sort_unstable();
+        ports.dedup();
+        Some(ports)
     }
 
     /// Get a HashSet of ports for efficient lookup
@@ -57,11 +620,104 @@ impl Args {
 
     /// Get a description of the port configuration
     pub fn get_port_description(&self) -> String {
-        if let Some(ref specific_ports) = self.ports {
-            format!("specific ports: {}", specific_ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", "))
+        let description = if let Some(specific_ports) = self.specific_ports() {
+            format!("specific ports: {}", format_port_ranges(&specific_ports))
         } else {
             format!("port range: {}-{}", self.start_port, self.end_port)
+        };
+        let description = if self.exclude_ports.is_empty() {
+            description
+        } else {
+            format!("{} excluding {}", description, format_port_ranges(&self.exclude_ports))
+        };
+        if self.reserve.is_empty() {
+            description
+        } else {
+            format!("{}, reserving {}", description, format_port_ranges(&self.reserve))
+        }
+    }
+
+    /// The `--interval` between scans, if one was given
+    pub fn scan_interval(&self) -> Option<Duration> {
+        self.interval.map(Duration::from_secs_f64)
+    }
+
+    /// The `--signal` to send first when killing a process
+    pub fn kill_signal(&self) -> Result<Signal, String> {
+        parse_signal(&self.signal)
+    }
+
+    /// `--name-regex`, compiled
+    pub fn name_regex(&self) -> Result<Option<Regex>, String> {
+        compile_regex("--name-regex", self.name_regex.as_deref())
+    }
+
+    /// `--ignore-regex`, compiled
+    pub fn ignore_regex(&self) -> Result<Option<Regex>, String> {
+        compile_regex("--ignore-regex", self.ignore_regex.as_deref())
+    }
+
+    /// Log level selected by the number of `-v` flags, if any were given
+    pub fn log_level(&self) -> Option<&'static str> {
+        match self.verbose {
+            0 => None,
+            1 => Some("info"),
+            2 => Some("debug"),
+            _ => Some("trace"),
+        }
+    }
+
+    /// The `--format` updates are printed in, where `--json` means [`OutputFormat::Json`]
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
+
+    /// Whether stdout carries machine-readable updates, so everything else goes to stderr
+    pub fn is_machine_output(&self) -> bool {
+        self.output_format() != OutputFormat::Text
+    }
+
+    /// Whether these arguments will kill processes without a user clicking anything
+    pub fn is_destructive(&self) -> bool {
+        self.auto_kill
+            || self.kill
+            || self.kill_container.is_some()
+            || self.kill_compose_project.is_some()
+            || self.kill_port.is_some()
+    }
+
+    /// Whether these arguments select a console-only mode or one-shot action
+    pub fn wants_console(&self) -> bool {
+        self.console
+            || self.is_machine_output()
+            || self.once
+            || self.kill
+            || self.command.is_some()
+            || self.kill_container.is_some()
+            || self.kill_compose_project.is_some()
+            || self.kill_port.is_some()
+            || self.wait_for_free.is_some()
+            || self.wait_for_listen.is_some()
+    }
+
+    /// Check the `--require-token` guardrail against the token provided via the environment
+    pub fn check_confirmation_token(&self, provided: Option<&str>) -> Result<(), String> {
+        let Some(ref required) = self.require_token else {
+            return Ok(());
+        };
+
+        if !self.is_destructive() || provided == Some(required.as_str()) {
+            return Ok(());
         }
+
+        Err(format!(
+            "Refusing to kill processes: {} does not match the value given to --require-token",
+            CONFIRM_TOKEN_ENV
+        ))
     }
 
     /// Validate the arguments
@@ -77,20 +733,241 @@ impl Args {
                 return Err("At least one port must be specified".to_string());
             }
             
-            for &port in specific_ports {
-                if port == 0 {
-                    return Err("Port 0 is not valid".to_string());
-                }
+            for range in specific_ports {
+                range.check()?;
+            }
+        }
+
+        if !self.exclude_ports.is_empty() && self.get_ports_to_monitor().is_empty() {
+            return Err("--exclude-ports excludes every monitored port".to_string());
+        }
+
+        if let Some(port) = self.kill_port.filter(|port| self.exclude_ports.contains(port)) {
+            return Err(format!("Port {} is excluded by --exclude-ports", port));
+        }
+
+        if self.reserve.contains(&0) {
+            return Err("Port 0 is not valid".to_string());
+        }
+
+        if let Some(port) = self.reserve.iter().find(|port| self.exclude_ports.contains(port)) {
+            return Err(format!("Port {} cannot be both reserved and excluded", port));
+        }
+
+        if self.json && !matches!(self.format, OutputFormat::Text | OutputFormat::Json) {
+            return Err("--json cannot be combined with another --format".to_string());
+        }
+
+        if self.is_machine_output() && (self.table || self.heatmap) {
+            return Err("--table and --heatmap only apply to --format text".to_string());
+        }
+
+        if let Some(interval) = self.interval {
+            if interval <= 0.0 || Duration::try_from_secs_f64(interval).is_err() {
+                return Err("--interval must be a positive number of seconds".to_string());
             }
         }
 
+        self.kill_signal()?;
+        self.name_regex()?;
+        self.ignore_regex()?;
+
+        if self.confirm_window == Some(0) {
+            return Err("Confirmation window must be at least 1 second".to_string());
+        }
+
+        if self.snapshot_every == Some(0) {
+            return Err("--snapshot-every must be at least 1".to_string());
+        }
+
+        if self.watch_only && self.is_destructive() {
+            return Err("--watch-only cannot be combined with options that kill processes".to_string());
+        }
+
+        if self.strict && !(self.auto_kill && self.once) {
+            return Err("--strict requires --auto-kill and --once".to_string());
+        }
+
+        if let Some(&pid) = self.pids.iter().flatten().find(|&&pid| pid <= 0) {
+            return Err(format!("PID {} is not valid", pid));
+        }
+
+        if [self.kill_port, self.wait_for_free, self.wait_for_listen].contains(&Some(0)) {
+            return Err("Port 0 is not valid".to_string());
+        }
+
+        if self.timeout.is_some() && self.wait_for_free.is_none() && self.wait_for_listen.is_none() {
+            return Err("--timeout requires --wait-for-free or --wait-for-listen".to_string());
+        }
+
         Ok(())
     }
 }
 
+/// One `--ports` entry: a single port (`3000`) or an inclusive range (`3000-3010`). Only the
+/// syntax is checked when parsing, [`Args::validate`] rejects port 0 and reversed ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "PortRangeValue", into = "PortRangeValue")]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    /// Reject port 0 and ranges whose start is past their end.
+    pub fn check(&self) -> Result<(), String> {
+        if self.start == 0 {
+            return Err("Port 0 is not valid".to_string());
+        }
+        if self.start > self.end {
+            return Err(format!("Range start {} is greater than end {}", self.start, self.end));
+        }
+        Ok(())
+    }
+
+    /// Every port in the range, in order.
+    pub fn ports(&self) -> std::ops::RangeInclusive<u16> {
+        self.start..=self.end
+    }
+}
+
+impl From<u16> for PortRange {
+    fn from(port: u16) -> Self {
+        Self { start: port, end: port }
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |s: &str| s.trim().parse::<u16>().map_err(|_| format!("'{}' is not a valid port", s.trim()));
+        match s.split_once('-') {
+            Some((start, end)) => Ok(Self { start: parse(start)?, end: parse(end)? }),
+            None => Ok(Self::from(parse(s)?)),
+        }
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+/// How a [`PortRange`] is written in config files: single ports stay plain numbers, so
+/// `ports = [3000, "8000-8010"]` reads naturally.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PortRangeValue {
+    Port(u16),
+    Range(String),
+}
+
+impl TryFrom<PortRangeValue> for PortRange {
+    type Error = String;
+
+    fn try_from(value: PortRangeValue) -> Result<Self, Self::Error> {
+        match value {
+            PortRangeValue::Port(port) => Ok(Self::from(port)),
+            PortRangeValue::Range(range) => range.parse(),
+        }
+    }
+}
+
+impl From<PortRange> for PortRangeValue {
+    fn from(range: PortRange) -> Self {
+        if range.start == range.end {
+            PortRangeValue::Port(range.start)
+        } else {
+            PortRangeValue::Range(range.to_string())
+        }
+    }
+}
+
+/// Parse a single port (`3000`) or inclusive range (`3000-3010`) into its ports, rejecting
+/// what [`PortRange::check`] does.
+pub fn parse_port_spec(spec: &str) -> Result<Vec<u16>, String> {
+    let range: PortRange = spec.parse()?;
+    range.check()?;
+    Ok(range.ports().collect())
+}
+
+/// Compile the pattern given to `flag`, if any.
+fn compile_regex(flag: &str, pattern: Option<&str>) -> Result<Option<Regex>, String> {
+    pattern
+        .map(|pattern| Regex::new(pattern).map_err(|e| format!("Invalid {} '{}': {}", flag, pattern, e)))
+        .transpose()
+}
+
+/// Parse a signal given as a name with or without the `SIG` prefix, in any case, or as a number.
+pub fn parse_signal(name: &str) -> Result<Signal, String> {
+    let name = name.trim();
+    let signal = match name.parse::<i32>() {
+        Ok(number) => Signal::try_from(number).ok(),
+        Err(_) => {
+            let upper = name.to_ascii_uppercase();
+            let full = if upper.starts_with("SIG") { upper } else { format!("SIG{}", upper) };
+            full.parse::<Signal>().ok()
+        }
+    };
+    signal.ok_or_else(|| format!("Unknown signal '{}', expected a name such as TERM, INT, HUP or KILL", name))
+}
+
+/// Parse the contents of a ports file: one port or range per line, `#` starts a comment.
+pub fn parse_ports_file(content: &str) -> Result<Vec<u16>, String> {
+    let mut ports = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let parsed = parse_port_spec(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        ports.extend(parsed);
+    }
+    Ok(ports)
+}
+
+/// Format ports as a sorted, deduplicated list with consecutive runs collapsed
+/// into ranges, e.g. `3000-3005, 8080, 9229`.
+pub fn format_port_ranges(ports: &[u16]) -> String {
+    let mut sorted = ports.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for port in sorted {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == port => *end = port,
+            _ => ranges.push((port, port)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::process_on;
+
+    fn default_args() -> Args {
+        Args::parse_from(["port-kill"])
+    }
 
     #[test]
     fn test_get_ports_to_monitor_range() {
@@ -99,7 +976,8 @@ mod tests {
             end_port: 3005,
             ports: None,
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         let ports = args.get_ports_to_monitor();
@@ -111,9 +989,10 @@ mod tests {
         let args = Args {
             start_port: 2000,
             end_port: 6000,
-            ports: Some(vec![3000, 8000, 8080]),
+            ports: Some(vec![PortRange::from(3000), PortRange::from(8000), PortRange::from(8080)]),
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         let ports = args.get_ports_to_monitor();
@@ -127,7 +1006,8 @@ mod tests {
             end_port: 3010,
             ports: None,
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         assert_eq!(args.get_port_description(), "port range: 3000-3010");
@@ -138,9 +1018,10 @@ mod tests {
         let args = Args {
             start_port: 2000,
             end_port: 6000,
-            ports: Some(vec![3000, 8000, 8080]),
+            ports: Some(vec![PortRange::from(3000), PortRange::from(8000), PortRange::from(8080)]),
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         assert_eq!(args.get_port_description(), "specific ports: 3000, 8000, 8080");
@@ -153,7 +1034,8 @@ mod tests {
             end_port: 3010,
             ports: None,
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         assert!(args.validate().is_ok());
@@ -166,7 +1048,8 @@ mod tests {
             end_port: 3000,
             ports: None,
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         assert!(args.validate().is_err());
@@ -179,9 +1062,290 @@ mod tests {
             end_port: 6000,
             ports: Some(vec![]),
             console: false,
-            verbose: false,
+            verbose: 0,
+            ..default_args()
         };
         
         assert!(args.validate().is_err());
     }
+
+    #[test]
+    fn test_port_ranges() {
+        let args = Args::parse_from(["port-kill", "--ports", "3000-3002,8080,3001,9000-9000"]);
+        assert_eq!(args.get_ports_to_monitor(), vec![3000, 3001, 3002, 8080, 9000]);
+        assert_eq!(args.get_port_description(), "specific ports: 3000-3002, 8080, 9000");
+        assert!(args.validate().is_ok());
+
+        let args = Args::parse_from(["port-kill", "--ports", "3010-3000"]);
+        assert_eq!(args.validate(), Err("Range start 3010 is greater than end 3000".to_string()));
+        let args = Args::parse_from(["port-kill", "--ports", "0-10"]);
+        assert_eq!(args.validate(), Err("Port 0 is not valid".to_string()));
+        assert!(Args::try_parse_from(["port-kill", "--ports", "3000-"]).is_err());
+        assert!(Args::try_parse_from(["port-kill", "--ports", "3000-70000"]).is_err());
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let process = |pid: i32, port: u16, name: &str| ProcessInfo {
+            command: name.to_string(),
+            name: name.to_string(),
+            ..process_on(port, pid)
+        };
+        let mut processes = [process(30, 8080, "api"), process(10, 5173, "vite"), process(20, 3000, "node"), process(10, 3001, "vite")];
+        (processes[0].started_at, processes[1].started_at, processes[2].started_at) = (Some(300), Some(100), Some(200));
+        let order = |key: SortKey| key.sorted(&processes).iter().map(|p| p.port).collect::<Vec<_>>();
+
+        assert_eq!(order(SortKey::Port), vec![3000, 3001, 5173, 8080]);
+        assert_eq!(order(SortKey::Pid), vec![3001, 5173, 3000, 8080]);
+        assert_eq!(order(SortKey::Name), vec![8080, 3000, 3001, 5173]);
+        assert_eq!(order(SortKey::Age), vec![5173, 3000, 8080, 3001]);
+        assert_eq!(Args::parse_from(["port-kill"]).sort, SortKey::Port);
+        assert_eq!(Args::parse_from(["port-kill", "--sort", "name"]).sort, SortKey::Name);
+    }
+
+    #[test]
+    fn test_ports_from_env() {
+        let parse = |argv: &[&str], value: &str| {
+            let matches = Args::command().get_matches_from(["port-kill"].iter().chain(argv));
+            let mut args = Args::from_arg_matches(&matches).unwrap();
+            args.apply_ports_env(&matches, Some(value.to_string())).map(|_| args)
+        };
+
+        let args = parse(&[], "3000,5173,8080-8081").unwrap();
+        assert_eq!(args.get_ports_to_monitor(), vec![3000, 5173, 8080, 8081]);
+        assert!(parse(&[], "").unwrap().ports.is_none());
+        assert_eq!(parse(&[], "3000,abc").unwrap_err(), "Invalid PORT_KILL_PORTS: 'abc' is not a valid port");
+
+        // Ports or a range on the command line win
+        assert_eq!(parse(&["--ports", "9000"], "3000").unwrap().get_ports_to_monitor(), vec![9000]);
+        let args = parse(&["--start-port", "4000", "--end-port", "4001"], "3000").unwrap();
+        assert_eq!(args.get_ports_to_monitor(), vec![4000, 4001]);
+    }
+
+    #[test]
+    fn test_validation_strict_requires_one_shot_kill() {
+        let mut args = default_args();
+        args.strict = true;
+        assert!(args.validate().is_err());
+
+        args.auto_kill = true;
+        args.once = true;
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("TERM"), Ok(Signal::SIGTERM));
+        assert_eq!(parse_signal("int"), Ok(Signal::SIGINT));
+        assert_eq!(parse_signal("SIGHUP"), Ok(Signal::SIGHUP));
+        assert_eq!(parse_signal("9"), Ok(Signal::SIGKILL));
+        assert!(parse_signal("TERMINATE").is_err());
+        assert!(parse_signal("0").is_err());
+        assert!(parse_signal("").is_err());
+
+        assert_eq!(default_args().kill_signal(), Ok(Signal::SIGTERM));
+        let args = Args::parse_from(["port-kill", "--signal", "BOGUS"]);
+        assert!(args.validate().unwrap_err().contains("Unknown signal 'BOGUS'"));
+    }
+
+    #[test]
+    fn test_validation_interval() {
+        let mut args = Args::parse_from(["port-kill", "--interval", "0.5"]);
+        assert!(args.validate().is_ok());
+        assert_eq!(args.scan_interval(), Some(Duration::from_millis(500)));
+
+        for invalid in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            args.interval = Some(invalid);
+            assert!(args.validate().is_err(), "{} should be rejected", invalid);
+        }
+
+        assert_eq!(default_args().scan_interval(), None);
+    }
+
+    #[test]
+    fn test_validation_timeout_requires_wait() {
+        let mut args = default_args();
+        args.timeout = Some(30);
+        assert!(args.validate().is_err());
+
+        args.wait_for_free = Some(3000);
+        assert!(args.validate().is_ok());
+        assert!(args.wants_console());
+
+        args.wait_for_free = None;
+        args.wait_for_listen = Some(3000);
+        assert!(args.validate().is_ok());
+        assert!(args.wants_console());
+    }
+
+    #[test]
+    fn test_kill_port_is_destructive() {
+        let mut args = default_args();
+        args.kill_port = Some(3000);
+        assert!(args.is_destructive());
+        assert!(args.wants_console());
+        assert!(args.validate().is_ok());
+
+        args.kill_port = Some(0);
+        assert!(args.validate().is_err());
+
+        let args = Args::try_parse_from(["port-kill", "--require-name", "node"]);
+        assert!(args.is_err());
+    }
+
+    #[test]
+    fn test_kill_is_a_destructive_one_shot() {
+        let args = Args::parse_from(["port-kill", "--kill", "--ports", "3000"]);
+        assert!(args.is_destructive());
+        assert!(args.wants_console());
+        assert!(args.validate().is_ok());
+
+        assert!(Args::try_parse_from(["port-kill", "--kill", "--once"]).is_err());
+        assert!(Args::try_parse_from(["port-kill", "--kill", "--auto-kill"]).is_err());
+    }
+
+    #[test]
+    fn test_kill_pids() {
+        let args = Args::parse_from(["port-kill", "--kill", "--pids", "4242,4243"]);
+        assert_eq!(args.pids, Some(vec![4242, 4243]));
+        assert!(args.validate().is_ok());
+
+        // 0 and negative PIDs would signal whole process groups
+        assert!(Args::parse_from(["port-kill", "--kill", "--pids", "0"]).validate().is_err());
+        assert!(Args::parse_from(["port-kill", "--kill", "--pids=-1"]).validate().is_err());
+        assert!(Args::try_parse_from(["port-kill", "--pids", "4242"]).is_err());
+    }
+
+    #[test]
+    fn test_exclude_ports() {
+        let args = Args::parse_from(["port-kill", "-s", "5430", "-e", "5434", "--exclude-ports", "5432,6379"]);
+        assert_eq!(args.get_ports_to_monitor(), vec![5430, 5431, 5433, 5434]);
+        assert_eq!(args.get_port_description(), "port range: 5430-5434 excluding 5432, 6379");
+        assert!(args.validate().is_ok());
+
+        let args = Args::parse_from(["port-kill", "--ports", "5432", "--exclude-ports", "5432"]);
+        assert!(args.validate().is_err());
+        let args = Args::parse_from(["port-kill", "--kill-port", "5432", "--exclude-ports", "5432"]);
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_reserve() {
+        let args = Args::parse_from(["port-kill", "--ports", "3000-3002", "--reserve", "5432,3001"]);
+        assert_eq!(args.get_ports_to_monitor(), vec![3000, 3001, 3002, 5432]);
+        assert_eq!(args.get_port_description(), "specific ports: 3000-3002, reserving 3001, 5432");
+        assert!(args.validate().is_ok());
+
+        let args = Args::parse_from(["port-kill", "--reserve", "5432", "--exclude-ports", "5432"]);
+        assert_eq!(args.validate().unwrap_err(), "Port 5432 cannot be both reserved and excluded");
+        assert!(Args::parse_from(["port-kill", "--reserve", "0"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_output_format() {
+        let args = Args::parse_from(["port-kill", "--format", "csv"]);
+        assert_eq!(args.output_format(), OutputFormat::Csv);
+        assert!(args.is_machine_output() && args.wants_console());
+        assert_eq!(Args::parse_from(["port-kill", "--json"]).output_format(), OutputFormat::Json);
+        assert!(!Args::parse_from(["port-kill"]).is_machine_output());
+
+        assert!(Args::parse_from(["port-kill", "--json", "--format", "json"]).validate().is_ok());
+        assert!(Args::parse_from(["port-kill", "--json", "--format", "tsv"]).validate().is_err());
+        assert!(Args::parse_from(["port-kill", "--format", "csv", "--table"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_watch_only_forbids_killing() {
+        assert!(Args::parse_from(["port-kill", "--watch-only"]).validate().is_ok());
+        assert!(Args::parse_from(["port-kill", "--watch-only", "--auto-kill"]).validate().is_err());
+        assert!(Args::parse_from(["port-kill", "--watch-only", "--kill-port", "3000"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_docker_lazy_requires_docker() {
+        assert!(Args::try_parse_from(["port-kill", "--docker-lazy"]).is_err());
+        assert!(Args::try_parse_from(["port-kill", "--docker", "--docker-lazy"]).is_ok());
+    }
+
+    #[test]
+    fn test_protocol_shorthands() {
+        let resolved = |flags: &[&str]| {
+            let mut args = Args::parse_from(["port-kill"].iter().chain(flags));
+            args.resolve().unwrap();
+            args.protocol
+        };
+        assert_eq!(resolved(&[]), ProtocolSelection::Tcp);
+        assert_eq!(resolved(&["--udp"]), ProtocolSelection::Udp);
+        assert_eq!(resolved(&["--tcp", "--udp"]), ProtocolSelection::Both);
+        assert_eq!(resolved(&["--both"]), ProtocolSelection::Both);
+        assert_eq!(resolved(&["--protocol", "udp"]), ProtocolSelection::Udp);
+
+        assert!(Args::try_parse_from(["port-kill", "--udp", "--protocol", "tcp"]).is_err());
+        assert!(Args::try_parse_from(["port-kill", "--both", "--tcp"]).is_err());
+    }
+
+    #[test]
+    fn test_format_port_ranges() {
+        assert_eq!(format_port_ranges(&[]), "");
+        assert_eq!(format_port_ranges(&[8080]), "8080");
+        assert_eq!(
+            format_port_ranges(&[9229, 3002, 3000, 3001, 8080, 3003, 3004, 3005, 3001]),
+            "3000-3005, 8080, 9229"
+        );
+        assert_eq!(format_port_ranges(&[65534, 65535]), "65534-65535");
+    }
+
+    #[test]
+    fn test_confirmation_token() {
+        let mut args = default_args();
+        args.require_token = Some("ship-it".to_string());
+
+        // Listing is never blocked
+        assert!(args.check_confirmation_token(None).is_ok());
+
+        args.auto_kill = true;
+        assert!(args.check_confirmation_token(None).is_err());
+        assert!(args.check_confirmation_token(Some("nope")).is_err());
+        assert!(args.check_confirmation_token(Some("ship-it")).is_ok());
+    }
+
+    #[test]
+    fn test_parse_ports_file() {
+        let content = "# dev services\n3000\n\n8080 # api\n9000-9002\n";
+        assert_eq!(parse_ports_file(content).unwrap(), vec![3000, 8080, 9000, 9001, 9002]);
+
+        assert!(parse_ports_file("3000\nabc\n").unwrap_err().contains("line 2"));
+        assert!(parse_ports_file("0\n").is_err());
+        assert!(parse_ports_file("9005-9000\n").is_err());
+        assert_eq!(parse_port_spec("3000 - 3002"), Ok(vec![3000, 3001, 3002]));
+        assert_eq!(parse_port_spec("0-10"), Err("Port 0 is not valid".to_string()));
+    }
+
+    #[test]
+    fn test_log_level_from_verbose_count() {
+        assert_eq!(Args::parse_from(["port-kill"]).log_level(), None);
+        assert_eq!(Args::parse_from(["port-kill", "-v"]).log_level(), Some("info"));
+        assert_eq!(Args::parse_from(["port-kill", "-vv"]).log_level(), Some("debug"));
+        assert_eq!(Args::parse_from(["port-kill", "-vvvv"]).log_level(), Some("trace"));
+    }
+
+    #[test]
+    fn test_print_config() {
+        let args = Args::parse_from([
+            "port-kill", "--print-config", "--ports", "3000,3001", "--protocol", "both", "--require-token", "secret",
+        ]);
+        assert_eq!(args.print_config, Some(ConfigFormat::Toml));
+
+        let toml = args.render_config(ConfigFormat::Toml).unwrap();
+        assert!(toml.contains("ports = [3000, 3001]"), "{}", toml);
+        assert!(toml.contains("protocol = \"both\""));
+        assert!(!toml.contains("secret") && !toml.contains("print-config"));
+
+        let json: serde_json::Value = serde_json::from_str(&args.render_config(ConfigFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["start-port"], 2000);
+        assert_eq!(json["docker-context"], serde_json::Value::Null);
+
+        let args = Args::parse_from(["port-kill", "--print-config", "json"]);
+        assert_eq!(args.print_config, Some(ConfigFormat::Json));
+    }
 }
@@ -0,0 +1,127 @@
+//! Docker Compose projects.
+//!
+//! Compose labels every container it starts with the project it belongs to. Containers that
+//! share the label are grouped so a whole stack can be stopped at once instead of one
+//! container at a time. Containers without the label are handled individually as before.
+
+use crate::container::ContainerCli;
+use crate::error::PortKillError;
+use crate::types::{Endpoint, ProcessInfo, Protocol};
+use std::collections::{BTreeMap, HashMap};
+
+/// Label Compose sets on every container of a project.
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+
+/// The project named by a label value as printed by a docker format template. Templates
+/// print an empty string or `<no value>` when the label is missing.
+pub fn parse_project_label(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty() && value != "<no value>").then(|| value.to_string())
+}
+
+/// The id and name of every running container in `project`.
+pub fn project_containers(cli: &ContainerCli, project: &str) -> Result<Vec<(String, String)>, PortKillError> {
+    let filter = format!("label={}={}", PROJECT_LABEL, project);
+    let output = cli.output(&["ps", "--filter", &filter, "--format", "{{.ID}}\t{{.Names}}"])?;
+    if !output.status.success() {
+        return Err(cli.failure("ps", &output));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(id, name)| (id.to_string(), name.to_string()))
+        .collect())
+}
+
+/// The Compose project publishing each host port, for callers that don't look containers up
+/// by PID. Containers outside any project are left out.
+pub fn published_ports(cli: &ContainerCli) -> Result<HashMap<Endpoint, String>, PortKillError> {
+    let filter = format!("label={}", PROJECT_LABEL);
+    let format = format!("{{{{.Label \"{}\"}}}}\t{{{{.Ports}}}}", PROJECT_LABEL);
+    let output = cli.output(&["ps", "--filter", &filter, "--format", &format])?;
+    if !output.status.success() {
+        return Err(cli.failure("ps", &output));
+    }
+    Ok(parse_published_ports(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `{{.Label "com.docker.compose.project"}}\t{{.Ports}}` lines, where ports read like
+/// `0.0.0.0:3000->3000/tcp, :::3000->3000/tcp, 5432/tcp`. Ports that aren't published on
+/// the host are skipped.
+fn parse_published_ports(output: &str) -> HashMap<Endpoint, String> {
+    let mut ports = HashMap::new();
+    for line in output.lines() {
+        let Some((label, mappings)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some(project) = parse_project_label(label) else {
+            continue;
+        };
+
+        for mapping in mappings.split(", ") {
+            let Some((host, container)) = mapping.split_once("->") else {
+                continue;
+            };
+            let protocol = match container.rsplit_once('/') {
+                Some((_, "udp")) => Protocol::Udp,
+                _ => Protocol::Tcp,
+            };
+            // Ranges publish as 0.0.0.0:8000-8002->8000-8002/tcp
+            let Some((_, host_ports)) = host.rsplit_once(':') else {
+                continue;
+            };
+            let (start, end) = host_ports.split_once('-').unwrap_or((host_ports, host_ports));
+            let (Ok(start), Ok(end)) = (start.parse::<u16>(), end.parse::<u16>()) else {
+                continue;
+            };
+            for port in start..=end {
+                ports.insert(Endpoint::Port { port, protocol }, project.clone());
+            }
+        }
+    }
+    ports
+}
+
+/// Processes grouped by Compose project, in project name order. Processes outside any
+/// project are left out.
+pub fn group_by_project<'a>(
+    processes: impl IntoIterator<Item = &'a ProcessInfo>,
+) -> BTreeMap<&'a str, Vec<&'a ProcessInfo>> {
+    let mut projects: BTreeMap<&str, Vec<&ProcessInfo>> = BTreeMap::new();
+    for process_info in processes {
+        if let Some(project) = process_info.compose_project.as_deref() {
+            projects.entry(project).or_default().push(process_info);
+        }
+    }
+    for members in projects.values_mut() {
+        members.sort_by_key(|p| p.endpoint());
+    }
+    projects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_label() {
+        assert_eq!(parse_project_label("shop\n"), Some("shop".to_string()));
+        assert_eq!(parse_project_label(""), None);
+        assert_eq!(parse_project_label("<no value>"), None);
+    }
+
+    #[test]
+    fn test_parse_published_ports() {
+        let output = "shop\t0.0.0.0:3000->3000/tcp, :::3000->3000/tcp, 5432/tcp\n\
+                      shop\t0.0.0.0:8000-8001->8000-8001/tcp\n\
+                      dns\t127.0.0.1:5353->53/udp\n\
+                      <no value>\t0.0.0.0:9000->9000/tcp\n";
+        let ports = parse_published_ports(output);
+        assert_eq!(ports.len(), 4);
+        assert_eq!(ports[&Endpoint::tcp(3000)], "shop");
+        assert_eq!(ports[&Endpoint::tcp(8001)], "shop");
+        assert_eq!(ports[&Endpoint::Port { port: 5353, protocol: Protocol::Udp }], "dns");
+        assert!(!ports.contains_key(&Endpoint::tcp(5432)));
+    }
+}
@@ -0,0 +1,254 @@
+//! Defaults loaded from a TOML config file, so long flag lists need not be repeated on every run.
+//!
+//! Settings are merged in this order, later sources winning:
+//! 1. built-in defaults
+//! 2. the config file: `--config <PATH>`, else `~/.config/port-kill/config.toml` if it exists
+//! 3. for the ports only, the `PORT_KILL_PORTS` environment variable
+//! 4. flags given on the command line
+//!
+//! Keys are the long flag names, as printed by `--print-config`:
+//!
+//! ```toml
+//! ports = [3000, 3001, "4000-4010", 5173, 8080]
+//! ignore = ["Docker", "rapportd"]
+//! exclude-ports = [5432, 6379]
+//! kill-timeout = 2000
+//! ```
+//!
+//! Only standing preferences can be set here. One-shot actions such as `--kill` or
+//! `--kill-port`, and `--require-token`, are command-line only.
+
+use crate::cli::{Args, ContainerRuntime, OutputFormat, PortRange, ProtocolSelection, SortKey};
+use crate::tray_menu::HexColor;
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+
+/// Where the config file is looked for when `--config` isn't given.
+pub fn default_path() -> Option<PathBuf> {
+    Some(config_home()?.join("port-kill").join("config.toml"))
+}
+
+/// The user's configuration directory: `$XDG_CONFIG_HOME`, else `~/.config`.
+pub(crate) fn config_home() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")),
+    }
+}
+
+/// The settings a config file may hold, mirroring the corresponding [`Args`] fields.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigFile {
+    start_port: Option<u16>,
+    end_port: Option<u16>,
+    ports: Option<Vec<PortRange>>,
+    ports_file: Option<PathBuf>,
+    exclude_ports: Option<Vec<u16>>,
+    reserve: Option<Vec<u16>>,
+    discover: Option<bool>,
+    protocol: Option<ProtocolSelection>,
+    host: Option<IpAddr>,
+    unix_sockets: Option<String>,
+    include_established: Option<bool>,
+    kill_established: Option<bool>,
+    console: Option<bool>,
+    auto_mode: Option<bool>,
+    verbose: Option<u8>,
+    docker: Option<bool>,
+    docker_lazy: Option<bool>,
+    docker_context: Option<String>,
+    container_runtime: Option<ContainerRuntime>,
+    show_pid: Option<bool>,
+    sort: Option<SortKey>,
+    idle_color: Option<HexColor>,
+    busy_color: Option<HexColor>,
+    warn_color: Option<HexColor>,
+    warn_count: Option<usize>,
+    interval: Option<f64>,
+    confirm_window: Option<u64>,
+    only: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+    name_regex: Option<String>,
+    ignore_regex: Option<String>,
+    cmd_contains: Option<Vec<String>>,
+    cmd_ignore: Option<Vec<String>>,
+    ignore_root: Option<bool>,
+    include_system: Option<bool>,
+    no_default_ignores: Option<bool>,
+    show_free: Option<bool>,
+    heatmap: Option<bool>,
+    table: Option<bool>,
+    json: Option<bool>,
+    format: Option<OutputFormat>,
+    quiet: Option<bool>,
+    snapshot_every: Option<u32>,
+    output_dir: Option<PathBuf>,
+    socket: Option<PathBuf>,
+    serve: Option<SocketAddr>,
+    metrics_addr: Option<SocketAddr>,
+    signal: Option<String>,
+    kill_timeout: Option<u64>,
+    no_sigkill: Option<bool>,
+    no_verify: Option<bool>,
+    kill_tree: Option<bool>,
+    kill_retries: Option<u32>,
+    log_file: Option<PathBuf>,
+    notify: Option<bool>,
+    watch_only: Option<bool>,
+    dry_run: Option<bool>,
+    fail_fast: Option<bool>,
+    yes: Option<bool>,
+}
+
+impl ConfigFile {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// Fill in every setting of `args` that wasn't given on the command line, as recorded in
+    /// `matches`, with the value from this file.
+    pub fn apply_to(self, args: &mut Args, matches: &ArgMatches) {
+        let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        // A port range given on the command line must not be overridden by the file's ports
+        let ports = if from_command_line("start_port") || from_command_line("end_port") {
+            None
+        } else {
+            self.ports
+        };
+
+        macro_rules! merge {
+            ($($field:ident = $value:expr),* $(,)?) => {$(
+                if let Some(value) = $value {
+                    if !from_command_line(stringify!($field)) {
+                        args.$field = value.into();
+                    }
+                }
+            )*};
+        }
+
+        merge!(
+            start_port = self.start_port,
+            end_port = self.end_port,
+            ports = ports,
+            ports_file = self.ports_file,
+            exclude_ports = self.exclude_ports,
+            reserve = self.reserve,
+            discover = self.discover,
+            protocol = self.protocol,
+            host = self.host,
+            unix_sockets = self.unix_sockets,
+            include_established = self.include_established,
+            kill_established = self.kill_established,
+            console = self.console,
+            auto_mode = self.auto_mode,
+            verbose = self.verbose,
+            docker = self.docker,
+            docker_lazy = self.docker_lazy,
+            docker_context = self.docker_context,
+            container_runtime = self.container_runtime,
+            show_pid = self.show_pid,
+            sort = self.sort,
+            idle_color = self.idle_color,
+            busy_color = self.busy_color,
+            warn_color = self.warn_color,
+            warn_count = self.warn_count,
+            interval = self.interval,
+            confirm_window = self.confirm_window,
+            only = self.only,
+            ignore = self.ignore,
+            name_regex = self.name_regex,
+            ignore_regex = self.ignore_regex,
+            cmd_contains = self.cmd_contains,
+            cmd_ignore = self.cmd_ignore,
+            ignore_root = self.ignore_root,
+            include_system = self.include_system,
+            no_default_ignores = self.no_default_ignores,
+            show_free = self.show_free,
+            heatmap = self.heatmap,
+            table = self.table,
+            json = self.json,
+            format = self.format,
+            quiet = self.quiet,
+            snapshot_every = self.snapshot_every,
+            output_dir = self.output_dir,
+            socket = self.socket,
+            serve = self.serve,
+            metrics_addr = self.metrics_addr,
+            signal = self.signal,
+            kill_timeout = self.kill_timeout,
+            no_sigkill = self.no_sigkill,
+            no_verify = self.no_verify,
+            kill_tree = self.kill_tree,
+            kill_retries = self.kill_retries,
+            log_file = self.log_file,
+            notify = self.notify,
+            watch_only = self.watch_only,
+            dry_run = self.dry_run,
+            fail_fast = self.fail_fast,
+            yes = self.yes,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::ConfigFormat;
+
+    fn parse_with(config: &str, argv: &[&str]) -> Result<Args, String> {
+        let path = std::env::temp_dir().join(format!("port-kill-config-{}-{}.toml", std::process::id(), argv.len()));
+        std::fs::write(&path, config).unwrap();
+        let result = Args::parse_from_with_config(["port-kill"].iter().chain(argv), Some(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn test_config_file_fills_in_defaults() {
+        let config = "ports = [3000, 3001]\nignore = [\"Docker\", \"rapportd\"]\ndocker = true\nkill-timeout = 2000\nprotocol = \"both\"\n";
+        let args = parse_with(config, &[]).unwrap();
+        assert_eq!(args.get_ports_to_monitor(), vec![3000, 3001]);
+        assert_eq!(args.ignore, vec!["Docker", "rapportd"]);
+        assert!(args.docker);
+        assert_eq!(args.kill_timeout, 2000);
+        assert_eq!(args.protocol, ProtocolSelection::Both);
+    }
+
+    #[test]
+    fn test_command_line_overrides_config_file() {
+        let config = "ports = [3000, 3001]\nkill-timeout = 2000\nsignal = \"INT\"\n";
+        let args = parse_with(config, &["--ports", "8080", "--kill-timeout", "100"]).unwrap();
+        assert_eq!(args.get_ports_to_monitor(), vec![8080]);
+        assert_eq!(args.kill_timeout, 100);
+        assert_eq!(args.signal, "INT");
+
+        // A range on the command line beats the file's specific ports
+        let args = parse_with(config, &["--start-port", "4000", "--end-port", "4010"]).unwrap();
+        assert_eq!(args.ports, None);
+        assert_eq!(args.get_ports_to_monitor().len(), 11);
+    }
+
+    #[test]
+    fn test_config_file_port_ranges() {
+        let args = parse_with("ports = [8080, \"3000-3002\"]\n", &[]).unwrap();
+        assert_eq!(args.get_ports_to_monitor(), vec![3000, 3001, 3002, 8080]);
+        assert!(args.render_config(ConfigFormat::Toml).unwrap().contains("ports = [8080, \"3000-3002\"]"));
+        assert!(parse_with("ports = [\"3000-x\"]\n", &[]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        let error = parse_with("kill = true\n", &[]).unwrap_err();
+        assert!(error.contains("unknown field `kill`"), "{}", error);
+        assert!(parse_with("ports = \"3000\"\n", &[]).is_err());
+
+        // An explicit --config must exist, the default location may not
+        assert!(Args::parse_from_with_config(["port-kill", "--config", "/nonexistent/port-kill.toml"], None).is_err());
+        assert!(Args::parse_from_with_config(["port-kill"], Some(PathBuf::from("/nonexistent/config.toml"))).is_ok());
+    }
+}
@@ -1,17 +1,337 @@
 use crate::{
+    compose,
+    error::{KillFailure, KillsFailed, PortKillError},
+    http,
+    ipc,
+    metrics::{self, Metrics},
+    pattern,
+    policy,
     process_monitor::ProcessMonitor,
-    types::{ProcessUpdate, StatusBarInfo},
-    cli::Args,
+    restart,
+    types::{Endpoint, KillAttempt, ProcessInfo, ProcessUpdate, Protocol, StatusBarInfo},
+    cli::{format_port_ranges, Args, Command, OutputFormat},
 };
 use anyhow::Result;
 use crossbeam_channel::{bounded, Receiver};
-use log::{error, info};
+use log::{debug, error, info};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+
+/// Print human-readable output. With `--json` or another machine-readable `--format`, stdout
+/// carries only the data stream, so everything meant for people goes to stderr instead.
+macro_rules! status {
+    ($app:expr, $($arg:tt)*) => {
+        if $app.args.is_machine_output() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// How often `--wait-for-free` / `--wait-for-listen` re-check the port.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long `--strict` gives killed processes to release their ports before failing.
+const STRICT_SETTLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy)]
+enum WaitCondition {
+    Free,
+    Listening,
+}
+
+impl WaitCondition {
+    fn describe(self) -> &'static str {
+        match self {
+            WaitCondition::Free => "be freed",
+            WaitCondition::Listening => "start listening",
+        }
+    }
+}
+
+/// Ports per row of the `--heatmap` grid.
+const HEATMAP_WIDTH: usize = 64;
+
+/// Render one character per port, `#` occupied and `.` free, in rows of `width` labelled with
+/// the first port of the row.
+fn render_heatmap(ports: &[u16], occupied: &HashSet<u16>, width: usize) -> String {
+    let mut grid = String::new();
+    for row in ports.chunks(width) {
+        grid.push_str(&format!("{:>5} ", row[0]));
+        grid.extend(row.iter().map(|port| if occupied.contains(port) { '#' } else { '.' }));
+        grid.push('\n');
+    }
+    grid
+}
+
+/// Longest COMMAND shown in a `--table` row before it is cut short.
+const TABLE_COMMAND_WIDTH: usize = 40;
+
+/// ANSI SGR codes used by `--table`.
+const BOLD: &str = "1";
+const YELLOW: &str = "33";
+const RED: &str = "31";
+
+/// Whether to color output: only when stdout is a terminal and `NO_COLOR` isn't set, per
+/// https://no-color.org.
+fn color_enabled() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// `text` wrapped in the ANSI escape for `code`.
+fn paint(text: &str, code: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Where a process listens as shown to the user: its bound address when known, e.g.
+/// `127.0.0.1:3000` or `*:5353/udp`, else the endpoint, followed by the IP families when
+/// known, e.g. `*:3000 (v4+v6)`.
+fn endpoint_label(process_info: &ProcessInfo) -> String {
+    let label = match (process_info.endpoint(), process_info.bound_address()) {
+        (Endpoint::Port { protocol: Protocol::Udp, .. }, Some(address)) => format!("{}/udp", address),
+        (Endpoint::Port { .. }, Some(address)) => address,
+        (endpoint, _) => endpoint.to_string(),
+    };
+    match process_info.family {
+        Some(family) => format!("{} ({})", label, family),
+        None => label,
+    }
+}
+
+/// `processes` as a table with PORT, PID, NAME, COMMAND and DOCKER columns, each padded to
+/// its widest cell. With `row_color` the header is bold and the rows are painted that color.
+fn render_table(processes: &[&ProcessInfo], row_color: Option<&str>) -> String {
+    const HEADER: [&str; 5] = ["PORT", "PID", "NAME", "COMMAND", "DOCKER"];
+    let rows: Vec<[String; 5]> = processes
+        .iter()
+        .map(|p| {
+            let command = match p.command.char_indices().nth(TABLE_COMMAND_WIDTH - 1) {
+                Some((end, _)) if p.command.chars().count() > TABLE_COMMAND_WIDTH => format!("{}…", &p.command[..end]),
+                _ => p.command.clone(),
+            };
+            let docker = p.container_name.clone().unwrap_or_else(|| "-".to_string());
+            [endpoint_label(p), p.pid.to_string(), p.name.clone(), command, docker]
+        })
+        .collect();
+
+    let mut widths = HEADER.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: [&str; 5]| {
+        let padded: Vec<String> = cells.iter().zip(widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        padded.join("  ").trim_end().to_string()
+    };
+
+    let mut table = String::new();
+    let header = line(HEADER);
+    table.push_str(&match row_color {
+        Some(_) => paint(&header, BOLD),
+        None => header,
+    });
+    table.push('\n');
+    for row in &rows {
+        let row = line(row.each_ref().map(String::as_str));
+        table.push_str(&match row_color {
+            Some(color) => paint(&row, color),
+            None => row,
+        });
+        table.push('\n');
+    }
+    table
+}
+
+/// Column names of `--format csv` and `--format tsv`.
+const DELIMITED_HEADER: [&str; 5] = ["port", "pid", "name", "command", "container_name"];
+
+/// One `--format csv` or `--format tsv` line of `fields`, separated by `delimiter`. CSV fields
+/// holding the delimiter, a quote or a line break are quoted with inner quotes doubled; TSV
+/// has no quoting, so tabs and line breaks in a field become spaces.
+fn delimited_line<'a>(fields: impl IntoIterator<Item = &'a str>, delimiter: char) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|field| {
+            if delimiter == '\t' {
+                field.replace(['\t', '\n', '\r'], " ")
+            } else if field.contains([delimiter, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    fields.join(&delimiter.to_string())
+}
+
+/// One `--format csv` or `--format tsv` row per process, in the [`DELIMITED_HEADER`] columns.
+fn render_delimited(processes: &[&ProcessInfo], delimiter: char) -> String {
+    let mut rows = String::new();
+    for p in processes {
+        let (endpoint, pid) = (p.endpoint().to_string(), p.pid.to_string());
+        let fields = [endpoint.as_str(), &pid, &p.name, &p.command, p.container_name.as_deref().unwrap_or("")];
+        rows.push_str(&delimited_line(fields, delimiter));
+        rows.push('\n');
+    }
+    rows
+}
+
+/// The trailer printed under each update's process list, e.g.
+/// `📊 7 processes across 5 ports, 2 Docker`. TCP and UDP on one port count as one port.
+fn totals_line(processes: &HashMap<Endpoint, ProcessInfo>) -> String {
+    let ports: HashSet<u16> = processes.keys().filter_map(Endpoint::port).collect();
+    let docker = processes.values().filter(|p| p.container_id.is_some()).count();
+    let processes_noun = if processes.len() == 1 { "process" } else { "processes" };
+    let ports_noun = if ports.len() == 1 { "port" } else { "ports" };
+    format!("📊 {} {} across {} {}, {} Docker", processes.len(), processes_noun, ports.len(), ports_noun, docker)
+}
+
+/// Exit status of a one-shot run that failed with `error`: 2 when the scan worked but some
+/// kills failed, 1 for anything else (a failed scan, a refused kill, bad arguments). Runs that
+/// succeed, including ones that found nothing to kill, exit with 0.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    if error.is::<KillsFailed>() || matches!(error.downcast_ref(), Some(PortKillError::KillsFailed(_))) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The error a `--kill-port` of `process_info` fails with: a kill that went wrong becomes
+/// [`KillsFailed`] so the run exits with 2, anything else, such as a refusal, is passed on.
+fn kill_port_error(process_info: &ProcessInfo, error: PortKillError) -> anyhow::Error {
+    if !error.is_kill_failure() {
+        return error.into();
+    }
+    KillsFailed::new(vec![KillFailure { pid: process_info.pid, endpoint: Some(process_info.endpoint()), error: error.to_string() }])
+        .into()
+}
+
+/// How to describe a kill in status lines, which under `--dry-run` didn't happen.
+fn kill_verb(monitor: &ProcessMonitor) -> &'static str {
+    if monitor.is_dry_run() {
+        "Would kill"
+    } else {
+        "Killed"
+    }
+}
+
+/// The `--require-name` guard for `--kill-port`: the name must equal `required` exactly, or
+/// match it when it is a glob.
+fn check_required_name(required: &str, process_info: &ProcessInfo) -> Result<()> {
+    if pattern::matches_exact(required, &process_info.name) {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "Refusing to kill {} (PID {}) on {}: name does not match '{}'",
+        process_info.name,
+        process_info.pid,
+        process_info.endpoint(),
+        required
+    ))
+}
+
+/// The `[y/N]` question asked before a kill-all signals `targets`, naming every endpoint of
+/// `processes` they hold.
+fn confirmation_question(processes: &HashMap<Endpoint, ProcessInfo>, targets: &[&ProcessInfo]) -> String {
+    let pids: HashSet<i32> = targets.iter().map(|p| p.pid).collect();
+    let mut endpoints: Vec<Endpoint> =
+        processes.values().filter(|p| pids.contains(&p.pid)).map(ProcessInfo::endpoint).collect();
+    endpoints.sort_unstable();
+    let endpoints: Vec<String> = endpoints.iter().map(Endpoint::to_string).collect();
+    let processes_noun = if targets.len() == 1 { "process" } else { "processes" };
+    let ports_noun = if endpoints.len() == 1 { "port" } else { "ports" };
+    format!("Kill {} {} on {} {}? [y/N] ", targets.len(), processes_noun, ports_noun, endpoints.join(", "))
+}
+
+/// Whether the answer to a `[y/N]` question is yes. Anything else, including nothing, is no.
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Read one line from stdin without blocking the runtime, empty at end of input.
+async fn read_answer() -> Result<String> {
+    let answer = tokio::task::spawn_blocking(|| {
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).map(|_| answer)
+    })
+    .await??;
+    Ok(answer)
+}
+
+/// A command typed on stdin while the console monitor is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConsoleCommand {
+    KillPort(u16),
+    /// Kill a port's process and relaunch its command line
+    RestartPort(u16),
+    KillAll,
+    Refresh,
+    Quit,
+}
+
+impl ConsoleCommand {
+    const HELP: &'static str = "k <port> kill a port, kr <port> kill and restart it, ka kill all, r refresh, q quit";
+
+    fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("k"), Some(port)) => match port.parse::<u16>() {
+                Ok(port) if port != 0 => ConsoleCommand::KillPort(port),
+                _ => return Err(format!("'{}' is not a valid port", port)),
+            },
+            (Some("kr"), Some(port)) => match port.parse::<u16>() {
+                Ok(port) if port != 0 => ConsoleCommand::RestartPort(port),
+                _ => return Err(format!("'{}' is not a valid port", port)),
+            },
+            (Some(command @ ("k" | "kr")), None) => return Err(format!("{} needs a port", command)),
+            (Some("ka"), None) => ConsoleCommand::KillAll,
+            (Some("r"), None) => ConsoleCommand::Refresh,
+            (Some("q"), None) => ConsoleCommand::Quit,
+            _ => return Err(format!("Unknown command '{}'", line.trim())),
+        };
+        if words.next().is_some() {
+            return Err(format!("Unknown command '{}'", line.trim()));
+        }
+        Ok(command)
+    }
+}
+
+/// Forward lines typed on stdin, or `None` when stdin isn't a terminal. A plain thread is
+/// used because a blocked stdin read would otherwise hold up runtime shutdown.
+fn spawn_stdin_reader() -> Option<mpsc::UnboundedReceiver<String>> {
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Some(receiver)
+}
 
 pub struct ConsolePortKillApp {
     process_monitor: Arc<Mutex<ProcessMonitor>>,
     update_receiver: Receiver<ProcessUpdate>,
+    snapshot_sender: watch::Sender<ProcessUpdate>,
+    shutdown_sender: watch::Sender<bool>,
+    monitor_handle: Option<JoinHandle<()>>,
+    server_handles: Vec<JoinHandle<()>>,
+    ports_to_monitor: Vec<u16>,
+    /// Whether the `--format csv`/`tsv` header row has been printed
+    header_printed: AtomicBool,
     args: Args,
 }
 
@@ -21,63 +341,208 @@ impl ConsolePortKillApp {
         let (update_sender, update_receiver) = bounded(100);
 
         // Create process monitor with configurable ports
-        let process_monitor = Arc::new(Mutex::new(ProcessMonitor::new(update_sender, args.get_ports_to_monitor(), args.docker)?));
+        let ports_to_monitor = args.get_ports_to_monitor();
+        let process_monitor = Arc::new(Mutex::new(ProcessMonitor::from_args(update_sender, &args)?));
+
+        // Latest snapshot, shared with socket and HTTP clients
+        let (snapshot_sender, _) = watch::channel(ProcessUpdate::empty());
+        let (shutdown_sender, _) = watch::channel(false);
 
         Ok(Self {
             process_monitor,
             update_receiver,
+            snapshot_sender,
+            shutdown_sender,
+            monitor_handle: None,
+            server_handles: Vec::new(),
+            ports_to_monitor,
+            header_printed: AtomicBool::new(false),
             args,
         })
     }
 
     pub async fn run(mut self) -> Result<()> {
         info!("Starting Console Port Kill application...");
-        println!("🚀 Port Kill Console Monitor Started!");
-        println!("📡 Monitoring {} every 2 seconds...", self.args.get_port_description());
-        println!("💡 Press Ctrl+C to quit");
-        println!("");
+
+        if let Some(Command::WhatsOn { port, suggest }) = self.args.command {
+            return self.run_whats_on(port, suggest).await;
+        }
+
+        if let Some(pattern) = self.args.kill_container.clone() {
+            return self.run_kill_container(&pattern).await;
+        }
+
+        if let Some(project) = self.args.kill_compose_project.clone() {
+            return self.run_kill_compose_project(&project).await;
+        }
+
+        if let Some(port) = self.args.kill_port {
+            return self.run_kill_port(port).await;
+        }
+
+        if let Some(port) = self.args.wait_for_free {
+            return self.run_wait_for(port, WaitCondition::Free).await;
+        }
+
+        if let Some(port) = self.args.wait_for_listen {
+            return self.run_wait_for(port, WaitCondition::Listening).await;
+        }
+
+        if self.args.kill {
+            return self.run_kill().await;
+        }
+
+        if self.args.once {
+            return self.run_once().await;
+        }
+
+        status!(self, "🚀 Port Kill Console Monitor Started!");
+        let interval = self.process_monitor.lock().await.interval();
+        status!(self, "📡 Monitoring {} every {:?}...", self.args.get_port_description(), interval);
+        if self.args.watch_only {
+            status!(self, "👀 Watch-only mode: killing is disabled");
+        }
+        status!(self, "💡 Press Ctrl+C to quit");
+
+        // Interactive commands only make sense when someone is typing
+        let commands = spawn_stdin_reader();
+        if commands.is_some() {
+            status!(self, "⌨️  Commands: {}", ConsoleCommand::HELP);
+        }
+        status!(self, "");
 
         // Start process monitoring in background
         let monitor = self.process_monitor.clone();
-        tokio::spawn(async move {
-            if let Err(e) = monitor.lock().await.start_monitoring().await {
+        let shutdown = self.shutdown_sender.subscribe();
+        self.monitor_handle = Some(tokio::spawn(async move {
+            if let Err(e) = ProcessMonitor::start_monitoring(monitor, shutdown).await {
                 error!("Process monitoring failed: {}", e);
             }
-        });
+        }));
+
+        // Serve the framed protocol for editor extensions
+        if let Some(socket_path) = self.args.socket.clone() {
+            let updates = self.snapshot_sender.subscribe();
+            let monitor = self.process_monitor.clone();
+            let shutdown = self.shutdown_sender.subscribe();
+            status!(self, "🔌 Serving updates on {}", socket_path.display());
+            self.server_handles.push(tokio::spawn(async move {
+                if let Err(e) = ipc::serve(&socket_path, updates, monitor, shutdown).await {
+                    error!("Socket server failed: {}", e);
+                }
+            }));
+        }
+
+        // Serve the same snapshot and kills over HTTP
+        if let Some(addr) = self.args.serve {
+            let updates = self.snapshot_sender.subscribe();
+            let monitor = self.process_monitor.clone();
+            let required_token = self.args.require_token.clone();
+            let shutdown = self.shutdown_sender.subscribe();
+            status!(self, "🌐 Serving HTTP on http://{}", addr);
+            self.server_handles.push(tokio::spawn(async move {
+                if let Err(e) = http::serve(addr, updates, monitor, required_token, shutdown).await {
+                    error!("HTTP server failed: {}", e);
+                }
+            }));
+        }
+
+        // Expose the snapshot to Prometheus
+        if let Some(addr) = self.args.metrics_addr {
+            let metrics = Metrics {
+                ports: self.ports_to_monitor.clone(),
+                protocols: self.args.protocol.protocols().to_vec(),
+                kills: self.process_monitor.lock().await.kill_count(),
+            };
+            let updates = self.snapshot_sender.subscribe();
+            let shutdown = self.shutdown_sender.subscribe();
+            status!(self, "📈 Serving metrics on http://{}/metrics", addr);
+            self.server_handles.push(tokio::spawn(async move {
+                if let Err(e) = metrics::serve(addr, metrics, updates, shutdown).await {
+                    error!("Metrics server failed: {}", e);
+                }
+            }));
+        }
+
+        // Handle updates in the main thread until interrupted
+        tokio::select! {
+            _ = self.handle_console_updates(commands) => {}
+            _ = tokio::signal::ctrl_c() => {
+                info!("Interrupt received, shutting down...");
+            }
+        }
 
-        // Handle updates in the main thread
-        self.handle_console_updates().await;
+        self.stop_monitoring().await;
+        self.print_summary().await;
 
         Ok(())
     }
 
-    async fn handle_console_updates(&mut self) {
+    /// Say what was left running and what was killed, once monitoring has stopped.
+    async fn print_summary(&self) {
+        let running = self.snapshot_sender.borrow().count;
+        let kills = self.process_monitor.lock().await.kill_count().load(Ordering::Relaxed);
+        status!(self, "");
+        status!(self, "🛑 Stopped monitoring: {} process(es) on monitored ports at the last scan, {} killed this session", running, kills);
+    }
+
+    /// Signal the monitoring task and servers to stop, and wait for any in-flight scan to
+    /// finish and the socket file to be removed.
+    async fn stop_monitoring(&mut self) {
+        let _ = self.shutdown_sender.send(true);
+        if let Some(handle) = self.monitor_handle.take() {
+            if let Err(e) = handle.await {
+                error!("Monitoring task failed to shut down cleanly: {}", e);
+            }
+        }
+        for handle in self.server_handles.drain(..) {
+            if let Err(e) = handle.await {
+                error!("Server failed to shut down cleanly: {}", e);
+            }
+        }
+    }
+
+    /// Print updates as they arrive and run any typed commands, until a quit command.
+    async fn handle_console_updates(&mut self, mut commands: Option<mpsc::UnboundedReceiver<String>>) {
         info!("Starting console update handler...");
+        // The processes in the last printed update, so --quiet can skip repeats
+        let mut last_printed: Option<HashMap<Endpoint, ProcessInfo>> = None;
 
         loop {
-            // Check for process updates
-            if let Ok(update) = self.update_receiver.try_recv() {
-                // Update status
-                let status_info = StatusBarInfo::from_process_count(update.count);
-                
-                // Print status to console
-                println!("🔄 Port Status: {} - {}", status_info.text, status_info.tooltip);
-                
-                if update.count > 0 {
-                    println!("📋 Detected Processes:");
-                    for (port, process_info) in &update.processes {
-                        if let (Some(_container_id), Some(container_name)) = (&process_info.container_id, &process_info.container_name) {
-                            println!("   • Port {}: {} - {} [Docker: {}]", 
-                                    port, process_info.name, process_info.command, container_name);
-                        } else if self.args.show_pid {
-                            println!("   • Port {}: {} (PID {}) - {}", 
-                                    port, process_info.name, process_info.pid, process_info.command);
-                        } else {
-                            println!("   • Port {}: {} - {}", 
-                                    port, process_info.name, process_info.command);
+            while let Some(line) = commands.as_mut().and_then(|c| c.try_recv().ok()) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match ConsoleCommand::parse(&line) {
+                    Ok(ConsoleCommand::Quit) => return,
+                    Ok(ConsoleCommand::KillAll) if self.needs_confirmation() => {
+                        if let Some(answers) = commands.as_mut() {
+                            self.confirm_kill_all(answers).await;
                         }
                     }
-                    println!("");
+                    Ok(command) => self.run_command(command).await,
+                    Err(e) => status!(self, "❓ {}. Commands: {}", e, ConsoleCommand::HELP),
+                }
+            }
+
+            // Check for process updates
+            if let Ok(mut update) = self.update_receiver.try_recv() {
+                self.enrich_for_display(&mut update).await;
+                self.snapshot_sender.send_replace(update.clone());
+
+                let unchanged = last_printed.as_ref().is_some_and(|printed| !ProcessUpdate::is_change(printed, &update.processes));
+                if self.args.quiet && unchanged {
+                    debug!("Processes unchanged since the last printed update, not printing");
+                } else {
+                    self.print_update(&update);
+                    last_printed = Some(update.processes.clone());
+                }
+
+                if self.args.auto_kill && update.count > 0 {
+                    info!("Auto-killing {} detected process(es)", update.count);
+                    if let Err(e) = self.process_monitor.lock().await.kill_all_processes().await {
+                        error!("Auto-kill failed: {}", e);
+                    }
                 }
             }
 
@@ -85,4 +550,643 @@ impl ConsolePortKillApp {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
     }
+
+    async fn run_command(&self, command: ConsoleCommand) {
+        let monitor = self.process_monitor.lock().await;
+        match command {
+            ConsoleCommand::KillPort(port) => match monitor.kill_port(port).await {
+                Ok(process_info) => status!(self, "✅ {} {} (PID {}) on port {}", kill_verb(&monitor), process_info.name, process_info.pid, port),
+                Err(PortKillError::NoProcess(_)) => status!(self, "✅ Port {} is already free", port),
+                Err(e) => status!(self, "❌ Failed to kill port {}: {}", port, e),
+            },
+            ConsoleCommand::RestartPort(port) => match monitor.get_process_on_port(port).await {
+                Ok(Some(process_info)) if !process_info.can_restart() => {
+                    status!(self, "❌ Not killing port {}: the command line of {} is unknown, so it can't be restarted", port, process_info.name)
+                }
+                Ok(Some(process_info)) => match monitor.kill_scanned(&process_info).await {
+                    Ok(()) => {
+                        status!(self, "✅ {} {} (PID {}) on port {}", kill_verb(&monitor), process_info.name, process_info.pid, port);
+                        if let Err(e) = self.restart(&monitor, &process_info) {
+                            status!(self, "❌ {:#}", e);
+                        }
+                    }
+                    Err(e) => status!(self, "❌ Failed to kill port {}: {}", port, e),
+                },
+                Ok(None) => status!(self, "✅ Port {} is already free", port),
+                Err(e) => status!(self, "❌ Failed to check port {}: {}", port, e),
+            },
+            ConsoleCommand::KillAll => match monitor.kill_all_processes().await {
+                Ok(()) => status!(self, "✅ {} all detected processes", kill_verb(&monitor)),
+                Err(e) => status!(self, "❌ Kill all failed: {}", e),
+            },
+            ConsoleCommand::Refresh => match monitor.scan_once().await {
+                Ok(report) => {
+                    drop(monitor);
+                    let mut update = ProcessUpdate::new(report.processes);
+                    self.enrich_for_display(&mut update).await;
+                    self.print_update(&update);
+                }
+                Err(e) => status!(self, "❌ Refresh failed: {}", e),
+            },
+            // Handled by the caller, which owns the loop
+            ConsoleCommand::Quit => {}
+        }
+    }
+
+    /// Whether a kill-all must be confirmed on stdin first. Dry runs and watch-only kill
+    /// nothing, and without a terminal there is nobody to ask.
+    fn needs_confirmation(&self) -> bool {
+        !self.args.yes && !self.args.dry_run && !self.args.watch_only && std::io::stdin().is_terminal()
+    }
+
+    /// List what a kill-all of `processes` would signal and ask whether to go ahead, returning
+    /// `false` without asking when nothing would be killed.
+    fn ask_to_kill(&self, monitor: &ProcessMonitor, processes: &HashMap<Endpoint, ProcessInfo>) -> bool {
+        let targets = monitor.kill_targets(processes);
+        if targets.is_empty() {
+            return false;
+        }
+
+        status!(self, "📋 About to kill:");
+        for process_info in &targets {
+            self.print_process(process_info);
+        }
+        let question = confirmation_question(processes, &targets);
+        // The answer is typed on the same line, so the question isn't newline-terminated
+        if self.args.is_machine_output() {
+            eprint!("{}", question);
+            let _ = std::io::stderr().flush();
+        } else {
+            print!("{}", question);
+            let _ = std::io::stdout().flush();
+        }
+        true
+    }
+
+    /// Run the `ka` command once the next typed line confirms it. The monitor is unlocked
+    /// while waiting, and what was listed is what gets killed.
+    async fn confirm_kill_all(&self, answers: &mut mpsc::UnboundedReceiver<String>) {
+        let processes = match self.process_monitor.lock().await.scan_processes().await {
+            Ok(processes) => processes,
+            Err(e) => {
+                status!(self, "❌ Kill all failed: {}", e);
+                return;
+            }
+        };
+        if !self.ask_to_kill(&*self.process_monitor.lock().await, &processes) {
+            status!(self, "✅ Nothing to kill");
+            return;
+        }
+
+        let answer = answers.recv().await.unwrap_or_default();
+        if !is_yes(&answer) {
+            status!(self, "↩️  Cancelled, nothing was killed");
+            return;
+        }
+
+        let monitor = self.process_monitor.lock().await;
+        match monitor.kill_processes(&processes).await {
+            Ok(attempts) => {
+                self.print_kill_attempts(&attempts);
+                status!(self, "✅ {} {} process(es)", kill_verb(&monitor), attempts.len());
+            }
+            Err(e) => status!(self, "❌ Kill all failed: {}", e),
+        }
+    }
+
+    /// Resolve container details for `--docker-lazy` now that the update is about to be shown.
+    async fn enrich_for_display(&self, update: &mut ProcessUpdate) {
+        let monitor = self.process_monitor.lock().await;
+        for process_info in update.processes.values_mut() {
+            monitor.enrich_docker_info(process_info).await;
+        }
+    }
+
+    /// Describe whatever holds a single port, optionally with the commands that would free it.
+    async fn run_whats_on(&self, port: u16, suggest: bool) -> Result<()> {
+        let monitor = self.process_monitor.lock().await;
+        let Some(mut process_info) = monitor.get_process_on_port(port).await? else {
+            status!(self, "✅ Port {} is free", port);
+            return Ok(());
+        };
+        monitor.enrich_docker_info(&mut process_info).await;
+
+        self.print_process(&process_info);
+
+        if suggest {
+            status!(self, "💡 To free port {} manually:", port);
+            for command in monitor.kill_commands(&process_info) {
+                status!(self, "   {}", command);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop all containers matching a name pattern and report each result.
+    async fn run_kill_container(&self, pattern: &str) -> Result<()> {
+        let results = self.process_monitor.lock().await.kill_containers_matching(pattern).await?;
+        if results.is_empty() {
+            return Err(anyhow::anyhow!("No running containers match '{}'", pattern));
+        }
+        self.report_container_stops(&results, "matching")
+    }
+
+    /// Stop all containers of a Compose project and report each result.
+    async fn run_kill_compose_project(&self, project: &str) -> Result<()> {
+        let results = self.process_monitor.lock().await.kill_compose_project(project).await?;
+        if results.is_empty() {
+            return Err(anyhow::anyhow!("No running containers in Compose project {}", project));
+        }
+        self.report_container_stops(&results, &format!("{} project", project))
+    }
+
+    /// Print the outcome of stopping each container, failing if any could not be stopped.
+    /// `which` describes the containers, e.g. "matching".
+    fn report_container_stops(&self, results: &[(String, Result<(), PortKillError>)], which: &str) -> Result<()> {
+        let mut failed = 0;
+        for (container_name, result) in results {
+            match result {
+                Ok(()) => status!(self, "✅ Stopped container {}", container_name),
+                Err(e) => {
+                    failed += 1;
+                    status!(self, "❌ Failed to stop container {}: {}", container_name, e);
+                }
+            }
+        }
+
+        if failed > 0 {
+            return Err(anyhow::anyhow!("{} of {} {} containers could not be stopped", failed, results.len(), which));
+        }
+        Ok(())
+    }
+
+    /// Kill whatever holds a single port, refusing if it doesn't match `--require-name`.
+    async fn run_kill_port(&self, port: u16) -> Result<()> {
+        let monitor = self.process_monitor.lock().await;
+        let Some(process_info) = monitor.get_process_on_port(port).await? else {
+            status!(self, "✅ Port {} is already free", port);
+            return Ok(());
+        };
+
+        if let Some(ref required) = self.args.require_name {
+            check_required_name(required, &process_info)?;
+        }
+
+        if let Some(reason) = policy::read(&monitor.policy()).protection(&process_info) {
+            return Err(anyhow::anyhow!("Refusing to kill {} on port {}: {}", process_info.name, port, reason));
+        }
+        // Check before killing, there's no getting the process back without its command line
+        if self.args.restart {
+            restart::describe(&process_info)?;
+        }
+
+        monitor.kill_scanned(&process_info).await.map_err(|e| kill_port_error(&process_info, e))?;
+        status!(self, "✅ {} {} (PID {}) on port {}", kill_verb(&monitor), process_info.name, process_info.pid, port);
+        if self.args.restart {
+            self.restart(&monitor, &process_info)?;
+        }
+        Ok(())
+    }
+
+    /// Relaunch `process_info` once it has been killed, or say what would be run in a dry run.
+    fn restart(&self, monitor: &ProcessMonitor, process_info: &ProcessInfo) -> Result<()> {
+        let command = restart::describe(process_info)?;
+        if monitor.is_dry_run() {
+            status!(self, "🔁 Dry run: would restart {}", command);
+            return Ok(());
+        }
+        let pid = restart::respawn(process_info)?;
+        status!(self, "🔁 Restarted {} (PID {})", command, pid);
+        Ok(())
+    }
+
+    /// Poll a single port until it reaches `condition`, failing if `--timeout` elapses first.
+    async fn run_wait_for(&self, port: u16, condition: WaitCondition) -> Result<()> {
+        let deadline = self.args.timeout.map(|secs| Instant::now() + Duration::from_secs(secs));
+
+        loop {
+            let process_info = self.process_monitor.lock().await.get_process_on_port(port).await?;
+            match (condition, process_info) {
+                (WaitCondition::Free, None) => {
+                    status!(self, "✅ Port {} is free", port);
+                    return Ok(());
+                }
+                (WaitCondition::Listening, Some(process_info)) => {
+                    status!(self, "✅ Port {} is now listening", port);
+                    self.print_process(&process_info);
+                    return Ok(());
+                }
+                (_, process_info) => {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        let state = match process_info {
+                            Some(p) => format!("still held by {} (PID {})", p.name, p.pid),
+                            None => "still free".to_string(),
+                        };
+                        return Err(anyhow::anyhow!(
+                            "Timed out after {}s waiting for port {} to {}: {}",
+                            self.args.timeout.unwrap_or_default(),
+                            port,
+                            condition.describe(),
+                            state
+                        ));
+                    }
+                }
+            }
+
+            debug!("Port {} not yet {}, waiting...", port, condition.describe());
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Scan a single time, optionally kill what was found, and return.
+    async fn run_once(&self) -> Result<()> {
+        let monitor = self.process_monitor.lock().await;
+        let processes = monitor.scan_processes().await?;
+        let mut update = ProcessUpdate::new(processes.clone());
+        for process_info in update.processes.values_mut() {
+            monitor.enrich_docker_info(process_info).await;
+        }
+        self.print_update(&update);
+
+        if !self.args.auto_kill || processes.is_empty() {
+            return Ok(());
+        }
+
+        let kill_result = self.kill_found(&monitor, &processes).await.map(|_| ());
+        if !self.args.strict {
+            return kill_result;
+        }
+
+        // Under --strict, a re-scan decides the outcome rather than the kill results, so
+        // respawned or partially killed processes are still caught. Processes and containers
+        // take a moment to tear down after the signal, so poll before declaring failure.
+        if let Err(e) = kill_result {
+            error!("{}", e);
+        }
+        let deadline = Instant::now() + STRICT_SETTLE_TIMEOUT;
+        let mut remaining = monitor.scan_processes().await?;
+        while !remaining.is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+            remaining = monitor.scan_processes().await?;
+        }
+        if !remaining.is_empty() {
+            let mut failures: Vec<KillFailure> = remaining
+                .into_iter()
+                .map(|(endpoint, process_info)| KillFailure {
+                    pid: process_info.pid,
+                    endpoint: Some(endpoint),
+                    error: "still holds the port after the kill (--strict)".to_string(),
+                })
+                .collect();
+            failures.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+            return Err(KillsFailed::new(failures).into());
+        }
+
+        status!(self, "✅ All monitored ports verified free");
+        Ok(())
+    }
+
+    /// Scan once and kill everything found. Ports that are already free are a success too.
+    async fn run_kill(&self) -> Result<()> {
+        if let Some(pids) = &self.args.pids {
+            return self.run_kill_pids(pids).await;
+        }
+
+        let monitor = self.process_monitor.lock().await;
+        let processes = monitor.scan_processes().await?;
+        if processes.is_empty() {
+            status!(self, "✅ No processes found on {}, nothing to kill", self.args.get_port_description());
+            return Ok(());
+        }
+        if self.needs_confirmation() && self.ask_to_kill(&monitor, &processes) && !is_yes(&read_answer().await?) {
+            return Err(anyhow::anyhow!("Cancelled, nothing was killed"));
+        }
+        if self.kill_found(&monitor, &processes).await? == 0 {
+            return Err(anyhow::anyhow!("Every process found on {} is protected, nothing was killed", self.args.get_port_description()));
+        }
+        Ok(())
+    }
+
+    /// `--kill --pids`: kill each of `pids` without scanning, saying which weren't running
+    /// and so were left alone. Fails if any kill failed.
+    async fn run_kill_pids(&self, pids: &[i32]) -> Result<()> {
+        let monitor = self.process_monitor.lock().await;
+        let mut not_running = Vec::new();
+        let mut failed = Vec::new();
+        for &pid in pids {
+            if !monitor.is_process_running(pid).await {
+                not_running.push(pid.to_string());
+                continue;
+            }
+            match monitor.kill_process(pid).await {
+                Ok(()) => status!(self, "✅ {} PID {}", kill_verb(&monitor), pid),
+                Err(e) => {
+                    status!(self, "❌ Failed to kill PID {}: {}", pid, e);
+                    failed.push(KillFailure { pid, endpoint: None, error: e.to_string() });
+                }
+            }
+        }
+
+        if !not_running.is_empty() {
+            status!(self, "💤 Not running, nothing to kill: PID {}", not_running.join(", "));
+        }
+        if !failed.is_empty() {
+            return Err(KillsFailed::new(failed).into());
+        }
+        Ok(())
+    }
+
+    /// Kill everything in `processes` (a fresh scan) and report the outcome, returning how
+    /// many processes were signalled. Protected processes are left out.
+    async fn kill_found(&self, monitor: &ProcessMonitor, processes: &HashMap<Endpoint, ProcessInfo>) -> Result<usize> {
+        let attempts = monitor.kill_processes(processes).await?;
+        self.print_kill_attempts(&attempts);
+        monitor.kill_outcome(&attempts)?;
+
+        let ports: Vec<u16> = processes.keys().filter_map(Endpoint::port).collect();
+        status!(self, "🔪 {} {} process(es) on ports {}", kill_verb(monitor), attempts.len(), format_port_ranges(&ports));
+        Ok(attempts.len())
+    }
+
+    /// Report each kill of a kill-all: as `kill_result` lines with `--json`, otherwise only
+    /// the failures, since the summary line covers the rest.
+    fn print_kill_attempts(&self, attempts: &[KillAttempt]) {
+        for attempt in attempts {
+            if self.args.output_format() == OutputFormat::Json {
+                match serde_json::to_string(&ipc::ServerMessage::from(attempt)) {
+                    Ok(line) => println!("{}", line),
+                    Err(e) => error!("Failed to serialize kill result: {}", e),
+                }
+            } else if let Some(ref error) = attempt.error {
+                status!(self, "   ❌ Port {} (PID {}): {}", attempt.endpoint, attempt.pid, error);
+            }
+        }
+    }
+
+    fn print_update(&self, update: &ProcessUpdate) {
+        let format = self.args.output_format();
+        if format == OutputFormat::Json {
+            match serde_json::to_string(&ipc::ServerMessage::from(update)) {
+                Ok(line) => println!("{}", line),
+                Err(e) => error!("Failed to serialize update: {}", e),
+            }
+            return;
+        }
+        if let Some(delimiter) = format.delimiter() {
+            if !self.header_printed.swap(true, Ordering::Relaxed) {
+                println!("{}", delimited_line(DELIMITED_HEADER, delimiter));
+            }
+            print!("{}", render_delimited(&self.args.sort.sorted(update.processes.values()), delimiter));
+            return;
+        }
+
+        // Update status
+        let status_info = StatusBarInfo::from_update(update).with_free_reserved(&update.free_ports(&self.args.reserve));
+
+        if self.args.heatmap {
+            let occupied: HashSet<u16> = update.processes.keys().filter_map(Endpoint::port).collect();
+            // Clear the screen and home the cursor so the grid redraws in place
+            print!("\x1b[2J\x1b[H");
+            println!("🔄 Port Status: {} - {}", status_info.text, status_info.tooltip);
+            print!("{}", render_heatmap(&self.ports_to_monitor, &occupied, HEATMAP_WIDTH));
+            return;
+        }
+        
+        // Print status to console
+        status!(self, "🔄 Port Status: {} - {}", status_info.text, status_info.tooltip);
+        for restart in &update.restarted {
+            status!(self, "🔁 {}", restart);
+        }
+        
+        if self.args.table {
+            if update.count > 0 {
+                let processes = self.args.sort.sorted(update.processes.values());
+                let row_color = color_enabled().then_some(if update.count >= self.args.warn_count { RED } else { YELLOW });
+                status!(self, "{}", render_table(&processes, row_color));
+                status!(self, "{}", totals_line(&update.processes));
+            }
+        } else if self.args.show_free {
+            // List every monitored port in order, marking the ones nothing holds
+            status!(self, "📋 Monitored Ports:");
+            for &port in &self.ports_to_monitor {
+                for &protocol in self.args.protocol.protocols() {
+                    let endpoint = Endpoint::Port { port, protocol };
+                    match update.processes.get(&endpoint) {
+                        Some(process_info) => self.print_process(process_info),
+                        None => status!(self, "   • Port {}: (free)", endpoint),
+                    }
+                }
+            }
+            // Sockets are only known once something holds them, so there is no free list
+            let mut sockets: Vec<&ProcessInfo> =
+                update.processes.values().filter(|p| p.socket_path.is_some()).collect();
+            sockets.sort_by_key(|p| p.endpoint());
+            for process_info in sockets {
+                self.print_process(process_info);
+            }
+            status!(self, "{}", totals_line(&update.processes));
+            status!(self, "");
+        } else if update.count > 0 {
+            status!(self, "📋 Detected Processes:");
+            for process_info in self.args.sort.sorted(update.processes.values()) {
+                self.print_process(process_info);
+            }
+            status!(self, "{}", totals_line(&update.processes));
+            status!(self, "");
+        }
+
+        for (project, members) in compose::group_by_project(update.processes.values()) {
+            let ports: Vec<String> = members.iter().map(|p| p.endpoint().to_string()).collect();
+            status!(self, "🐳 Compose project {}: ports {} (stop all with --kill-compose-project {})",
+                    project, ports.join(", "), project);
+        }
+    }
+
+    fn print_process(&self, process_info: &ProcessInfo) {
+        let mut port = match process_info.endpoint() {
+            Endpoint::UnixSocket(path) => format!("Socket {}", path),
+            Endpoint::Port { .. } => format!("Port {}", endpoint_label(process_info)),
+            Endpoint::Connection { port, .. } => format!("Port {}", port),
+        };
+        // Listeners and clients share the listing, so say which is which
+        if self.args.include_established && process_info.socket_path.is_none() {
+            port = format!("[{}] {}", process_info.state, port);
+        }
+        if process_info.is_root() {
+            port.push_str(" (root)");
+        }
+        let usage = process_info.resource_usage().map(|usage| format!(" [{}]", usage)).unwrap_or_default();
+        if let (Some(_container_id), Some(container_name)) = (&process_info.container_id, &process_info.container_name) {
+            status!(self, "   • {}: {} - {} [Docker: {}]{}", 
+                    port, process_info.name, process_info.command, container_name, usage);
+        } else if self.args.show_pid {
+            let pids: Vec<String> = process_info.all_pids().map(|pid| pid.to_string()).collect();
+            status!(self, "   • {}: {} (PID {}) - {}{}", 
+                    port, process_info.name, pids.join(", "), process_info.command, usage);
+        } else {
+            status!(self, "   • {}: {} - {}{}", 
+                    port, process_info.name, process_info.command, usage);
+        }
+        if let Some(context) = process_info.context() {
+            status!(self, "     {}", context);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+    use nix::sys::signal::Signal;
+
+    fn process(port: u16, pid: i32, name: &str, command: &str, container: Option<&str>) -> ProcessInfo {
+        ProcessInfo {
+            command: command.to_string(),
+            name: name.to_string(),
+            container_id: container.map(|_| "abc123".to_string()),
+            container_name: container.map(str::to_string),
+            address: Some("127.0.0.1".parse().unwrap()),
+            ..process_on(port, pid)
+        }
+    }
+
+    #[test]
+    fn test_render_table() {
+        let node = process(3000, 4242, "node", "node server.js", None);
+        let long_command = format!("/usr/bin/python3 {}", "x".repeat(60));
+        let postgres = process(5432, 7, "postgres", &long_command, Some("db"));
+
+        let table = render_table(&[&node, &postgres], None);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "PORT            PID   NAME      COMMAND                                   DOCKER");
+        assert_eq!(lines[1], "127.0.0.1:3000  4242  node      node server.js                            -");
+        assert_eq!(lines[2], format!("127.0.0.1:5432  7     postgres  {}…  db", &long_command[..39]));
+
+        let colored = render_table(&[&node], Some(RED));
+        assert!(colored.starts_with("\x1b[1mPORT"), "{:?}", colored);
+        assert!(colored.ends_with("\x1b[31m127.0.0.1:3000  4242  node  node server.js  -\x1b[0m\n"), "{:?}", colored);
+    }
+
+    #[test]
+    fn test_render_delimited() {
+        let node = process(3000, 4242, "node", "node server.js --title \"a, b\"", None);
+        let postgres = process(5432, 7, "postgres", "postgres\t-D data", Some("db"));
+
+        assert_eq!(delimited_line(DELIMITED_HEADER, ','), "port,pid,name,command,container_name");
+        assert_eq!(
+            render_delimited(&[&node, &postgres], ','),
+            "3000,4242,node,\"node server.js --title \"\"a, b\"\"\",\n5432,7,postgres,postgres\t-D data,db\n"
+        );
+        assert_eq!(
+            render_delimited(&[&node, &postgres], '\t'),
+            "3000\t4242\tnode\tnode server.js --title \"a, b\"\t\n5432\t7\tpostgres\tpostgres -D data\tdb\n"
+        );
+    }
+
+    #[test]
+    fn test_render_heatmap() {
+        let ports: Vec<u16> = (3000..3010).collect();
+        let occupied = HashSet::from([3001, 3008]);
+        assert_eq!(render_heatmap(&ports, &occupied, 4), " 3000 .#..\n 3004 ....\n 3008 #.\n");
+    }
+
+    #[test]
+    fn test_parse_console_commands() {
+        assert_eq!(ConsoleCommand::parse("k 3000"), Ok(ConsoleCommand::KillPort(3000)));
+        assert_eq!(ConsoleCommand::parse("  ka "), Ok(ConsoleCommand::KillAll));
+        assert_eq!(ConsoleCommand::parse("r"), Ok(ConsoleCommand::Refresh));
+        assert_eq!(ConsoleCommand::parse("q"), Ok(ConsoleCommand::Quit));
+
+        assert_eq!(ConsoleCommand::parse("kr 3000"), Ok(ConsoleCommand::RestartPort(3000)));
+        assert!(ConsoleCommand::parse("k").is_err());
+        assert_eq!(ConsoleCommand::parse("kr"), Err("kr needs a port".to_string()));
+        assert!(ConsoleCommand::parse("k 0").is_err());
+        assert!(ConsoleCommand::parse("k abc").is_err());
+        assert!(ConsoleCommand::parse("q now").is_err());
+        assert!(ConsoleCommand::parse("kill 3000").is_err());
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let failed = KillsFailed::new(vec![KillFailure { pid: 42, endpoint: None, error: "EPERM".to_string() }]);
+        assert_eq!(exit_code(&failed.clone().into()), 2);
+        assert_eq!(exit_code(&anyhow::Error::from(failed.clone()).context("--kill")), 2);
+        assert_eq!(exit_code(&PortKillError::KillsFailed(failed).into()), 2);
+        assert_eq!(exit_code(&PortKillError::Refused { pid: 42, reason: "protected".to_string() }.into()), 1);
+        assert_eq!(exit_code(&anyhow::anyhow!("lsof failed")), 1);
+    }
+
+    #[test]
+    fn test_kill_port_exit_code() {
+        let process = process_on(3000, 42);
+        let refused = PortKillError::Refused { pid: 42, reason: "protected".to_string() };
+        assert_eq!(exit_code(&kill_port_error(&process, refused)), 1);
+        let watch_only = PortKillError::WatchOnly { action: "kill process 42".to_string() };
+        assert_eq!(exit_code(&kill_port_error(&process, watch_only)), 1);
+
+        let survived = PortKillError::StillRunning { pid: 42, signal: Signal::SIGTERM, timeout: Duration::from_millis(500) };
+        let error = kill_port_error(&process, survived);
+        assert_eq!(exit_code(&error), 2);
+        assert_eq!(error.downcast_ref::<KillsFailed>().unwrap().failures[0].endpoint, Some(Endpoint::tcp(3000)));
+    }
+
+    #[test]
+    fn test_totals_line() {
+        let process = |pid: i32, port: u16, protocol, container: Option<&str>| ProcessInfo {
+            protocol,
+            container_id: container.map(str::to_string),
+            container_name: container.map(str::to_string),
+            ..process_on(port, pid)
+        };
+        let mut processes: HashMap<Endpoint, ProcessInfo> = [
+            process(1, 3000, Protocol::Tcp, None),
+            process(2, 3000, Protocol::Udp, None),
+            process(3, 5432, Protocol::Tcp, Some("db")),
+        ]
+        .into_iter()
+        .map(|p| (p.endpoint(), p))
+        .collect();
+        assert_eq!(totals_line(&processes), "📊 3 processes across 2 ports, 1 Docker");
+
+        processes.retain(|_, p| p.pid == 3);
+        assert_eq!(totals_line(&processes), "📊 1 process across 1 port, 1 Docker");
+    }
+
+    #[test]
+    fn test_confirmation_question() {
+        let processes: HashMap<Endpoint, ProcessInfo> = [process_on(8080, 1), process_on(3000, 2), process_on(3001, 2), process_on(5432, 3)]
+            .into_iter()
+            .map(|p| (p.endpoint(), p))
+            .collect();
+
+        // A PID holding several ports is one process, and untargeted ones aren't mentioned
+        let targets = vec![&processes[&Endpoint::tcp(3000)], &processes[&Endpoint::tcp(8080)]];
+        assert_eq!(confirmation_question(&processes, &targets), "Kill 2 processes on ports 3000, 3001, 8080? [y/N] ");
+        assert_eq!(confirmation_question(&processes, &targets[..1]), "Kill 1 process on ports 3000, 3001? [y/N] ");
+        assert_eq!(confirmation_question(&processes, &targets[1..]), "Kill 1 process on port 8080? [y/N] ");
+
+        assert!(is_yes("y\n") && is_yes(" YES "));
+        assert!(!is_yes("") && !is_yes("\n") && !is_yes("n") && !is_yes("yes please"));
+    }
+
+    #[test]
+    fn test_require_name_refuses_partial_matches() {
+        let process_named = |name: &str| ProcessInfo {
+            command: name.to_string(),
+            name: name.to_string(),
+            ..process_on(3000, 123)
+        };
+
+        assert!(check_required_name("node", &process_named("node")).is_ok());
+        assert!(check_required_name("node*", &process_named("nodemon")).is_ok());
+
+        let refused = check_required_name("node", &process_named("node_exporter")).unwrap_err();
+        assert_eq!(
+            refused.to_string(),
+            "Refusing to kill node_exporter (PID 123) on 3000: name does not match 'node'"
+        );
+        assert!(check_required_name("node", &process_named("nodemon")).is_err());
+        assert!(check_required_name("n", &process_named("node")).is_err());
+    }
 }
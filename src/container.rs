@@ -0,0 +1,165 @@
+//! The container runtime CLI used to find and stop containers.
+//!
+//! Podman's CLI is a drop-in replacement for every `docker` subcommand used here, so the
+//! runtime mostly decides which binary runs. The exceptions are the flag selecting a remote
+//! daemon and `top`, which Podman reports with container-namespace PIDs unless asked for
+//! host PIDs.
+
+use crate::cli::{Args, ContainerRuntime};
+use crate::error::PortKillError;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// A runtime whose binary was chosen, either explicitly or by looking on `PATH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runtime {
+    Docker,
+    Podman,
+}
+
+impl Runtime {
+    pub fn program(self) -> &'static str {
+        match self {
+            Runtime::Docker => "docker",
+            Runtime::Podman => "podman",
+        }
+    }
+
+    /// The first of docker and podman found on `PATH`.
+    pub fn detect() -> Option<Self> {
+        let path = std::env::var_os("PATH")?;
+        [Runtime::Docker, Runtime::Podman]
+            .into_iter()
+            .find(|runtime| std::env::split_paths(&path).any(|dir| is_executable(&dir.join(runtime.program()))))
+    }
+
+    /// Flag naming the daemon to talk to: a Docker context or a Podman connection.
+    fn context_flag(self) -> &'static str {
+        match self {
+            Runtime::Docker => "--context",
+            Runtime::Podman => "--connection",
+        }
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Builds commands for the chosen runtime, against a specific context if one was given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerCli {
+    runtime: Runtime,
+    context: Option<String>,
+}
+
+impl Default for ContainerCli {
+    fn default() -> Self {
+        Self::new(Runtime::Docker, None)
+    }
+}
+
+impl ContainerCli {
+    pub fn new(runtime: Runtime, context: Option<String>) -> Self {
+        Self { runtime, context }
+    }
+
+    /// The runtime selected by `--container-runtime`, with `auto` picking whichever binary is
+    /// installed and falling back to docker so errors name a familiar command.
+    pub fn from_args(args: &Args) -> Self {
+        let runtime = match args.container_runtime {
+            ContainerRuntime::Docker => Runtime::Docker,
+            ContainerRuntime::Podman => Runtime::Podman,
+            ContainerRuntime::Auto => Runtime::detect().unwrap_or(Runtime::Docker),
+        };
+        Self::new(runtime, args.docker_context.clone())
+    }
+
+    pub fn runtime(&self) -> Runtime {
+        self.runtime
+    }
+
+    pub fn program(&self) -> &'static str {
+        self.runtime.program()
+    }
+
+    /// A command for the runtime, with the context selected.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(self.program());
+        if let Some(ref context) = self.context {
+            command.args([self.runtime.context_flag(), context]);
+        }
+        command
+    }
+
+    /// Run the runtime with `args`, the subcommand first. Only failing to start it is an
+    /// error here; callers check the exit status and can report it with [`Self::failure`].
+    pub fn output(&self, args: &[&str]) -> Result<Output, PortKillError> {
+        self.command().args(args).output().map_err(|source| PortKillError::Io {
+            action: format!("execute {} {} command", self.program(), args.first().unwrap_or(&"")),
+            source,
+        })
+    }
+
+    /// The error for `subcommand` exiting unsuccessfully with `output`.
+    pub fn failure(&self, subcommand: &str, output: &Output) -> PortKillError {
+        PortKillError::ContainerCommandFailed {
+            command: format!("{} {}", self.program(), subcommand),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+    }
+
+    /// The same command as a user would type it, e.g. `docker --context colima`.
+    pub fn command_line(&self) -> String {
+        match self.context {
+            Some(ref context) => format!("{} {} {}", self.program(), self.runtime.context_flag(), context),
+            None => self.program().to_string(),
+        }
+    }
+
+    /// Arguments for `top` listing a container's processes by host PID.
+    pub fn top_args<'a>(&self, container_id: &'a str) -> Vec<&'a str> {
+        match self.runtime {
+            Runtime::Docker => vec!["top", container_id],
+            Runtime::Podman => vec!["top", container_id, "hpid"],
+        }
+    }
+
+    /// The host PIDs in the output of [`Self::top_args`]. Docker prints `ps -ef` style
+    /// columns with the PID second; Podman prints only the requested `HPID` column.
+    pub fn parse_top_pids(&self, output: &str) -> Vec<i32> {
+        let column = match self.runtime {
+            Runtime::Docker => 1,
+            Runtime::Podman => 0,
+        };
+        output
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(|line| line.split_whitespace().nth(column)?.parse().ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        assert_eq!(ContainerCli::default().command_line(), "docker");
+        assert_eq!(ContainerCli::new(Runtime::Docker, Some("colima".to_string())).command_line(), "docker --context colima");
+        assert_eq!(ContainerCli::new(Runtime::Podman, Some("remote".to_string())).command_line(), "podman --connection remote");
+    }
+
+    #[test]
+    fn test_parse_top_pids() {
+        let docker = "UID   PID    PPID   C  STIME  TTY  TIME      CMD\nroot  4242   4200   0  10:00  ?    00:00:01  node server.js\n";
+        assert_eq!(ContainerCli::default().top_args("abc"), vec!["top", "abc"]);
+        assert_eq!(ContainerCli::default().parse_top_pids(docker), vec![4242]);
+
+        let podman = ContainerCli::new(Runtime::Podman, None);
+        assert_eq!(podman.top_args("abc"), vec!["top", "abc", "hpid"]);
+        assert_eq!(podman.parse_top_pids("HPID\n4242\n4250\n"), vec![4242, 4250]);
+    }
+}
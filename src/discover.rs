@@ -0,0 +1,158 @@
+//! Port discovery for `--discover`: work out which ports a project uses from files in its
+//! directory, so `port-kill --discover` in a repo needs no other flags.
+//!
+//! Sources, in order:
+//! - `.port-kill.toml` with `ports = [3000, "8000-8010"]`
+//! - the published (host-side) ports of a Compose file
+
+use crate::cli::parse_port_spec;
+use std::path::Path;
+
+/// Project config file read by discovery.
+pub const PROJECT_CONFIG_FILE: &str = ".port-kill.toml";
+
+/// Compose file names, checked in the order `docker compose` itself prefers them.
+pub const COMPOSE_FILES: &[&str] = &["compose.yaml", "compose.yml", "docker-compose.yaml", "docker-compose.yml"];
+
+/// Collect the ports declared by the project in `dir`, sorted and deduplicated.
+pub fn discover_ports(dir: &Path) -> Result<Vec<u16>, String> {
+    let mut ports = Vec::new();
+
+    let config_path = dir.join(PROJECT_CONFIG_FILE);
+    if config_path.is_file() {
+        let content = std::fs::read_to_string(&config_path)
+            .map_err(|e| format!("Failed to read {}: {}", config_path.display(), e))?;
+        let config_ports = parse_project_config(&content)
+            .map_err(|e| format!("Invalid {}: {}", config_path.display(), e))?;
+        ports.extend(config_ports);
+    }
+
+    // Only the first Compose file counts, matching how `docker compose` picks one
+    if let Some(compose_path) = COMPOSE_FILES.iter().map(|name| dir.join(name)).find(|path| path.is_file()) {
+        let content = std::fs::read_to_string(&compose_path)
+            .map_err(|e| format!("Failed to read {}: {}", compose_path.display(), e))?;
+        ports.extend(parse_compose_ports(&content));
+    }
+
+    ports.sort_unstable();
+    ports.dedup();
+    Ok(ports)
+}
+
+/// Parse the `ports` array of a `.port-kill.toml`, accepting numbers and range strings.
+pub fn parse_project_config(content: &str) -> Result<Vec<u16>, String> {
+    let value: toml::Value = toml::from_str(content).map_err(|e| e.to_string())?;
+    let Some(entries) = value.get("ports") else {
+        return Ok(Vec::new());
+    };
+    let entries = entries.as_array().ok_or("'ports' must be an array")?;
+
+    let mut ports = Vec::new();
+    for entry in entries {
+        let spec = match entry {
+            toml::Value::Integer(port) => port.to_string(),
+            toml::Value::String(spec) => spec.clone(),
+            other => return Err(format!("'{}' is not a port or range", other)),
+        };
+        ports.extend(parse_port_spec(&spec)?);
+    }
+    Ok(ports)
+}
+
+/// Extract host-published ports from a Compose file.
+///
+/// This is a line-based scan of `ports:` blocks rather than a full YAML parse. It handles the
+/// short syntax (`"8080:80"`, `"127.0.0.1:5432:5432"`, `"3000-3005:3000-3005"`), flow lists
+/// (`ports: ["8080:80"]`) and the long syntax's `published:` key. Container-only entries such
+/// as `"3000"` publish a random host port and are skipped, as is anything unparseable.
+pub fn parse_compose_ports(content: &str) -> Vec<u16> {
+    let mut ports = Vec::new();
+    // Indentation of the `ports:` key whose block we are inside, if any
+    let mut block_indent: Option<usize> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+
+        if block_indent.is_some_and(|block| indent <= block) {
+            block_indent = None;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("ports:") {
+            let rest = rest.trim();
+            match rest.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                Some(items) => items.split(',').for_each(|item| ports.extend(short_syntax_host_ports(item))),
+                None => block_indent = Some(indent),
+            }
+            continue;
+        }
+
+        if block_indent.is_none() {
+            continue;
+        }
+
+        let item = trimmed.strip_prefix('-').map(str::trim).unwrap_or(trimmed);
+        if let Some(published) = item.strip_prefix("published:") {
+            ports.extend(parse_port_spec(unquote(published)).unwrap_or_default());
+        } else if trimmed.starts_with('-') && !item.contains(": ") {
+            ports.extend(short_syntax_host_ports(item));
+        }
+    }
+
+    ports
+}
+
+/// Host ports of a short-syntax entry: `[IP:]HOST:CONTAINER[/PROTO]`.
+fn short_syntax_host_ports(entry: &str) -> Vec<u16> {
+    let entry = unquote(entry);
+    // Drop the container side, then whatever precedes the host side is a bind address
+    let Some((published, _container)) = entry.rsplit_once(':') else {
+        return Vec::new();
+    };
+    let host = published.rsplit_once(':').map_or(published, |(_, host)| host);
+    parse_port_spec(host).unwrap_or_default()
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches(|c| c == '"' || c == '\'')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compose_ports() {
+        let compose = r#"
+services:
+  web:
+    image: nginx
+    ports:
+      - "8080:80"
+      - 127.0.0.1:5432:5432
+      - "3000-3002:3000-3002/tcp"
+      - "9229"
+    environment:
+      - "PORT=1234"
+  api:
+    ports: ["4000:4000"]
+  worker:
+    ports:
+      - target: 80
+        published: "9000"
+        protocol: tcp
+"#;
+        assert_eq!(parse_compose_ports(compose), vec![8080, 5432, 3000, 3001, 3002, 4000, 9000]);
+    }
+
+    #[test]
+    fn test_parse_project_config() {
+        assert_eq!(parse_project_config(r#"ports = [3000, "8000-8002"]"#).unwrap(), vec![3000, 8000, 8001, 8002]);
+        assert_eq!(parse_project_config("").unwrap(), Vec::<u16>::new());
+        assert!(parse_project_config("ports = [true]").is_err());
+        assert!(parse_project_config("ports = 3000").is_err());
+    }
+}
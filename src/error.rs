@@ -0,0 +1,163 @@
+//! [`PortKillError`], what [`ProcessMonitor`](crate::process_monitor::ProcessMonitor) methods
+//! and [`PortScanner`](crate::scanner::PortScanner)s fail with, so library callers can tell a
+//! missing scanner from a refused or failed kill without matching on messages. The binaries
+//! report them through `anyhow` like any other error.
+
+use crate::scanner::{LsofFailed, LsofPermissionDenied};
+use crate::types::Endpoint;
+use nix::sys::signal::Signal;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PortKillError {
+    /// The scanner's binary, e.g. lsof, isn't installed or couldn't be started
+    #[error("{scanner} is not available, install it or check it is on PATH: {source}")]
+    ScannerUnavailable {
+        scanner: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Running a command such as `ps` or `docker`, or reading what the system reports under
+    /// `/proc`, failed
+    #[error("Failed to {action}: {source}")]
+    Io {
+        action: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// The scanner was refused access to process information
+    #[error(transparent)]
+    PermissionDenied(#[from] LsofPermissionDenied),
+    /// The scanner kept failing, so the scan was abandoned
+    #[error(transparent)]
+    ScanFailed(#[from] LsofFailed),
+    /// docker or podman ran but reported an error, usually because its daemon isn't running
+    #[error("{command} failed: {stderr}")]
+    ContainerCommandFailed { command: String, stderr: String },
+    /// Nothing holds the port
+    #[error("No process is listening on port {0}")]
+    NoProcess(u16),
+    /// `--watch-only` forbids killing anything
+    #[error("Refusing to {action}: killing is disabled by --watch-only")]
+    WatchOnly { action: String },
+    /// The process must not be killed: it is protected, excluded, only a client, or no
+    /// longer holds the port it was scanned on
+    #[error("Refusing to kill process {pid}: {reason}")]
+    Refused { pid: i32, reason: String },
+    /// The signal couldn't be sent to the process
+    #[error("Failed to send {signal} to process {pid}: {source}")]
+    KillFailed {
+        pid: i32,
+        signal: Signal,
+        #[source]
+        source: nix::Error,
+    },
+    /// The process outlived the signal and `--no-sigkill` rules out escalating
+    #[error("Process {pid} still running {}ms after {signal} (SIGKILL disabled by --no-sigkill)", .timeout.as_millis())]
+    StillRunning { pid: i32, signal: Signal, timeout: Duration },
+    /// Neither stopping nor force-removing the container worked
+    #[error("Failed to stop container {container}: {stderr}")]
+    ContainerStopFailed { container: String, stderr: String },
+    /// Some kills of a kill-all failed
+    #[error(transparent)]
+    KillsFailed(#[from] KillsFailed),
+    /// An option has a value that can't be acted on, e.g. an unknown `--signal`
+    #[error("{0}")]
+    InvalidArgument(String),
+}
+
+impl PortKillError {
+    /// Classify a failure to start `program` for a scan: not installed, or anything else.
+    pub(crate) fn from_command(program: &'static str, source: std::io::Error) -> Self {
+        if source.kind() == std::io::ErrorKind::NotFound {
+            Self::ScannerUnavailable { scanner: program, source }
+        } else {
+            Self::Io { action: format!("execute {} command", program), source }
+        }
+    }
+
+    /// Whether a process was meant to die and didn't, as opposed to a kill that was refused
+    /// or never got as far as the process.
+    pub fn is_kill_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::KillFailed { .. } | Self::StillRunning { .. } | Self::ContainerStopFailed { .. } | Self::KillsFailed(_)
+        )
+    }
+}
+
+/// A kill that didn't go through, for [`KillsFailed`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct KillFailure {
+    pub pid: i32,
+    /// Where the process was found, unless it was named by PID
+    pub endpoint: Option<Endpoint>,
+    pub error: String,
+}
+
+impl fmt::Display for KillFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.endpoint {
+            Some(endpoint) => write!(f, "Port {} (PID {}): {}", endpoint, self.pid, self.error),
+            None => write!(f, "PID {}: {}", self.pid, self.error),
+        }
+    }
+}
+
+/// The scan worked but some kills didn't: processes survived, or couldn't be signalled.
+/// Kept apart from other errors so one-shot runs can exit with a distinct code.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub struct KillsFailed {
+    pub failures: Vec<KillFailure>,
+    /// `--fail-fast` gave up at the first failure, leaving the remaining kills unattempted
+    pub stopped_early: bool,
+}
+
+impl KillsFailed {
+    pub fn new(failures: Vec<KillFailure>) -> Self {
+        Self { failures, stopped_early: false }
+    }
+}
+
+impl fmt::Display for KillsFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failures: Vec<String> = self.failures.iter().map(KillFailure::to_string).collect();
+        if self.stopped_early {
+            write!(f, "Stopped after the first failed kill: {}", failures.join("; "))
+        } else {
+            write!(f, "Some processes failed to kill: {}", failures.join("; "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_errors_are_classified() {
+        let missing = PortKillError::from_command("lsof", std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(matches!(missing, PortKillError::ScannerUnavailable { scanner: "lsof", .. }));
+
+        let denied = PortKillError::from_command("ps", std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert!(matches!(denied, PortKillError::Io { .. }));
+        assert!(denied.to_string().starts_with("Failed to execute ps command"), "{}", denied);
+
+        let denied: PortKillError = LsofPermissionDenied::from_stderr("lsof: Operation not permitted").unwrap().into();
+        assert!(denied.to_string().starts_with("lsof was denied access"));
+        assert!(!denied.is_kill_failure());
+    }
+
+    #[test]
+    fn test_kills_failed_lists_each_failure() {
+        let mut failed = KillsFailed::new(vec![
+            KillFailure { pid: 42, endpoint: Some(Endpoint::tcp(3000)), error: "EPERM".to_string() },
+            KillFailure { pid: 43, endpoint: None, error: "ESRCH".to_string() },
+        ]);
+        assert_eq!(failed.to_string(), "Some processes failed to kill: Port 3000 (PID 42): EPERM; PID 43: ESRCH");
+        failed.stopped_early = true;
+        assert!(failed.to_string().starts_with("Stopped after the first failed kill: Port 3000"));
+        assert!(PortKillError::from(failed).is_kill_failure());
+    }
+}
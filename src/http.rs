@@ -0,0 +1,420 @@
+//! Minimal HTTP/1.1 endpoint for tools that would rather not speak the `--socket` framing,
+//! such as an editor on another machine querying a remote dev box.
+//!
+//! Two routes are served, both answering with the same JSON messages as the socket:
+//! - `GET /processes` returns the latest [`ServerMessage::Update`]
+//! - `POST /kill/{pid}` kills a process holding a monitored port, returning a
+//!   [`ServerMessage::KillResult`]
+//!
+//! Kills are refused with 403 when the request carries an `Origin` header or a `Host` other
+//! than the address being served, so a web page can't trigger them from the user's browser,
+//! and, under `--require-token`, unless the `X-Port-Kill-Confirm` header carries the token.
+//!
+//! Every response closes the connection, so request bodies and keep-alive never need handling.
+
+use crate::{
+    ipc::{self, ClientMessage, ServerMessage},
+    process_monitor::ProcessMonitor,
+    types::ProcessUpdate,
+};
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{watch, Mutex};
+
+/// Request heads larger than this are rejected rather than buffered.
+const MAX_REQUEST_HEAD_LEN: u64 = 8 * 1024;
+
+/// How long a client gets to send its request head before the connection is dropped.
+const REQUEST_READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The header `POST /kill/{pid}` must carry the `--require-token` value in.
+pub const CONFIRM_TOKEN_HEADER: &str = "X-Port-Kill-Confirm";
+
+/// A request's method, path without its query, and headers.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Request {
+    pub method: String,
+    pub path: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// The value of header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(header, _)| header.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Who may kill through [`serve`]: requests made directly to `addr`, carrying `token` if one
+/// is required.
+#[derive(Debug, Clone)]
+struct KillAccess {
+    addr: SocketAddr,
+    token: Option<String>,
+}
+
+impl KillAccess {
+    /// Why `request` may not kill anything, if it may not.
+    fn refusal(&self, request: &Request) -> Option<String> {
+        if request.header("Origin").is_some() {
+            return Some("Kills from web pages are not accepted".to_string());
+        }
+        match request.header("Host") {
+            Some(host) if host_matches(host, self.addr) => {}
+            _ => return Some(format!("Kills must be addressed to {}", self.addr)),
+        }
+        match self.token {
+            Some(ref token) if request.header(CONFIRM_TOKEN_HEADER) != Some(token.as_str()) => {
+                Some(format!("{} does not match the value given to --require-token", CONFIRM_TOKEN_HEADER))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Whether a `Host` header names `addr`: its port, and its IP, or `localhost` when `addr` is
+/// a loopback address. Any name is accepted when serving on all interfaces.
+fn host_matches(host: &str, addr: SocketAddr) -> bool {
+    let (name, port) = match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => (name, port.parse::<u16>().ok()),
+        _ => (host, Some(80)),
+    };
+    if port != Some(addr.port()) {
+        return false;
+    }
+    if addr.ip().is_unspecified() {
+        return true;
+    }
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+    match name.parse::<IpAddr>() {
+        Ok(ip) => ip == addr.ip(),
+        Err(_) => name.eq_ignore_ascii_case("localhost") && addr.ip().is_loopback(),
+    }
+}
+
+/// A complete response being built.
+type ResponseFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send>>;
+
+/// Listen on `addr` and answer requests until `shutdown` is set to `true` or its sender is
+/// dropped. Kills must carry `required_token`, the `--require-token` value, if there is one.
+pub async fn serve(
+    addr: SocketAddr,
+    updates: watch::Receiver<ProcessUpdate>,
+    monitor: Arc<Mutex<ProcessMonitor>>,
+    required_token: Option<String>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    if !addr.ip().is_loopback() {
+        warn!("HTTP server on {} is reachable from other machines and lets them kill processes", addr);
+    }
+    let access = KillAccess { addr, token: required_token };
+    serve_with(addr, "HTTP", shutdown, api(updates, monitor, access)).await
+}
+
+/// The handler for [`serve`]'s routes.
+fn api(
+    updates: watch::Receiver<ProcessUpdate>,
+    monitor: Arc<Mutex<ProcessMonitor>>,
+    access: KillAccess,
+) -> impl Fn(Request) -> ResponseFuture + Clone + Send + Sync + 'static {
+    move |request| {
+        let (updates, monitor, access) = (updates.clone(), monitor.clone(), access.clone());
+        Box::pin(async move {
+            let (status, message) = route(&request, &updates, &monitor, &access).await;
+            response(status, &message)
+        })
+    }
+}
+
+/// Listen on `addr`, answering each request with the complete response `handler` builds from
+/// it, until `shutdown` is set to `true` or its sender is dropped. `name` says what is being
+/// served in log lines.
+pub(crate) async fn serve_with<H, F>(addr: SocketAddr, name: &str, mut shutdown: watch::Receiver<bool>, handler: H) -> Result<()>
+where
+    H: Fn(Request) -> F + Clone + Send + Sync + 'static,
+    F: Future<Output = Result<Vec<u8>>> + Send,
+{
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind {} server to {}", name, addr))?;
+    info!("Serving {} on {}", name, addr);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => return Err(e.into()),
+            },
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        let handler = handler.clone();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, REQUEST_READ_TIMEOUT, handler).await {
+                warn!("{} request failed: {}", name, e);
+            }
+        });
+    }
+}
+
+/// Answer the request on `stream`, dropping the connection if its head takes longer than
+/// `read_timeout` to arrive.
+async fn handle_connection<H, F>(stream: TcpStream, read_timeout: Duration, handler: H) -> Result<()>
+where
+    H: Fn(Request) -> F,
+    F: Future<Output = Result<Vec<u8>>>,
+{
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader.take(MAX_REQUEST_HEAD_LEN));
+
+    let head = tokio::time::timeout(read_timeout, read_request_head(&mut reader))
+        .await
+        .with_context(|| format!("No request received within {}ms", read_timeout.as_millis()))??;
+
+    let response = match head {
+        Some((request_line, headers)) => match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+            [method, path, _version] => {
+                let path = path.split('?').next().unwrap_or(path);
+                handler(Request { method: method.to_string(), path: path.to_string(), headers }).await?
+            }
+            _ => bad_request_response("Malformed request line")?,
+        },
+        None => bad_request_response("Incomplete or oversized request head")?,
+    };
+
+    writer.write_all(&response).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
+
+/// The request line and headers, or `None` if the head ended before its blank line.
+async fn read_request_head<R>(reader: &mut R) -> Result<Option<(String, Vec<(String, String)>)>>
+where
+    R: AsyncBufReadExt + Unpin,
+{
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    let mut headers = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? > 0 {
+        let header = line.trim_end();
+        if header.is_empty() {
+            return Ok(Some((request_line, headers)));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        line.clear();
+    }
+    Ok(None)
+}
+
+fn bad_request_response(message: &str) -> Result<Vec<u8>> {
+    let (status, message) = bad_request(message);
+    response(status, &message)
+}
+
+/// The status code and body answering `request`.
+async fn route(
+    request: &Request,
+    updates: &watch::Receiver<ProcessUpdate>,
+    monitor: &Arc<Mutex<ProcessMonitor>>,
+    access: &KillAccess,
+) -> (u16, ServerMessage) {
+    let (method, path) = (request.method.as_str(), request.path.as_str());
+    if path == "/processes" {
+        if method != "GET" {
+            return method_not_allowed("GET");
+        }
+        return (200, ServerMessage::from(&*updates.borrow()));
+    }
+
+    if let Some(pid) = path.strip_prefix("/kill/") {
+        if method != "POST" {
+            return method_not_allowed("POST");
+        }
+        let Ok(pid) = pid.parse::<i32>() else {
+            return bad_request(&format!("'{}' is not a valid PID", pid));
+        };
+        if let Some(reason) = access.refusal(request) {
+            warn!("Refused HTTP kill of PID {}: {}", pid, reason);
+            return (403, ServerMessage::Error { message: reason });
+        }
+        let result = ipc::handle_request(ClientMessage::Kill { pid }, monitor).await;
+        let status = match result {
+            ServerMessage::KillResult { success: true, .. } => 200,
+            _ => 409,
+        };
+        return (status, result);
+    }
+
+    (404, ServerMessage::Error { message: format!("No route for {}", path) })
+}
+
+fn bad_request(message: &str) -> (u16, ServerMessage) {
+    (400, ServerMessage::Error { message: message.to_string() })
+}
+
+fn method_not_allowed(allowed: &str) -> (u16, ServerMessage) {
+    (405, ServerMessage::Error { message: format!("Only {} is supported here", allowed) })
+}
+
+/// A complete HTTP response carrying `message` as JSON.
+fn response(status: u16, message: &ServerMessage) -> Result<Vec<u8>> {
+    let body = serde_json::to_vec(message).context("Failed to serialize response")?;
+    Ok(raw_response(status, "application/json", body))
+}
+
+/// A complete HTTP response carrying `body`.
+pub(crate) fn raw_response(status: u16, content_type: &str, body: Vec<u8>) -> Vec<u8> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "",
+    };
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len()
+    )
+    .into_bytes();
+    response.extend(body);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor() -> Arc<Mutex<ProcessMonitor>> {
+        let (sender, _receiver) = crossbeam_channel::bounded(1);
+        Arc::new(Mutex::new(ProcessMonitor::new(sender, vec![3000], false).unwrap()))
+    }
+
+    fn access(token: Option<&str>) -> KillAccess {
+        KillAccess { addr: "127.0.0.1:8080".parse().unwrap(), token: token.map(str::to_string) }
+    }
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)]) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let (_sender, updates) = watch::channel(ProcessUpdate::empty());
+        let monitor = monitor();
+        let access = access(None);
+        let route = |method, path| {
+            let request = request(method, path, &[("Host", "127.0.0.1:8080")]);
+            let (updates, monitor, access) = (updates.clone(), monitor.clone(), access.clone());
+            async move { route(&request, &updates, &monitor, &access).await }
+        };
+
+        let (status, message) = route("GET", "/processes").await;
+        assert_eq!(status, 200);
+        assert_eq!(message, ServerMessage::Update { processes: Vec::new(), count: 0 });
+
+        // Kills go through the socket's checks, so only monitored PIDs can be killed
+        let (status, message) = route("POST", "/kill/1").await;
+        assert_eq!(status, 409);
+        assert!(matches!(message, ServerMessage::KillResult { success: false, .. }));
+
+        assert_eq!(route("GET", "/kill/1").await.0, 405);
+        assert_eq!(route("POST", "/kill/node").await.0, 400);
+        assert_eq!(route("GET", "/").await.0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_kills_need_direct_requests() {
+        let (_sender, updates) = watch::channel(ProcessUpdate::empty());
+        let monitor = monitor();
+
+        let from_page = request("POST", "/kill/1", &[("Host", "127.0.0.1:8080"), ("Origin", "https://example.com")]);
+        assert_eq!(route(&from_page, &updates, &monitor, &access(None)).await.0, 403);
+        let rebound = request("POST", "/kill/1", &[("Host", "attacker.example:8080")]);
+        assert_eq!(route(&rebound, &updates, &monitor, &access(None)).await.0, 403);
+        assert_eq!(route(&request("POST", "/kill/1", &[]), &updates, &monitor, &access(None)).await.0, 403);
+
+        let unconfirmed = request("POST", "/kill/1", &[("host", "localhost:8080")]);
+        let (status, message) = route(&unconfirmed, &updates, &monitor, &access(Some("ship-it"))).await;
+        assert_eq!(status, 403);
+        assert!(matches!(message, ServerMessage::Error { ref message } if message.contains("--require-token")));
+        let confirmed = request("POST", "/kill/1", &[("host", "localhost:8080"), ("x-port-kill-confirm", "ship-it")]);
+        assert_eq!(route(&confirmed, &updates, &monitor, &access(Some("ship-it"))).await.0, 409);
+
+        // Reads stay open to anything that can reach the server
+        let from_page = request("GET", "/processes", &[("Origin", "https://example.com")]);
+        assert_eq!(route(&from_page, &updates, &monitor, &access(Some("ship-it"))).await.0, 200);
+    }
+
+    #[test]
+    fn test_host_matches() {
+        let loopback: SocketAddr = "127.0.0.1:8080".parse().unwrap();
+        assert!(host_matches("127.0.0.1:8080", loopback));
+        assert!(host_matches("LocalHost:8080", loopback));
+        assert!(!host_matches("127.0.0.1:9090", loopback));
+        assert!(!host_matches("127.0.0.1", loopback));
+        assert!(!host_matches("evil.example:8080", loopback));
+
+        assert!(host_matches("[::1]:80", "[::1]:80".parse().unwrap()));
+        assert!(host_matches("[::1]", "[::1]:80".parse().unwrap()));
+        assert!(host_matches("devbox.lan:8080", "0.0.0.0:8080".parse().unwrap()));
+        assert!(!host_matches("localhost:8080", "192.168.1.5:8080".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_serves_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (_sender, updates) = watch::channel(ProcessUpdate::empty());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            handle_connection(stream, REQUEST_READ_TIMEOUT, api(updates, monitor(), KillAccess { addr, token: None })).await.unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"GET /processes HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        server.await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.ends_with(r#"{"type":"update","processes":[],"count":0}"#), "{}", response);
+    }
+
+    #[tokio::test]
+    async fn test_silent_clients_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let (_sender, updates) = watch::channel(ProcessUpdate::empty());
+        let handler = api(updates, monitor(), KillAccess { addr, token: None });
+        let error = handle_connection(stream, Duration::from_millis(50), handler).await.unwrap_err();
+        assert!(error.to_string().starts_with("No request received within"), "{}", error);
+    }
+}
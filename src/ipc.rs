@@ -0,0 +1,319 @@
+//! Length-prefixed message protocol for editor extensions and other tooling.
+//!
+//! Every frame on the `--socket` is a 4-byte big-endian length followed by that many
+//! bytes of JSON. The server pushes a [`ServerMessage::Update`] on connect and on every
+//! change; clients send [`ClientMessage`]s to request kills.
+
+use crate::{
+    error::PortKillError,
+    process_monitor::ProcessMonitor,
+    types::{KillAttempt, ProcessInfo, ProcessUpdate},
+};
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{watch, Mutex};
+
+/// Frames larger than this are rejected to guard against corrupt length prefixes.
+pub const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+/// Messages sent from port-kill to a connected client.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Full snapshot of the processes currently holding monitored ports.
+    Update {
+        processes: Vec<ProcessInfo>,
+        count: usize,
+    },
+    /// Outcome of a `kill` or `kill_all` request, or of one kill in a `--once --auto-kill`
+    /// run with `--json`.
+    KillResult {
+        pid: Option<i32>,
+        success: bool,
+        error: Option<String>,
+    },
+    /// The client sent something that could not be handled.
+    Error { message: String },
+}
+
+/// Messages sent from a client to port-kill.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Kill a single process by PID.
+    Kill { pid: i32 },
+    /// Kill every process on the monitored ports.
+    KillAll,
+}
+
+impl From<&ProcessUpdate> for ServerMessage {
+    fn from(update: &ProcessUpdate) -> Self {
+        // Ordered by endpoint so consecutive lines of an unchanged set are identical
+        let mut processes: Vec<ProcessInfo> = update.processes.values().cloned().collect();
+        processes.sort_by_key(ProcessInfo::endpoint);
+        ServerMessage::Update {
+            processes,
+            count: update.count,
+        }
+    }
+}
+
+impl From<&KillAttempt> for ServerMessage {
+    fn from(attempt: &KillAttempt) -> Self {
+        ServerMessage::KillResult {
+            pid: Some(attempt.pid),
+            success: attempt.error.is_none(),
+            error: attempt.error.clone(),
+        }
+    }
+}
+
+/// Write a single length-prefixed JSON frame.
+pub async fn write_frame<W, T>(writer: &mut W, message: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let body = serde_json::to_vec(message).context("Failed to serialize frame")?;
+    let len = u32::try_from(body.len()).context("Frame too large")?;
+    writer.write_all(&len.to_be_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read a single length-prefixed JSON frame, returning `Ok(None)` on a clean EOF.
+pub async fn read_frame<R, T>(reader: &mut R) -> Result<Option<T>>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut len_bytes = [0u8; 4];
+    match reader.read_exact(&mut len_bytes).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let len = u32::from_be_bytes(len_bytes);
+    if len > MAX_FRAME_LEN {
+        return Err(anyhow::anyhow!("Frame of {} bytes exceeds limit of {}", len, MAX_FRAME_LEN));
+    }
+
+    let mut body = vec![0u8; len as usize];
+    reader.read_exact(&mut body).await?;
+    let message = serde_json::from_slice(&body).context("Failed to parse frame")?;
+    Ok(Some(message))
+}
+
+/// Listen on a Unix socket and serve the framed protocol to every client that connects,
+/// until `shutdown` is set to `true` or its sender is dropped. The socket file is removed on
+/// the way out.
+pub async fn serve(
+    path: &Path,
+    updates: watch::Receiver<ProcessUpdate>,
+    monitor: Arc<Mutex<ProcessMonitor>>,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    remove_stale_socket(path)?;
+
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind socket {}", path.display()))?;
+    info!("Listening for clients on {}", path.display());
+
+    let result = loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => break Err(e.into()),
+            },
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    break Ok(());
+                }
+                continue;
+            }
+        };
+        let updates = updates.clone();
+        let monitor = monitor.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, updates, monitor).await {
+                warn!("Socket client disconnected with error: {}", e);
+            }
+        });
+    };
+
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!("Failed to remove socket {}: {}", path.display(), e);
+    }
+    result
+}
+
+/// A socket file left behind by a previous run would make bind fail, so remove it. Anything
+/// else at the path is left alone, so a mistyped `--socket` can't delete a regular file.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(anyhow::anyhow!("{} already exists and is not a socket", path.display()));
+    }
+    std::fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {}", path.display()))
+}
+
+async fn handle_client(
+    stream: UnixStream,
+    mut updates: watch::Receiver<ProcessUpdate>,
+    monitor: Arc<Mutex<ProcessMonitor>>,
+) -> Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+
+    // Send the current snapshot straight away so clients don't wait for the next change
+    let snapshot = ServerMessage::from(&*updates.borrow_and_update());
+    write_frame(&mut writer, &snapshot).await?;
+
+    loop {
+        tokio::select! {
+            changed = updates.changed() => {
+                if changed.is_err() {
+                    // The console app has shut down
+                    return Ok(());
+                }
+                let message = ServerMessage::from(&*updates.borrow_and_update());
+                write_frame(&mut writer, &message).await?;
+            }
+            request = read_frame::<_, ClientMessage>(&mut reader) => {
+                let response = match request {
+                    Ok(Some(request)) => handle_request(request, &monitor).await,
+                    Ok(None) => return Ok(()),
+                    Err(e) => ServerMessage::Error { message: e.to_string() },
+                };
+                write_frame(&mut writer, &response).await?;
+            }
+        }
+    }
+}
+
+pub(crate) async fn handle_request(request: ClientMessage, monitor: &Arc<Mutex<ProcessMonitor>>) -> ServerMessage {
+    let (pid, result) = match request {
+        ClientMessage::Kill { pid } => {
+            let monitor = monitor.lock().await;
+            // Like the tray menu, only act on PIDs from the current scan, never arbitrary ones
+            let result = if monitor.is_monitored_pid(pid) {
+                monitor.kill_process(pid).await
+            } else {
+                Err(PortKillError::Refused { pid, reason: "it is not holding a monitored port".to_string() })
+            };
+            (Some(pid), result)
+        }
+        ClientMessage::KillAll => (None, monitor.lock().await.kill_all_processes().await),
+    };
+
+    if let Err(ref e) = result {
+        error!("Socket kill request failed: {}", e);
+    }
+
+    ServerMessage::KillResult {
+        pid,
+        success: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+
+    #[tokio::test]
+    async fn test_frame_round_trip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        write_frame(&mut client, &ClientMessage::Kill { pid: 123 }).await.unwrap();
+        write_frame(&mut client, &ClientMessage::KillAll).await.unwrap();
+        drop(client);
+
+        let first: Option<ClientMessage> = read_frame(&mut server).await.unwrap();
+        let second: Option<ClientMessage> = read_frame(&mut server).await.unwrap();
+        let eof: Option<ClientMessage> = read_frame(&mut server).await.unwrap();
+
+        assert_eq!(first, Some(ClientMessage::Kill { pid: 123 }));
+        assert_eq!(second, Some(ClientMessage::KillAll));
+        assert_eq!(eof, None);
+    }
+
+    #[tokio::test]
+    async fn test_frame_rejects_oversized_length() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(&(MAX_FRAME_LEN + 1).to_be_bytes()).await.unwrap();
+
+        let result: Result<Option<ClientMessage>> = read_frame(&mut server).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stale_socket_removal_spares_regular_files() {
+        let dir = std::env::temp_dir().join(format!("port-kill-ipc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let file = dir.join("Cargo.toml");
+        std::fs::write(&file, "not a socket").unwrap();
+        assert!(remove_stale_socket(&file).is_err());
+        assert!(file.exists());
+
+        let socket = dir.join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+        remove_stale_socket(&socket).unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kill_rejects_unmonitored_pid() {
+        let (sender, _receiver) = crossbeam_channel::bounded(1);
+        let monitor = Arc::new(Mutex::new(ProcessMonitor::new(sender, vec![3000], false).unwrap()));
+
+        let response = handle_request(ClientMessage::Kill { pid: 1 }, &monitor).await;
+        match response {
+            ServerMessage::KillResult { success, error, .. } => {
+                assert!(!success);
+                assert!(error.unwrap().contains("not holding a monitored port"));
+            }
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_message_schema() {
+        let json = serde_json::to_string(&ClientMessage::Kill { pid: 42 }).unwrap();
+        assert_eq!(json, r#"{"type":"kill","pid":42}"#);
+
+        let json = serde_json::to_string(&ClientMessage::KillAll).unwrap();
+        assert_eq!(json, r#"{"type":"kill_all"}"#);
+    }
+
+    #[test]
+    fn test_update_lists_processes_by_endpoint() {
+        let processes = [8080, 3000, 5000]
+            .into_iter()
+            .map(|port| {
+                let process = process_on(port, port as i32);
+                (process.endpoint(), process)
+            })
+            .collect();
+
+        let ServerMessage::Update { processes, count } = ServerMessage::from(&ProcessUpdate::new(processes)) else {
+            panic!("expected an update message");
+        };
+        assert_eq!(count, 3);
+        assert_eq!(processes.iter().map(|p| p.port).collect::<Vec<_>>(), vec![3000, 5000, 8080]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Kill history for `--log-file`, one JSON object per line.
+//!
+//! Every kill that is attempted is appended, including refused and failed ones, so the file
+//! answers "what did port-kill terminate, and when" across restarts. Dry runs terminate
+//! nothing and are not recorded.
+
+use crate::types::ProcessInfo;
+use anyhow::{Context, Result};
+use log::warn;
+use nix::sys::signal::Signal;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a kill ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillResult {
+    Killed,
+    Failed,
+}
+
+/// One line of the kill history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillRecord {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u128,
+    pub pid: i32,
+    /// Port the process held, when known
+    pub port: Option<u16>,
+    /// Process name, when known
+    pub name: Option<String>,
+    /// Signal sent first, e.g. `SIGTERM`
+    pub signal: String,
+    pub result: KillResult,
+    /// Why the kill failed
+    pub error: Option<String>,
+    /// Whether the process outlived `signal` and was sent SIGKILL
+    pub escalated_to_sigkill: bool,
+    /// The container that was stopped instead of signalling the process
+    pub container_id: Option<String>,
+}
+
+impl KillRecord {
+    /// Start a record for killing `pid`, described by `process_info` if it was scanned.
+    pub fn new(pid: i32, process_info: Option<&ProcessInfo>, signal: Signal) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        Self {
+            timestamp_ms,
+            pid,
+            port: process_info.and_then(|p| p.endpoint().port()),
+            name: process_info.map(|p| p.name.clone()),
+            signal: signal.to_string(),
+            result: KillResult::Killed,
+            error: None,
+            escalated_to_sigkill: false,
+            container_id: None,
+        }
+    }
+
+    /// Fill in the outcome of the kill.
+    pub fn finish<E: std::fmt::Display>(mut self, result: &Result<(), E>) -> Self {
+        if let Err(e) = result {
+            self.result = KillResult::Failed;
+            self.error = Some(e.to_string());
+        }
+        self
+    }
+}
+
+/// Appends [`KillRecord`]s to a file, creating it if needed.
+#[derive(Debug, Clone)]
+pub struct KillLog {
+    path: PathBuf,
+}
+
+impl KillLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Append `record` as one line.
+    pub fn append(&self, record: &KillRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record).context("Failed to serialize kill record")?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to append to kill log {}", self.path.display()))
+    }
+
+    /// Append `record`, only logging a failure: a kill must not fail because its history
+    /// couldn't be written.
+    pub fn record(&self, record: &KillRecord) {
+        if let Err(e) = self.append(record) {
+            warn!("{:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+
+    #[test]
+    fn test_appends_one_json_object_per_kill() {
+        let path = std::env::temp_dir().join(format!("port-kill-history-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = KillLog::new(path.clone());
+
+        let process = process_on(3000, 42);
+        let mut killed = KillRecord::new(42, Some(&process), Signal::SIGTERM).finish(&Ok::<(), anyhow::Error>(()));
+        killed.escalated_to_sigkill = true;
+        log.append(&killed).unwrap();
+        let failed = KillRecord::new(7, None, Signal::SIGINT).finish(&Err(anyhow::anyhow!("EPERM")));
+        log.append(&failed).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let records: Vec<KillRecord> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records, vec![killed, failed]);
+        assert_eq!((records[0].port, records[0].name.as_deref()), (Some(3000), Some("node")));
+        assert_eq!(records[1].result, KillResult::Failed);
+        assert_eq!(records[1].error.as_deref(), Some("EPERM"));
+        assert!(content.contains(r#""signal":"SIGINT","result":"failed""#));
+    }
+}
@@ -1,6 +1,63 @@
+//! Find and kill the processes holding development ports.
+//!
+//! Besides the `port-kill` and `port-kill-console` binaries, the crate can be used as a
+//! library: [`scan_ports`] reports what listens on a set of ports, and
+//! [`process_monitor::ProcessMonitor`] offers continuous monitoring and killing. Both fail
+//! with a [`PortKillError`].
+
 pub mod app;
+pub mod compose;
+pub mod config;
 pub mod console_app;
+pub mod container;
+pub mod discover;
+pub mod error;
+pub mod http;
+pub mod ipc;
+pub mod kill_log;
+pub mod log_dedupe;
+pub mod metrics;
+pub mod notify;
+pub mod pattern;
+pub mod policy;
 pub mod process_monitor;
+pub mod restart;
+pub mod scanner;
+pub mod service;
+pub mod snapshot;
 pub mod tray_menu;
 pub mod types;
 pub mod cli;
+
+pub use error::PortKillError;
+pub use types::{Endpoint, ProcessInfo, Protocol};
+
+/// Find the processes listening on `ports` over TCP, sorted by port. With `docker`, processes
+/// running in a container also get its id, name and Compose project.
+///
+/// This only looks: nothing is killed and no ignore or protection rules are applied, so every
+/// listener found is returned.
+pub async fn scan_ports(ports: &[u16], docker: bool) -> Result<Vec<ProcessInfo>, PortKillError> {
+    // A one-off scan never sends updates, so the receiving end can be dropped
+    let (sender, _receiver) = crossbeam_channel::bounded(1);
+    let monitor = process_monitor::ProcessMonitor::new(sender, ports.to_vec(), docker)?;
+
+    let mut processes: Vec<ProcessInfo> = monitor.scan_processes().await?.into_values().collect();
+    processes.sort_by_key(ProcessInfo::endpoint);
+    Ok(processes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scan_ports_finds_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let processes = scan_ports(&[port], false).await.unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!((processes[0].port, processes[0].pid), (port, std::process::id() as i32));
+    }
+}
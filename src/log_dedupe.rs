@@ -0,0 +1,80 @@
+//! Collapse repeated identical warnings.
+//!
+//! A persistent failure such as the Docker daemon being down produces the same message for
+//! every PID on every scan. [`LogDeduper`] logs each distinct message once, counts the
+//! repeats, and reports the count when the message stops recurring.
+
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+struct Repeats {
+    suppressed: u64,
+    seen_this_scan: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct LogDeduper {
+    seen: Mutex<HashMap<String, Repeats>>,
+}
+
+impl LogDeduper {
+    /// Log `message` as a warning unless it has already been logged and is still recurring.
+    pub fn warn(&self, message: String) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        match seen.get_mut(&message) {
+            Some(repeats) => {
+                repeats.suppressed += 1;
+                repeats.seen_this_scan = true;
+            }
+            None => {
+                warn!("{}", message);
+                seen.insert(message, Repeats { suppressed: 0, seen_this_scan: true });
+            }
+        }
+    }
+
+    /// Call once per scan. Messages that did not recur during the scan are forgotten, with a
+    /// final line saying how many repeats were hidden, so they are logged again if they return.
+    pub fn end_scan(&self) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|message, repeats| {
+            if repeats.seen_this_scan {
+                repeats.seen_this_scan = false;
+                return true;
+            }
+            if repeats.suppressed > 0 {
+                warn!("{} (suppressed {} repeats)", message, repeats.suppressed);
+            }
+            false
+        });
+    }
+
+    #[cfg(test)]
+    fn suppressed(&self, message: &str) -> Option<u64> {
+        self.seen.lock().unwrap().get(message).map(|r| r.suppressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_are_counted_across_scans_until_they_stop() {
+        let deduper = LogDeduper::default();
+        let message = "Docker lookup failed: daemon not running";
+
+        deduper.warn(message.to_string());
+        deduper.warn(message.to_string());
+        deduper.end_scan();
+        deduper.warn(message.to_string());
+        deduper.end_scan();
+        assert_eq!(deduper.suppressed(message), Some(2));
+
+        // A scan without the message clears it, so a later recurrence is logged afresh
+        deduper.end_scan();
+        assert_eq!(deduper.suppressed(message), None);
+    }
+}
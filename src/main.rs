@@ -1,11 +1,34 @@
 use anyhow::Result;
-use log::info;
-use port_kill::{app::PortKillApp, cli::Args};
-use clap::Parser;
+use log::{info, warn};
+use port_kill::{app::PortKillApp, cli::{Args, CONFIRM_TOKEN_ENV}, console_app::{self, ConsolePortKillApp}, service};
 
 fn main() -> Result<()> {
-    // Parse command-line arguments
-    let args = Args::parse();
+    // Parse command-line arguments over the config file defaults
+    let mut args = match Args::parse_with_config() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Merge port sources that need reading from disk
+    if let Err(e) = args.resolve() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    // Show the merged settings instead of running
+    if let Some(format) = args.print_config {
+        match args.render_config(format) {
+            Ok(config) => println!("{}", config.trim_end()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
     
     // Validate arguments
     if let Err(e) = args.validate() {
@@ -13,19 +36,49 @@ fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // Guard scripted destructive runs behind an optional confirmation token
+    let provided_token = std::env::var(CONFIRM_TOKEN_ENV).ok();
+    if let Err(e) = args.check_confirmation_token(provided_token.as_deref()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
     // Set up logging level based on verbose flag
-    if args.verbose {
-        std::env::set_var("RUST_LOG", "debug");
+    if let Some(level) = args.log_level() {
+        std::env::set_var("RUST_LOG", level);
     } else if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info");
     }
 
     // Initialize logging
     env_logger::init();
+
+    // Set up or remove the login service instead of running
+    if args.install_service || args.uninstall_service {
+        if let Err(e) = service::run(args.install_service) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     
     info!("Starting Port Kill application...");
     info!("Monitoring: {}", args.get_port_description());
 
+    if !args.wants_console() && PortKillApp::is_headless() {
+        if !args.auto_mode {
+            eprintln!("Error: No graphical display detected, so the status bar icon cannot be shown.");
+            eprintln!("Run with --console (or use port-kill-console), or pass --auto-mode to switch automatically.");
+            std::process::exit(1);
+        }
+        warn!("No graphical display detected, switching to console mode");
+        return run_console(args);
+    }
+
+    if args.wants_console() {
+        return run_console(args);
+    }
+
     // Create and run the application
     let app = PortKillApp::new(args)?;
     app.run()?;
@@ -33,3 +86,19 @@ fn main() -> Result<()> {
     info!("Port Kill application stopped");
     Ok(())
 }
+
+fn run_console(args: Args) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let result = runtime.block_on(async {
+        let app = ConsolePortKillApp::new(args)?;
+        app.run().await
+    });
+    // Same exit codes as port-kill-console: failed kills exit with 2, other errors with 1
+    if let Err(e) = result {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(console_app::exit_code(&e));
+    }
+
+    info!("Console Port Kill application stopped");
+    Ok(())
+}
@@ -1,12 +1,35 @@
 use anyhow::Result;
 use log::info;
-use port_kill::{console_app::ConsolePortKillApp, cli::Args};
-use clap::Parser;
+use port_kill::{console_app::{self, ConsolePortKillApp}, cli::{Args, CONFIRM_TOKEN_ENV}, service};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Parse command-line arguments
-    let args = Args::parse();
+    // Parse command-line arguments over the config file defaults
+    let mut args = match Args::parse_with_config() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Merge port sources that need reading from disk
+    if let Err(e) = args.resolve() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    // Show the merged settings instead of running
+    if let Some(format) = args.print_config {
+        match args.render_config(format) {
+            Ok(config) => println!("{}", config.trim_end()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
     
     // Validate arguments
     if let Err(e) = args.validate() {
@@ -14,22 +37,41 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
+    // Guard scripted destructive runs behind an optional confirmation token
+    let provided_token = std::env::var(CONFIRM_TOKEN_ENV).ok();
+    if let Err(e) = args.check_confirmation_token(provided_token.as_deref()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
     // Set up logging level based on verbose flag
-    if args.verbose {
-        std::env::set_var("RUST_LOG", "debug");
+    if let Some(level) = args.log_level() {
+        std::env::set_var("RUST_LOG", level);
     } else if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info");
     }
 
     // Initialize logging
     env_logger::init();
+
+    // Set up or remove the login service instead of running
+    if args.install_service || args.uninstall_service {
+        if let Err(e) = service::run(args.install_service) {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     
     info!("Starting Console Port Kill application...");
     info!("Monitoring: {}", args.get_port_description());
 
-    // Create and run the console application
+    // Create and run the console application. Failed kills exit with 2, other errors with 1
     let app = ConsolePortKillApp::new(args)?;
-    app.run().await?;
+    if let Err(e) = app.run().await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(console_app::exit_code(&e));
+    }
 
     info!("Console Port Kill application stopped");
     Ok(())
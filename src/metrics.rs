@@ -0,0 +1,115 @@
+//! Prometheus metrics for `--metrics-addr`, served as `GET /metrics` in the plain text
+//! exposition format:
+//! - `port_kill_processes`: processes currently holding a monitored endpoint
+//! - `port_kill_port_in_use{port,protocol}`: 1 for each monitored port that is held, else 0.
+//!   Past [`PORT_SERIES_LIMIT`] ports, such as the default 2000-6000 range, only held ports
+//!   get a series, so a scrape stays small
+//! - `port_kill_kills_total`: processes killed since startup
+//!
+//! Values come from the latest snapshot the console app publishes, so a scrape never waits
+//! on a scan.
+
+use crate::http;
+use crate::types::{Endpoint, ProcessUpdate, Protocol};
+use anyhow::Result;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Content type of the text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+/// Most `port_kill_port_in_use` series listed for free ports as well as held ones.
+const PORT_SERIES_LIMIT: usize = 100;
+
+/// What the metrics are computed from.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    pub ports: Vec<u16>,
+    pub protocols: Vec<Protocol>,
+    /// Counts successful kills, shared with the monitor
+    pub kills: Arc<AtomicU64>,
+}
+
+impl Metrics {
+    /// Every metric for `update`, in the text exposition format.
+    pub fn render(&self, update: &ProcessUpdate) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP port_kill_processes Processes holding a monitored port or socket.");
+        let _ = writeln!(out, "# TYPE port_kill_processes gauge");
+        let _ = writeln!(out, "port_kill_processes {}", update.count);
+
+        let _ = writeln!(out, "# HELP port_kill_port_in_use Whether something is listening on the port.");
+        let _ = writeln!(out, "# TYPE port_kill_port_in_use gauge");
+        let held_only = self.ports.len() * self.protocols.len() > PORT_SERIES_LIMIT;
+        for &port in &self.ports {
+            for &protocol in &self.protocols {
+                let in_use = update.processes.contains_key(&Endpoint::Port { port, protocol });
+                if held_only && !in_use {
+                    continue;
+                }
+                let _ = writeln!(out, "port_kill_port_in_use{{port=\"{}\",protocol=\"{}\"}} {}", port, protocol, u8::from(in_use));
+            }
+        }
+
+        let _ = writeln!(out, "# HELP port_kill_kills_total Processes killed since port-kill started.");
+        let _ = writeln!(out, "# TYPE port_kill_kills_total counter");
+        let _ = writeln!(out, "port_kill_kills_total {}", self.kills.load(Ordering::Relaxed));
+        out
+    }
+}
+
+/// Serve `GET /metrics` on `addr` until `shutdown` is set to `true` or its sender is dropped.
+pub async fn serve(
+    addr: SocketAddr,
+    metrics: Metrics,
+    updates: watch::Receiver<ProcessUpdate>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    http::serve_with(addr, "metrics", shutdown, move |request: http::Request| {
+        let response = route(&request.method, &request.path, &metrics, &updates);
+        async move { Ok(response) }
+    })
+    .await
+}
+
+fn route(method: &str, path: &str, metrics: &Metrics, updates: &watch::Receiver<ProcessUpdate>) -> Vec<u8> {
+    match (method, path) {
+        ("GET", "/metrics") => http::raw_response(200, CONTENT_TYPE, metrics.render(&updates.borrow()).into_bytes()),
+        (_, "/metrics") => http::raw_response(405, CONTENT_TYPE, b"Only GET is supported here\n".to_vec()),
+        _ => http::raw_response(404, CONTENT_TYPE, format!("No route for {}\n", path).into_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_render() {
+        let process = process_on(3000, 42);
+        let update = ProcessUpdate::new(HashMap::from([(Endpoint::tcp(3000), process)]));
+        let metrics = Metrics { ports: vec![3000, 3001], protocols: vec![Protocol::Tcp], kills: Arc::new(AtomicU64::new(2)) };
+
+        let text = metrics.render(&update);
+        assert!(text.contains("\nport_kill_processes 1\n"), "{}", text);
+        assert!(text.contains("\nport_kill_port_in_use{port=\"3000\",protocol=\"tcp\"} 1\n"), "{}", text);
+        assert!(text.contains("\nport_kill_port_in_use{port=\"3001\",protocol=\"tcp\"} 0\n"), "{}", text);
+        assert!(text.contains("# TYPE port_kill_kills_total counter\nport_kill_kills_total 2\n"), "{}", text);
+
+        // A wide range only lists the ports that are held
+        let wide = Metrics { ports: (2000..=6000).collect(), protocols: vec![Protocol::Tcp, Protocol::Udp], ..metrics.clone() };
+        let text = wide.render(&update);
+        assert_eq!(text.matches("port_kill_port_in_use{").count(), 1, "{}", text);
+        assert!(text.contains("\nport_kill_port_in_use{port=\"3000\",protocol=\"tcp\"} 1\n"), "{}", text);
+
+        let (_sender, updates) = watch::channel(update);
+        let response = String::from_utf8(route("GET", "/metrics", &metrics, &updates)).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n"), "{}", response);
+        assert!(String::from_utf8(route("GET", "/", &metrics, &updates)).unwrap().starts_with("HTTP/1.1 404"));
+        assert!(String::from_utf8(route("POST", "/metrics", &metrics, &updates)).unwrap().starts_with("HTTP/1.1 405"));
+    }
+}
@@ -0,0 +1,197 @@
+//! Desktop notifications for processes that start listening on a monitored port, and for
+//! reserved ports (`--reserve`) that nothing listens on.
+//!
+//! Notifications go through `osascript` on macOS and `notify-send` elsewhere, so nothing
+//! beyond what the desktop already ships is needed. Delivery happens on a background thread
+//! and failures are only logged: a missing notifier must never hold up a scan.
+
+use crate::types::{Endpoint, ProcessUpdate};
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// A process that reappears on the same port within this long is not announced again.
+pub const NOTIFY_DEBOUNCE: Duration = Duration::from_secs(60);
+
+const NOTIFICATION_TITLE: &str = "Port Kill";
+
+#[derive(Debug, Default)]
+pub struct Notifier {
+    /// When each process was last announced on each port
+    announced: HashMap<(Endpoint, i32), Instant>,
+    /// Whether a scan has completed. Whatever was already listening at startup is the
+    /// baseline, not news.
+    primed: bool,
+    /// Whether new listeners are announced at all, rather than only free reserved ports
+    announce_listeners: bool,
+    /// Ports something should always hold
+    reserved_ports: Vec<u16>,
+    /// Reserved ports already announced as free, so each outage is announced once
+    free_reserved: HashSet<u16>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self { announce_listeners: true, ..Self::default() }
+    }
+
+    /// A notifier that only announces free reserved ports.
+    pub fn reserved_only() -> Self {
+        Self::default()
+    }
+
+    /// Also announce whenever one of `ports` is free.
+    pub fn with_reserved_ports(mut self, ports: Vec<u16>) -> Self {
+        self.reserved_ports = ports;
+        self
+    }
+
+    /// Announce every port that `update` reports as newly occupied.
+    pub fn notify(&mut self, update: &ProcessUpdate) {
+        for message in self.messages(update, Instant::now()) {
+            send(message);
+        }
+    }
+
+    /// Call after every successful scan, whether or not it produced an update.
+    pub fn end_scan(&mut self) {
+        self.primed = true;
+    }
+
+    /// The notifications due for `update`, skipping the startup baseline and any process
+    /// announced on the same port within [`NOTIFY_DEBOUNCE`]. Freed ports are only announced
+    /// when reserved.
+    fn messages(&mut self, update: &ProcessUpdate, now: Instant) -> Vec<String> {
+        self.announced.retain(|_, at| now.duration_since(*at) < NOTIFY_DEBOUNCE);
+        let mut messages = self.reserved_messages(update);
+        if !self.primed || !self.announce_listeners {
+            return messages;
+        }
+
+        for endpoint in &update.added_ports {
+            // Clients connect and disconnect all the time, only new listeners are news
+            if matches!(endpoint, Endpoint::Connection { .. }) {
+                continue;
+            }
+            let Some(process_info) = update.processes.get(endpoint) else {
+                continue;
+            };
+            let key = (endpoint.clone(), process_info.pid);
+            if self.announced.contains_key(&key) {
+                debug!("Not announcing {} on {} again so soon", process_info.pid, endpoint);
+                continue;
+            }
+            self.announced.insert(key, now);
+            messages.push(format!("{} (PID {}) is now listening on {}", process_info.name, process_info.pid, endpoint));
+        }
+        messages
+    }
+
+    /// Alerts for reserved ports that became free. A reserved port that is already free at
+    /// startup is announced too: unlike a listener, its absence is never the expected baseline.
+    fn reserved_messages(&mut self, update: &ProcessUpdate) -> Vec<String> {
+        let free = update.free_ports(&self.reserved_ports);
+        let messages = free
+            .iter()
+            .filter(|port| !self.free_reserved.contains(port))
+            .map(|port| format!("Reserved port {} is not in use", port))
+            .collect();
+        self.free_reserved = free.into_iter().collect();
+        messages
+    }
+}
+
+/// Show `message` as a desktop notification without waiting for it to be delivered.
+fn send(message: String) {
+    std::thread::spawn(move || {
+        let mut command = notification_command(&message);
+        match command.output() {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => warn!("Failed to show notification: {}", String::from_utf8_lossy(&output.stderr).trim()),
+            Err(e) => warn!("Failed to show notification: {}", e),
+        }
+    });
+}
+
+fn notification_command(message: &str) -> Command {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.args([
+            "-e",
+            &format!(
+                "display notification {} with title {}",
+                applescript_string(message),
+                applescript_string(NOTIFICATION_TITLE)
+            ),
+        ]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args([NOTIFICATION_TITLE, message]);
+        command
+    }
+}
+
+/// Quote `text` as an AppleScript string literal.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{process_on, ProcessInfo};
+
+    fn update(previous: &[ProcessInfo], current: &[ProcessInfo]) -> ProcessUpdate {
+        let map = |processes: &[ProcessInfo]| processes.iter().map(|p| (p.endpoint(), p.clone())).collect();
+        ProcessUpdate::diff(&map(previous), map(current))
+    }
+
+    #[test]
+    fn test_announces_additions_once() {
+        let mut notifier = Notifier::new();
+        let start = Instant::now();
+
+        // Processes already running at startup are not announced
+        assert!(notifier.messages(&update(&[], &[process_on(3000, 10)]), start).is_empty());
+        notifier.end_scan();
+
+        let messages = notifier.messages(&update(&[process_on(3000, 10)], &[process_on(3000, 10), process_on(3001, 20)]), start);
+        assert_eq!(messages, vec!["node (PID 20) is now listening on 3001".to_string()]);
+
+        // Removals are silent, and the same PID coming back soon after is not repeated
+        assert!(notifier.messages(&update(&[process_on(3001, 20)], &[]), start).is_empty());
+        let back = update(&[], &[process_on(3001, 20)]);
+        assert!(notifier.messages(&back, start + Duration::from_secs(5)).is_empty());
+        assert_eq!(notifier.messages(&back, start + NOTIFY_DEBOUNCE).len(), 1);
+
+        // A different PID on the same port is news
+        let replaced = update(&[], &[process_on(3001, 21)]);
+        assert_eq!(notifier.messages(&replaced, start + NOTIFY_DEBOUNCE).len(), 1);
+    }
+
+    #[test]
+    fn test_announces_free_reserved_ports_once_per_outage() {
+        let mut notifier = Notifier::reserved_only().with_reserved_ports(vec![5432, 6379]);
+        let start = Instant::now();
+
+        // Reserved ports are checked from the first scan on, and new listeners are not announced
+        let messages = notifier.messages(&update(&[], &[process_on(5432, 10), process_on(3000, 11)]), start);
+        assert_eq!(messages, vec!["Reserved port 6379 is not in use".to_string()]);
+        notifier.end_scan();
+        assert!(notifier.messages(&update(&[], &[process_on(5432, 10), process_on(3001, 12)]), start).is_empty());
+
+        let messages = notifier.messages(&update(&[process_on(5432, 10)], &[process_on(6379, 13)]), start);
+        assert_eq!(messages, vec!["Reserved port 5432 is not in use".to_string()]);
+
+        // Coming back and going away again is a new outage
+        assert!(notifier.messages(&update(&[], &[process_on(5432, 14), process_on(6379, 13)]), start).is_empty());
+        assert_eq!(notifier.messages(&update(&[], &[process_on(6379, 13)]), start).len(), 1);
+    }
+
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(applescript_string(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+    }
+}
@@ -0,0 +1,99 @@
+//! Simple name matching shared by the container and process filters.
+
+/// Match `text` against `pattern`. Patterns containing `*` or `?` are treated as globs that
+/// must match the whole text; anything else matches as a substring.
+pub fn matches(pattern: &str, text: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern.as_bytes(), text.as_bytes())
+    } else {
+        text.contains(pattern)
+    }
+}
+
+/// Like [`matches`], but a pattern without glob characters must equal the text exactly.
+/// For guards, where a substring such as `node` must not accept `node_exporter`.
+pub fn matches_exact(pattern: &str, text: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern.as_bytes(), text.as_bytes())
+    } else {
+        text == pattern
+    }
+}
+
+/// Match a filesystem path against `pattern`: a glob that must match the whole path, or
+/// otherwise a prefix such as a directory.
+pub fn matches_path(pattern: &str, path: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern.as_bytes(), path.as_bytes())
+    } else {
+        path.starts_with(pattern)
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen and the text position it was tried at, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substring_match() {
+        assert!(matches("web", "my-web-1"));
+        assert!(!matches("api", "my-web-1"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(matches("web-*", "web-1"));
+        assert!(matches("web-*", "web-"));
+        assert!(!matches("web-*", "my-web-1"));
+        assert!(matches("*-db-?", "app-db-1"));
+        assert!(!matches("*-db-?", "app-db-12"));
+        assert!(matches("*", "anything"));
+        assert!(matches("a*b*c", "aXXbYYc"));
+        assert!(!matches("a*b*c", "aXXbYY"));
+    }
+
+    #[test]
+    fn test_exact_match() {
+        assert!(matches_exact("node", "node"));
+        assert!(!matches_exact("node", "nodemon"));
+        assert!(!matches_exact("n", "node"));
+        assert!(matches_exact("node*", "nodemon"));
+    }
+
+    #[test]
+    fn test_path_match() {
+        assert!(matches_path("/tmp/app", "/tmp/app/web.sock"));
+        assert!(!matches_path("app", "/tmp/app/web.sock"));
+        assert!(matches_path("/tmp/*.sock", "/tmp/web.sock"));
+        assert!(!matches_path("/tmp/*.sock", "/run/web.sock"));
+    }
+}
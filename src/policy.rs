@@ -0,0 +1,420 @@
+//! Runtime-mutable rules deciding which processes are hidden and which must never be killed.
+//!
+//! The policy is shared between `ProcessMonitor` and the UI as a [`SharedPolicy`], so a change
+//! made from the tray is honored by the very next scan or kill.
+//!
+//! Locking discipline: take the lock only to read or update the rules and release it straight
+//! away. Never hold a guard across an `.await`, a subprocess call, or while acquiring the
+//! monitor's mutex; use [`snapshot`] when the rules are needed for longer than a single check.
+
+use crate::cli::Args;
+use crate::pattern;
+use crate::types::{Endpoint, ProcessInfo, SocketState};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+pub type SharedPolicy = Arc<RwLock<Policy>>;
+
+/// System processes that hold ports in commonly monitored ranges but are never what the user
+/// is looking for. Hidden by default; `--no-default-ignores` shows them again.
+///
+/// - `rapportd`, `sharingd`, `identityservicesd`: macOS Continuity/Handoff, on random high ports
+/// - `ControlCenter`, `AirPlayXPCHelper`: macOS AirPlay receiver on 5000 and 7000
+/// - `cupsd`, `systemd-resolved`: Linux printing and DNS stub resolver
+///
+/// Names are listed in full; see [`TRUNCATED_NAME_LENGTHS`] for how shortened names match.
+pub const DEFAULT_IGNORED_NAMES: &[&str] = &[
+    "rapportd",
+    "sharingd",
+    "identityservicesd",
+    "ControlCenter",
+    "AirPlayXPCHelper",
+    "cupsd",
+    "systemd-resolved",
+];
+
+/// Lengths process names get cut to: lsof's COMMAND column keeps 9 characters and Linux's
+/// `comm` 15. A name of exactly one of these lengths also matches any ignored name it is a
+/// prefix of, so `identitys` and `systemd-resolve` are hidden like their full names.
+pub const TRUNCATED_NAME_LENGTHS: &[usize] = &[9, 15];
+
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// Process names that are left out of scan results entirely.
+    pub ignored_names: HashSet<String>,
+    /// Ports whose processes are listed but never killed: `--reserve`.
+    pub protected_ports: HashSet<u16>,
+    /// When non-empty, only processes whose name or command matches one of these (`--only`)
+    /// are kept.
+    pub only_patterns: Vec<String>,
+    /// Processes whose name or command matches any of these (`--ignore`) are left out, even
+    /// when they match `only_patterns`.
+    pub ignore_patterns: Vec<String>,
+    /// When set, only processes whose name or command matches it (`--name-regex`) are kept.
+    pub name_regex: Option<Regex>,
+    /// Processes whose name or command matches this (`--ignore-regex`) are left out.
+    pub ignore_regex: Option<Regex>,
+    /// When non-empty, only processes whose full command line contains one of these
+    /// (`--cmd-contains`) are kept.
+    pub cmd_contains: Vec<String>,
+    /// Processes whose full command line contains any of these (`--cmd-ignore`) are left out.
+    pub cmd_ignore: Vec<String>,
+    /// Leave root-owned processes out of scan results entirely (`--ignore-root`).
+    pub hide_root: bool,
+    /// List root-owned processes but never kill them (on unless `--include-system`).
+    pub protect_root: bool,
+    /// PIDs left out of scan results and never killed: `--exclude-pids`, plus port-kill
+    /// itself and its parent when built from arguments.
+    pub excluded_pids: HashSet<i32>,
+    /// Also kill processes that are only connected to a monitored port (`--kill-established`).
+    pub kill_established: bool,
+}
+
+impl Policy {
+    /// A policy that starts out hiding [`DEFAULT_IGNORED_NAMES`].
+    pub fn with_default_ignores() -> Self {
+        Self {
+            ignored_names: DEFAULT_IGNORED_NAMES.iter().map(|name| name.to_string()).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// The starting rules for these command-line arguments. Invalid regular expressions
+    /// are rejected by [`Args::validate`] beforehand.
+    pub fn from_args(args: &Args) -> Self {
+        let policy = if args.no_default_ignores {
+            Self::default()
+        } else {
+            Self::with_default_ignores()
+        };
+        Self {
+            only_patterns: args.only.clone(),
+            ignore_patterns: args.ignore.clone(),
+            name_regex: args.name_regex().ok().flatten(),
+            ignore_regex: args.ignore_regex().ok().flatten(),
+            cmd_contains: args.cmd_contains.clone(),
+            cmd_ignore: args.cmd_ignore.clone(),
+            hide_root: args.ignore_root,
+            protect_root: !args.include_system,
+            excluded_pids: args.exclude_pids.iter().copied().chain(own_pids()).collect(),
+            protected_ports: args.reserve.iter().copied().collect(),
+            kill_established: args.kill_established,
+            ..policy
+        }
+    }
+
+    pub fn shared(self) -> SharedPolicy {
+        Arc::new(RwLock::new(self))
+    }
+
+    pub fn is_ignored(&self, process_info: &ProcessInfo) -> bool {
+        if self.excluded_pids.contains(&process_info.pid)
+            || !self.passes_filters(process_info)
+            || (self.hide_root && process_info.is_root())
+        {
+            return true;
+        }
+
+        let name = &process_info.name;
+        if self.ignored_names.contains(name) {
+            return true;
+        }
+        TRUNCATED_NAME_LENGTHS.contains(&name.chars().count())
+            && self.ignored_names.iter().any(|ignored| ignored.starts_with(name.as_str()))
+    }
+
+    /// Whether `pid` must never be killed: one of `excluded_pids`, or port-kill itself or the
+    /// shell or launcher that started it, whatever the policy says.
+    pub fn is_excluded_pid(&self, pid: i32) -> bool {
+        self.excluded_pids.contains(&pid) || own_pids().contains(&pid)
+    }
+
+    /// Whether the process survives `--only` and `--name-regex` (applied first) and then
+    /// `--ignore` and `--ignore-regex`. Patterns match the name or command as a glob, or else
+    /// as a substring; regular expressions match anywhere unless anchored.
+    pub fn passes_filters(&self, process_info: &ProcessInfo) -> bool {
+        let matches_any = |patterns: &[String]| {
+            patterns.iter().any(|pattern| {
+                pattern::matches(pattern, &process_info.name) || pattern::matches(pattern, &process_info.command)
+            })
+        };
+        let matches_regex = |regex: &Regex| regex.is_match(&process_info.name) || regex.is_match(&process_info.command);
+
+        (self.only_patterns.is_empty() || matches_any(&self.only_patterns))
+            && self.name_regex.as_ref().is_none_or(matches_regex)
+            && !matches_any(&self.ignore_patterns)
+            && !self.ignore_regex.as_ref().is_some_and(matches_regex)
+    }
+
+    /// Whether the process survives `--cmd-contains` and then `--cmd-ignore`. The full command
+    /// line is only known once the process context has been read, so this is checked after
+    /// [`Self::is_ignored`].
+    pub fn passes_command_line_filters(&self, process_info: &ProcessInfo) -> bool {
+        let command_line = &process_info.command;
+        (self.cmd_contains.is_empty() || self.cmd_contains.iter().any(|text| command_line.contains(text.as_str())))
+            && !self.cmd_ignore.iter().any(|text| command_line.contains(text.as_str()))
+    }
+
+    pub fn is_protected(&self, process_info: &ProcessInfo) -> bool {
+        self.protection(process_info).is_some()
+    }
+
+    /// Why the process must not be killed, if it must not.
+    pub fn protection(&self, process_info: &ProcessInfo) -> Option<&'static str> {
+        if process_info.endpoint().port().is_some_and(|port| self.protected_ports.contains(&port)) {
+            Some("it holds a reserved port (--reserve)")
+        } else if self.protect_root && process_info.is_root() {
+            Some("it runs as root (pass --include-system to allow this)")
+        } else {
+            None
+        }
+    }
+
+    /// Whether the entry may be killed for its state: listeners always, clients that are only
+    /// connected to the port with `--kill-established`.
+    pub fn kills_state(&self, process_info: &ProcessInfo) -> bool {
+        process_info.state == SocketState::Listen || self.kill_established
+    }
+
+    /// Whether a process listed by `entries` must be left alone because every one of them is
+    /// a connection to a port rather than a listener, and `--kill-established` wasn't given.
+    pub fn is_only_connected<'a>(&self, entries: impl IntoIterator<Item = &'a ProcessInfo>) -> bool {
+        let mut entries = entries.into_iter().peekable();
+        entries.peek().is_some() && entries.all(|p| !self.kills_state(p))
+    }
+
+    /// The processes a kill-all should signal: one per PID, since a process listening on
+    /// several ports or protocols only needs killing once, and none that are protected,
+    /// excluded, or only connected to a port.
+    pub fn kill_targets<'a>(&self, processes: &'a HashMap<Endpoint, ProcessInfo>) -> Vec<&'a ProcessInfo> {
+        let protected_pids: HashSet<i32> = processes
+            .values()
+            .filter(|p| self.is_protected(p))
+            .map(|p| p.pid)
+            .collect();
+
+        let mut targets: Vec<&ProcessInfo> = processes
+            .values()
+            .filter(|p| !protected_pids.contains(&p.pid) && !self.is_excluded_pid(p.pid) && self.kills_state(p))
+            .collect();
+        targets.sort_by_key(|p| (p.pid, p.endpoint()));
+        targets.dedup_by_key(|p| p.pid);
+        targets
+    }
+}
+
+/// port-kill's own PID and its parent's. Killing either would take down the tool or the
+/// terminal it runs in.
+fn own_pids() -> [i32; 2] {
+    [std::process::id() as i32, std::os::unix::process::parent_id() as i32]
+}
+
+/// Lock the policy for reading. The rules are plain data, so a poisoned lock is still usable.
+pub fn read(policy: &SharedPolicy) -> RwLockReadGuard<'_, Policy> {
+    policy.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lock the policy for updating.
+pub fn write(policy: &SharedPolicy) -> RwLockWriteGuard<'_, Policy> {
+    policy.write().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Copy the current rules so no lock is held while they are applied.
+pub fn snapshot(policy: &SharedPolicy) -> Policy {
+    read(policy).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{process_on, Protocol};
+    use clap::Parser;
+
+    fn process_named(name: &str) -> ProcessInfo {
+        ProcessInfo {
+            command: name.to_string(),
+            name: name.to_string(),
+            ..process_on(5000, 1)
+        }
+    }
+
+    #[test]
+    fn test_default_ignores() {
+        let policy = Policy::with_default_ignores();
+        assert!(policy.is_ignored(&process_named("rapportd")));
+        assert!(!policy.is_ignored(&process_named("node")));
+
+        assert!(!Policy::default().is_ignored(&process_named("rapportd")));
+    }
+
+    #[test]
+    fn test_only_then_ignore_filters() {
+        let policy = Policy {
+            only_patterns: vec!["node".to_string(), "py*".to_string()],
+            ignore_patterns: vec!["*-worker".to_string()],
+            ..Policy::default()
+        };
+        assert!(policy.passes_filters(&process_named("node")));
+        assert!(policy.passes_filters(&process_named("nodemon")));
+        assert!(policy.passes_filters(&process_named("python3")));
+        assert!(!policy.passes_filters(&process_named("ruby")));
+        assert!(!policy.passes_filters(&process_named("node-worker")));
+        assert!(policy.is_ignored(&process_named("ruby")));
+
+        // The command is matched as well as the name
+        let mut process = process_named("java");
+        process.command = "/usr/bin/node server.js".to_string();
+        assert!(policy.passes_filters(&process));
+
+        let ignore_only = Policy {
+            ignore_patterns: vec!["Docker".to_string(), "com.docker.backend".to_string(), "rapportd".to_string()],
+            ..Policy::default()
+        };
+        assert!(!ignore_only.passes_filters(&process_named("com.docker.backend")));
+        assert!(!ignore_only.passes_filters(&process_named("Docker Desktop")));
+        assert!(ignore_only.passes_filters(&process_named("node")));
+        assert!(Policy::default().passes_filters(&process_named("anything")));
+    }
+
+    #[test]
+    fn test_regex_filters() {
+        let args = Args::parse_from(["port-kill", "--name-regex", "^(node|deno)$", r"--ignore-regex=--inspect\b"]);
+        let policy = Policy::from_args(&args);
+        assert!(policy.passes_filters(&process_named("node")));
+        assert!(policy.passes_filters(&process_named("deno")));
+        assert!(!policy.passes_filters(&process_named("nodemon")));
+        assert!(policy.is_ignored(&process_named("nodemon")));
+
+        let mut debugged = process_named("node");
+        debugged.command = "node --inspect server.js".to_string();
+        assert!(!policy.passes_filters(&debugged));
+
+        let error = Args::parse_from(["port-kill", "--name-regex", "node("]).validate().unwrap_err();
+        assert!(error.starts_with("Invalid --name-regex 'node('"), "{}", error);
+        assert!(Args::parse_from(["port-kill", "--ignore-regex", "[a-"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_command_line_filters() {
+        let command_line = |command: &str| ProcessInfo { command: command.to_string(), ..process_named("python3") };
+        let runserver = command_line("python3 manage.py runserver 0.0.0.0:8000");
+        let worker = command_line("python3 manage.py rqworker");
+        let debug = command_line("python3 manage.py runserver --settings=debug");
+
+        let args = Args::parse_from(["port-kill", "--cmd-contains", "manage.py runserver", "--cmd-ignore=--settings=debug"]);
+        let policy = Policy::from_args(&args);
+        assert!(policy.passes_command_line_filters(&runserver));
+        assert!(!policy.passes_command_line_filters(&worker));
+        assert!(!policy.passes_command_line_filters(&debug));
+        assert!(Policy::default().passes_command_line_filters(&worker));
+    }
+
+    #[test]
+    fn test_root_processes() {
+        let root = ProcessInfo { uid: Some(0), ..process_named("launchd") };
+        let user = ProcessInfo { uid: Some(501), ..process_named("node") };
+        let unknown = process_named("node");
+
+        let policy = Policy { protect_root: true, ..Policy::default() };
+        assert!(policy.is_protected(&root));
+        assert!(!policy.is_ignored(&root));
+        assert!(!policy.is_protected(&user));
+        assert!(!policy.is_protected(&unknown));
+
+        let processes = HashMap::from([(Endpoint::tcp(80), ProcessInfo { port: 80, pid: 2, ..root.clone() }), (Endpoint::tcp(5000), user.clone())]);
+        let targets = policy.kill_targets(&processes);
+        assert_eq!(targets.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![1]);
+
+        let policy = Policy { hide_root: true, ..Policy::default() };
+        assert!(policy.is_ignored(&root));
+        assert!(!policy.is_ignored(&user));
+        assert!(!policy.is_protected(&root));
+    }
+
+    #[test]
+    fn test_default_ignores_match_truncated_names() {
+        let policy = Policy::with_default_ignores();
+        // As printed in lsof's 9-character COMMAND column
+        for name in ["ControlCe", "identitys", "AirPlayXP", "systemd-r"] {
+            assert!(policy.is_ignored(&process_named(name)), "{} should be ignored", name);
+        }
+        // As reported by `ps -o comm=` on Linux, capped at 15 characters
+        assert!(policy.is_ignored(&process_named("systemd-resolve")));
+
+        // Only names cut at exactly those lengths count as truncated
+        assert!(!policy.is_ignored(&process_named("systemd")));
+        assert!(!policy.is_ignored(&process_named("ControlC")));
+    }
+
+    #[test]
+    fn test_kill_targets_dedupe_by_pid_across_protocols() {
+        let tcp = ProcessInfo { pid: 7, ..process_named("dnsmasq") };
+        let udp = ProcessInfo { protocol: Protocol::Udp, ..tcp.clone() };
+        let other = ProcessInfo { pid: 8, port: 6000, ..process_named("node") };
+        let processes: HashMap<Endpoint, ProcessInfo> =
+            [tcp, udp, other].into_iter().map(|p| (p.endpoint(), p)).collect();
+        assert_eq!(processes.len(), 3);
+
+        let pids: Vec<i32> = Policy::default().kill_targets(&processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![7, 8]);
+
+        // Protecting one of PID 7's ports spares it entirely
+        let policy = Policy {
+            protected_ports: HashSet::from([5000]),
+            ..Policy::default()
+        };
+        let pids: Vec<i32> = policy.kill_targets(&processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![8]);
+
+        // --reserve protects the same way
+        let policy = Policy::from_args(&Args::parse_from(["port-kill", "--reserve", "6000"]));
+        let pids: Vec<i32> = policy.kill_targets(&processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![7]);
+        assert_eq!(policy.protection(&processes[&Endpoint::tcp(6000)]), Some("it holds a reserved port (--reserve)"));
+    }
+
+    #[test]
+    fn test_clients_are_only_killed_with_kill_established() {
+        let server = ProcessInfo { pid: 7, ..process_named("node") };
+        let client = ProcessInfo { pid: 8, state: SocketState::Established, ..process_named("curl") };
+        // The server is also a client of another monitored port, which doesn't spare it
+        let server_as_client = ProcessInfo { port: 5432, state: SocketState::Established, ..server.clone() };
+        let processes: HashMap<Endpoint, ProcessInfo> =
+            [server.clone(), client.clone(), server_as_client.clone()].into_iter().map(|p| (p.endpoint(), p)).collect();
+        assert_eq!(processes.len(), 3);
+
+        let policy = Policy::default();
+        let pids: Vec<i32> = policy.kill_targets(&processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![7]);
+        assert!(policy.is_only_connected([&client]));
+        assert!(!policy.is_only_connected([&server, &server_as_client]));
+        assert!(!policy.is_only_connected([]));
+
+        let args = Args::parse_from(["port-kill", "--include-established", "--kill-established"]);
+        let policy = Policy::from_args(&args);
+        let pids: Vec<i32> = policy.kill_targets(&processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![7, 8]);
+        assert!(!policy.is_only_connected([&client]));
+        assert!(Args::try_parse_from(["port-kill", "--kill-established"]).is_err());
+    }
+
+    #[test]
+    fn test_never_kills_itself_or_excluded_pids() {
+        let own_pid = std::process::id() as i32;
+        let own = ProcessInfo { pid: own_pid, ..process_named("port-kill") };
+        let excluded = ProcessInfo { pid: 9, port: 6000, ..process_named("node") };
+        let other = ProcessInfo { pid: 8, port: 7000, ..process_named("node") };
+        let processes: HashMap<Endpoint, ProcessInfo> =
+            [own.clone(), excluded.clone(), other].into_iter().map(|p| (p.endpoint(), p)).collect();
+
+        let policy = Policy::from_args(&Args::parse_from(["port-kill", "--exclude-pids", "9"]));
+        let pids: Vec<i32> = policy.kill_targets(&processes).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![8]);
+        assert!(policy.is_ignored(&own) && policy.is_ignored(&excluded));
+
+        // Without arguments the running process is still listed, but never killed
+        assert!(!Policy::default().is_ignored(&own));
+        assert!(Policy::default().kill_targets(&processes).iter().all(|p| p.pid != own_pid));
+    }
+}
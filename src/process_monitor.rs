@@ -1,287 +1,991 @@
-use crate::types::{ProcessInfo, ProcessUpdate};
-use anyhow::{Context, Result};
-use crossbeam_channel::Sender;
-use log::{error, info, warn};
+use crate::cli::{format_port_ranges, Args};
+use crate::compose;
+use crate::container::ContainerCli;
+use crate::error::{KillFailure, KillsFailed, PortKillError};
+use crate::kill_log::{KillLog, KillRecord};
+use crate::log_dedupe::LogDeduper;
+use crate::notify::Notifier;
+use crate::pattern;
+use crate::policy::{self, Policy, SharedPolicy};
+use crate::scanner::{self, LsofPermissionDenied, PortScanner, ProcessContext};
+use crate::snapshot::SnapshotWriter;
+use crate::types::{Endpoint, KillAttempt, ProcessInfo, ProcessUpdate, Protocol, ScanReport, SocketState};
+use crossbeam_channel::{Sender, TrySendError};
+use log::{debug, error, info, warn};
 use nix::sys::signal::{kill, Signal};
 use nix::unistd::Pid;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::process::Command;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
 use tokio::time::sleep;
 
 const MONITORING_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a PID's container lookup is reused before the runtime is asked again.
+const CONTAINER_CACHE_TTL: Duration = Duration::from_secs(30);
+/// How often a signalled process is checked for having exited.
+const KILL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Wait before the first retry of a failed signal, doubling for each further retry.
+const KILL_RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+/// Longest wait between retries of a failed signal.
+const KILL_RETRY_MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// How processes are asked to exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KillOptions {
+    /// Sent first (`--signal`)
+    pub signal: Signal,
+    /// How long the process gets to exit after `signal` (`--kill-timeout`)
+    pub timeout: Duration,
+    /// Follow up with SIGKILL if it is still running after `timeout` (off with `--no-sigkill`)
+    pub sigkill: bool,
+    /// Also kill every descendant of the process (`--kill-tree`)
+    pub tree: bool,
+    /// How many more times a signal is sent after failing to send (`--kill-retries`)
+    pub retries: u32,
+    /// Check that a scanned process still holds its port just before killing it (off with
+    /// `--no-verify`)
+    pub verify: bool,
+}
+
+impl Default for KillOptions {
+    fn default() -> Self {
+        Self {
+            signal: Signal::SIGTERM,
+            timeout: Duration::from_millis(500),
+            sigkill: true,
+            tree: false,
+            retries: 0,
+            verify: true,
+        }
+    }
+}
+
+impl KillOptions {
+    pub fn from_args(args: &Args) -> Result<Self, String> {
+        Ok(Self {
+            signal: args.kill_signal()?,
+            timeout: Duration::from_millis(args.kill_timeout),
+            sigkill: !args.no_sigkill,
+            tree: args.kill_tree,
+            retries: args.kill_retries,
+            verify: !args.no_verify,
+        })
+    }
+
+    /// How long to wait before retry number `retry` (counting from 0) of a failed signal.
+    pub fn retry_delay(retry: u32) -> Duration {
+        KILL_RETRY_BASE_DELAY
+            .checked_mul(2u32.saturating_pow(retry))
+            .map_or(KILL_RETRY_MAX_DELAY, |delay| delay.min(KILL_RETRY_MAX_DELAY))
+    }
+}
 
 pub struct ProcessMonitor {
     update_sender: Sender<ProcessUpdate>,
-    current_processes: HashMap<u16, ProcessInfo>,
+    pending_update: Option<ProcessUpdate>,
+    dropped_updates: u64,
+    current_processes: HashMap<Endpoint, ProcessInfo>,
     ports_to_monitor: Vec<u16>,
+    /// Finds the listeners on `ports_to_monitor`
+    scanner: Box<dyn PortScanner>,
+    /// Time between scans in [`Self::start_monitoring`]
+    interval: Duration,
+    protocols: Vec<Protocol>,
+    /// Also list processes listening on Unix sockets whose path matches this pattern
+    unix_sockets: Option<String>,
+    /// Also list clients connected to the monitored ports (`--include-established`)
+    include_established: bool,
     docker_enabled: bool,
+    /// Runs docker or podman, against the chosen context
+    container_cli: ContainerCli,
+    /// Skip container lookups during scans and leave them to [`Self::enrich_docker_info`]
+    docker_lazy: bool,
+    policy: SharedPolicy,
+    snapshots: Option<SnapshotWriter>,
+    /// Announces processes that newly appear on a monitored port, and free reserved ports
+    notifier: Option<Notifier>,
+    /// Abort a kill-all at the first failure
+    fail_fast: bool,
+    /// Log kills instead of performing them
+    dry_run: bool,
+    /// Refuse every kill and container stop
+    watch_only: bool,
+    /// Which signal to send and whether to escalate to SIGKILL
+    kill_options: KillOptions,
+    /// Where every kill is recorded (`--log-file`)
+    kill_log: Option<KillLog>,
+    /// Keeps per-PID failures (e.g. Docker being down) from flooding the log every scan
+    log_deduper: LogDeduper,
+    /// Recent container lookups, so each scan only asks about new PIDs
+    container_cache: ContainerCache,
+    /// Processes killed so far, for `--metrics-addr`
+    kill_count: Arc<AtomicU64>,
+    /// Whether a scan has succeeded yet; the first result is always sent, even when nothing
+    /// was found, so consumers can leave the [`ProcessUpdate::empty`] baseline
+    scanned: bool,
 }
 
 impl ProcessMonitor {
-    pub fn new(update_sender: Sender<ProcessUpdate>, ports_to_monitor: Vec<u16>, docker_enabled: bool) -> Result<Self> {
+    pub fn new(update_sender: Sender<ProcessUpdate>, ports_to_monitor: Vec<u16>, docker_enabled: bool) -> Result<Self, PortKillError> {
         Ok(Self {
             update_sender,
+            pending_update: None,
+            dropped_updates: 0,
             current_processes: HashMap::new(),
             ports_to_monitor,
+            scanner: scanner::detect(None),
+            interval: MONITORING_INTERVAL,
+            protocols: vec![Protocol::Tcp],
+            unix_sockets: None,
+            include_established: false,
             docker_enabled,
+            container_cli: ContainerCli::default(),
+            docker_lazy: false,
+            snapshots: None,
+            notifier: None,
+            fail_fast: false,
+            dry_run: false,
+            watch_only: false,
+            kill_options: KillOptions::default(),
+            kill_log: None,
+            log_deduper: LogDeduper::default(),
+            container_cache: ContainerCache::default(),
+            kill_count: Arc::default(),
+            scanned: false,
+            policy: Policy::default().shared(),
         })
     }
 
-    pub async fn start_monitoring(&mut self) -> Result<()> {
-        let port_description = if self.ports_to_monitor.len() <= 10 {
-            format!("ports: {}", self.ports_to_monitor.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", "))
-        } else {
-            format!("{} ports: {} to {}", 
-                self.ports_to_monitor.len(), 
-                self.ports_to_monitor.first().unwrap_or(&0), 
-                self.ports_to_monitor.last().unwrap_or(&0))
+    /// Create a monitor configured from command-line arguments.
+    pub fn from_args(update_sender: Sender<ProcessUpdate>, args: &Args) -> Result<Self, PortKillError> {
+        let snapshots = match (args.snapshot_every, &args.output_dir) {
+            (Some(every), Some(dir)) => Some(SnapshotWriter::new(every, dir.clone()).map_err(|source| PortKillError::Io {
+                action: format!("create snapshot directory {}", dir.display()),
+                source,
+            })?),
+            _ => None,
         };
-        
-        info!("Starting process monitoring on {}", port_description);
 
-        loop {
-            match self.scan_processes().await {
-                Ok(processes) => {
-                    let update = ProcessUpdate::new(processes.clone());
-                    
-                    // Check if there are any changes
-                    if self.current_processes != processes {
-                        info!("Process update: {} processes found", update.count);
-                        self.current_processes = processes;
-                        
-                        if let Err(e) = self.update_sender.send(update) {
-                            error!("Failed to send process update: {}", e);
-                        }
+        Ok(Self::new(update_sender, args.get_ports_to_monitor(), args.docker)?
+            .with_interval(args.scan_interval().unwrap_or(MONITORING_INTERVAL))
+            .with_protocols(args.protocol.protocols().to_vec())
+            .with_scanner(scanner::detect(args.host))
+            .with_unix_sockets(args.unix_sockets.clone())
+            .with_established(args.include_established)
+            .with_container_cli(ContainerCli::from_args(args))
+            .with_docker_lazy(args.docker_lazy)
+            .with_policy(Policy::from_args(args).shared())
+            .with_snapshots(snapshots)
+            .with_notifications(args.notify)
+            .with_reserved_ports(args.reserve.clone())
+            .with_fail_fast(args.fail_fast)
+            .with_dry_run(args.dry_run)
+            .with_watch_only(args.watch_only)
+            .with_kill_log(args.log_file.clone().map(KillLog::new))
+            .with_kill_options(KillOptions::from_args(args).map_err(PortKillError::InvalidArgument)?))
+    }
+
+    /// Wait this long between scans when monitoring continuously.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Time between scans when monitoring continuously.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Find listeners with this scanner instead of the one detected for the system.
+    pub fn with_scanner(mut self, scanner: Box<dyn PortScanner>) -> Self {
+        self.scanner = scanner;
+        self
+    }
+
+    /// Name of the scanner finding listeners, e.g. `lsof`.
+    pub fn backend(&self) -> &'static str {
+        self.scanner.name()
+    }
+
+    /// Look for listeners on these protocols (TCP only by default).
+    pub fn with_protocols(mut self, protocols: Vec<Protocol>) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// Include listening Unix sockets matching `pattern` alongside the monitored ports.
+    pub fn with_unix_sockets(mut self, unix_sockets: Option<String>) -> Self {
+        self.unix_sockets = unix_sockets;
+        self
+    }
+
+    /// Also list processes with an established TCP connection to a monitored port.
+    pub fn with_established(mut self, include_established: bool) -> Self {
+        self.include_established = include_established;
+        self
+    }
+
+    /// Run every docker command against the given context instead of the current one.
+    pub fn with_container_cli(mut self, container_cli: ContainerCli) -> Self {
+        self.container_cli = container_cli;
+        self
+    }
+
+    /// Defer container lookups until a process is about to be shown or killed.
+    pub fn with_docker_lazy(mut self, lazy: bool) -> Self {
+        self.docker_lazy = lazy;
+        self
+    }
+
+    /// Write periodic full snapshots from the monitoring loop.
+    pub fn with_snapshots(mut self, snapshots: Option<SnapshotWriter>) -> Self {
+        self.snapshots = snapshots;
+        self
+    }
+
+    /// Show a desktop notification whenever a process starts listening on a monitored port.
+    pub fn with_notifications(mut self, notify: bool) -> Self {
+        self.notifier = notify.then(Notifier::new);
+        self
+    }
+
+    /// Show a desktop notification whenever one of `ports` is free, whether or not
+    /// [`Self::with_notifications`] is on: something is meant to hold them (`--reserve`).
+    pub fn with_reserved_ports(mut self, ports: Vec<u16>) -> Self {
+        if !ports.is_empty() {
+            let notifier = self.notifier.take().unwrap_or_else(Notifier::reserved_only);
+            self.notifier = Some(notifier.with_reserved_ports(ports));
+        }
+        self
+    }
+
+    /// Stop kill-alls at the first failure rather than attempting every process.
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Only log the signals and container stops a kill would perform.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Refuse to kill anything, for monitoring shared machines.
+    pub fn with_watch_only(mut self, watch_only: bool) -> Self {
+        self.watch_only = watch_only;
+        self
+    }
+
+    /// Append a line describing every kill to this history.
+    pub fn with_kill_log(mut self, kill_log: Option<KillLog>) -> Self {
+        self.kill_log = kill_log;
+        self
+    }
+
+    /// Fail with a clear error if `--watch-only` forbids `action`.
+    fn check_kill_allowed(&self, action: &str) -> Result<(), PortKillError> {
+        if self.watch_only {
+            warn!("Watch-only mode, refusing to {}", action);
+            return Err(PortKillError::WatchOnly { action: action.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Choose the signal sent to processes and how long they get before SIGKILL.
+    pub fn with_kill_options(mut self, kill_options: KillOptions) -> Self {
+        self.kill_options = kill_options;
+        self
+    }
+
+    /// How many processes have been killed, counting up as kills happen.
+    pub fn kill_count(&self) -> Arc<AtomicU64> {
+        self.kill_count.clone()
+    }
+
+    /// Whether kills are only being reported.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Share an existing policy handle, e.g. one the UI also updates.
+    pub fn with_policy(mut self, policy: SharedPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Handle to the ignore/protect rules consulted on every scan and kill.
+    pub fn policy(&self) -> SharedPolicy {
+        self.policy.clone()
+    }
+
+    /// Scan on every interval until `shutdown` is set to `true` or its sender is dropped.
+    /// The lock is only held for the duration of each scan so other tasks sharing the
+    /// monitor (e.g. socket clients) can kill processes in between.
+    pub async fn start_monitoring(
+        monitor: Arc<Mutex<ProcessMonitor>>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<(), PortKillError> {
+        let (port_description, interval) = {
+            let monitor = monitor.lock().await;
+            (monitor.port_description(), monitor.interval)
+        };
+        info!("Starting process monitoring on {} every {:?}", port_description, interval);
+
+        while !*shutdown.borrow() {
+            monitor.lock().await.check_for_updates().await;
+            if Self::wait_for_next_scan(interval, &mut shutdown).await {
+                break;
+            }
+        }
+
+        info!("Process monitoring stopped");
+        Ok(())
+    }
+
+    /// Sleep until the next scan is due, returning `true` if shutdown was requested meanwhile.
+    async fn wait_for_next_scan(interval: Duration, shutdown: &mut watch::Receiver<bool>) -> bool {
+        tokio::select! {
+            _ = sleep(interval) => *shutdown.borrow(),
+            changed = shutdown.changed() => changed.is_err() || *shutdown.borrow(),
+        }
+    }
+
+    /// Run a single scan and send an update if the set of processes changed.
+    pub async fn check_for_updates(&mut self) {
+        match self.scan_once().await {
+            Ok(report) => {
+                debug!("Scanned {} ports with {} in {:?}", report.scanned_ports, report.backend, report.elapsed);
+                let processes = report.processes;
+
+                if let Some(ref mut snapshots) = self.snapshots {
+                    match snapshots.record(&processes) {
+                        Ok(Some(path)) => debug!("Wrote snapshot {}", path.display()),
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to write snapshot: {}", e),
                     }
                 }
-                Err(e) => {
-                    error!("Failed to scan processes: {}", e);
+                // Check if there are any changes
+                if !self.scanned || ProcessUpdate::is_change(&self.current_processes, &processes) {
+                    self.scanned = true;
+                    let update = ProcessUpdate::diff(&self.current_processes, processes.clone());
+                    info!("Process update: {} processes found", update.count);
+                    for restart in &update.restarted {
+                        info!("{}", restart);
+                    }
+                    if let Some(ref mut notifier) = self.notifier {
+                        notifier.notify(&update);
+                    }
+                    self.current_processes = processes;
+
+                    // Updates are full snapshots, so a newer one supersedes anything still
+                    // waiting for channel space
+                    if self.pending_update.replace(update).is_some() {
+                        self.dropped_updates += 1;
+                        warn!("Dropped a stale process update ({} total) because the consumer is behind", self.dropped_updates);
+                    }
+                }
+                if let Some(ref mut notifier) = self.notifier {
+                    notifier.end_scan();
                 }
             }
-
-            sleep(MONITORING_INTERVAL).await;
+            Err(e) => {
+                error!("Failed to scan processes: {}", e);
+            }
         }
+
+        self.log_deduper.end_scan();
+        self.flush_pending_update();
     }
 
-    async fn scan_processes(&self) -> Result<HashMap<u16, ProcessInfo>> {
-        let mut processes = HashMap::new();
+    /// Try to hand the pending update to the consumer without ever blocking the scan loop.
+    fn flush_pending_update(&mut self) {
+        let Some(update) = self.pending_update.take() else {
+            return;
+        };
 
-        for &port in &self.ports_to_monitor {
-            if let Ok(process_info) = self.get_process_on_port(port).await {
-                processes.insert(port, process_info);
+        match self.update_sender.try_send(update) {
+            Ok(()) => {}
+            Err(TrySendError::Full(update)) => {
+                debug!("Update channel full, holding latest snapshot until the consumer catches up");
+                self.pending_update = Some(update);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                error!("Failed to send process update: receiver disconnected");
             }
         }
+    }
 
-        Ok(processes)
+    fn port_description(&self) -> String {
+        format!("{} ports: {}", self.ports_to_monitor.len(), format_port_ranges(&self.ports_to_monitor))
+    }
+
+    /// Scan every monitored port once and report what was found along with how long it took.
+    pub async fn scan_once(&self) -> Result<ScanReport, PortKillError> {
+        let started = Instant::now();
+        let processes = self.scan_processes().await?;
+        Ok(ScanReport {
+            processes,
+            scanned_ports: self.ports_to_monitor.len(),
+            elapsed: started.elapsed(),
+            backend: self.scanner.name(),
+        })
     }
 
-    async fn get_process_on_port(&self, port: u16) -> Result<ProcessInfo> {
-        // Use lsof to find processes listening on the port
-        let output = Command::new("lsof")
-            .args(&["-ti", &format!(":{}", port), "-sTCP:LISTEN"])
-            .output()
-            .context("Failed to execute lsof command")?;
+    pub(crate) async fn scan_processes(&self) -> Result<HashMap<Endpoint, ProcessInfo>, PortKillError> {
+        let policy = policy::snapshot(&self.policy);
 
-        if output.status.success() {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            let pid_str = output_str.trim();
-            if !pid_str.is_empty() {
-                let pid: i32 = pid_str.parse().context("Failed to parse PID")?;
-                
-                // Get process details using ps
-                let process_info = self.get_process_details(pid, port).await?;
-                return Ok(process_info);
+        let mut processes = self
+            .scanner
+            .scan(&self.ports_to_monitor, &self.protocols)
+            .await?;
+        processes.retain(|_, process_info| !policy.is_ignored(process_info));
+        if self.docker_enabled && !self.docker_lazy {
+            self.add_docker_info(&mut processes).await;
+        }
+
+        if let Some(pattern) = &self.unix_sockets {
+            for mut process_info in list_unix_sockets(pattern)? {
+                if policy.is_ignored(&process_info) {
+                    continue;
+                }
+                if self.docker_enabled && !self.docker_lazy {
+                    self.get_docker_container_info(process_info.pid).await.apply_to(&mut process_info);
+                }
+                // Forked servers share one listening socket; the first PID stands for it
+                let pid = process_info.pid;
+                processes.entry(process_info.endpoint()).and_modify(|existing| existing.also_held_by(pid)).or_insert(process_info);
             }
         }
 
-        Err(anyhow::anyhow!("No process found on port {}", port))
-    }
+        if self.include_established && self.protocols.contains(&Protocol::Tcp) {
+            for process_info in list_connections(&self.ports_to_monitor)? {
+                if !policy.is_ignored(&process_info) {
+                    processes.insert(process_info.endpoint(), process_info);
+                }
+            }
+        }
 
-    async fn get_process_details(&self, pid: i32, port: u16) -> Result<ProcessInfo> {
-        // Get process command and name using ps
-        let output = Command::new("ps")
-            .args(&["-p", &pid.to_string(), "-o", "comm="])
-            .output()
-            .context("Failed to execute ps command")?;
+        add_process_context(&mut processes);
+        processes.retain(|_, process_info| policy.passes_command_line_filters(process_info));
+        self.container_cache.retain(&processes.values().map(|p| p.pid).collect());
+        Ok(processes)
+    }
 
-        let command = if output.status.success() {
-            String::from_utf8_lossy(&output.stdout).trim().to_string()
-        } else {
-            "unknown".to_string()
+    /// The process on `port` for the first monitored protocol that has one.
+    pub(crate) async fn get_process_on_port(&self, port: u16) -> Result<Option<ProcessInfo>, PortKillError> {
+        let mut processes = self
+            .scanner
+            .scan(&[port], &self.protocols)
+            .await?;
+        let Some(mut process_info) = self
+            .protocols
+            .iter()
+            .find_map(|&protocol| processes.remove(&Endpoint::Port { port, protocol }))
+        else {
+            return Ok(None);
         };
+        if self.docker_enabled && !self.docker_lazy {
+            self.get_docker_container_info(process_info.pid).await.apply_to(&mut process_info);
+        }
+        if let Some(context) = scanner::process_context(&[process_info.pid]).remove(&process_info.pid) {
+            apply_context(&mut process_info, context);
+        }
+        Ok(Some(process_info))
+    }
 
-        // Extract process name (basename of command)
-        let name = command
-            .split('/')
-            .last()
-            .unwrap_or("unknown")
-            .to_string();
+    /// Fill in container details for every scanned process, looking each PID up only once.
+    async fn add_docker_info(&self, processes: &mut HashMap<Endpoint, ProcessInfo>) {
+        let pids: Vec<i32> = processes.values().map(|p| p.pid).collect();
+        let containers = self.get_docker_containers(&pids).await;
+        for process_info in processes.values_mut() {
+            if let Some(container) = containers.get(&process_info.pid) {
+                container.clone().apply_to(process_info);
+            }
+        }
+    }
 
-        // Check if this process is running in a Docker container
-        let (container_id, container_name) = if self.docker_enabled {
-            self.get_docker_container_info(pid).await
-        } else {
-            (None, None)
-        };
+    /// With `--docker-lazy`, fill in the container id and name for a process that is about to
+    /// be shown. Scans already did this otherwise, so it is a no-op.
+    pub async fn enrich_docker_info(&self, process_info: &mut ProcessInfo) {
+        if !self.is_docker_lazy() || process_info.container_id.is_some() {
+            return;
+        }
 
-        Ok(ProcessInfo {
-            pid,
-            port,
-            command,
-            name,
-            container_id,
-            container_name,
-        })
+        self.get_docker_container_info(process_info.pid).await.apply_to(process_info);
     }
 
-    async fn get_docker_container_info(&self, pid: i32) -> (Option<String>, Option<String>) {
-        // Try to find the container ID for this PID
-        let container_id = match self.find_container_id_for_pid(pid).await {
-            Ok(id) => id,
-            Err(_) => None,
-        };
+    /// Whether scans leave container details to [`Self::enrich_docker_info`].
+    fn is_docker_lazy(&self) -> bool {
+        self.docker_enabled && self.docker_lazy
+    }
+
+    async fn get_docker_container_info(&self, pid: i32) -> ContainerInfo {
+        self.get_docker_containers(&[pid]).await.remove(&pid).unwrap_or_default()
+    }
 
-        // If we found a container ID, get the container name
-        let container_name = if let Some(ref id) = container_id {
-            match self.get_container_name(id).await {
-                Ok(name) => Some(name),
-                Err(_) => None,
+    /// The container each of `pids` runs in, taken from the cache where it is fresh. The rest
+    /// are looked up together, so a scan costs one `ps`, one `top` per container and one
+    /// `inspect` per container found, however many PIDs it turned up.
+    async fn get_docker_containers(&self, pids: &[i32]) -> HashMap<i32, ContainerInfo> {
+        let mut containers = HashMap::new();
+        let mut uncached = Vec::new();
+        for &pid in pids {
+            match self.container_cache.get(pid) {
+                Some(container) => {
+                    containers.insert(pid, container);
+                }
+                None if !uncached.contains(&pid) => uncached.push(pid),
+                None => {}
+            }
+        }
+        if uncached.is_empty() {
+            return containers;
+        }
+
+        // Lookups that failed aren't cached, so they are retried on the next scan
+        let (container_ids, cacheable) = match self.find_container_ids_for_pids(&uncached).await {
+            Ok(Some(container_ids)) => (container_ids, true),
+            Ok(None) => (HashMap::new(), false),
+            Err(e) => {
+                self.log_deduper.warn(format!("Docker container lookup failed: {}", e));
+                (HashMap::new(), false)
             }
-        } else {
-            None
         };
 
-        (container_id, container_name)
+        let mut details: HashMap<&str, ContainerInfo> = HashMap::new();
+        for pid in uncached {
+            let container = match container_ids.get(&pid) {
+                Some(id) => match details.get(id.as_str()) {
+                    Some(container) => container.clone(),
+                    None => {
+                        let container = self.get_container_info(id).await;
+                        details.insert(id, container.clone());
+                        container
+                    }
+                },
+                None => ContainerInfo::default(),
+            };
+            if cacheable {
+                self.container_cache.insert(pid, container.clone());
+            }
+            containers.insert(pid, container);
+        }
+        containers
+    }
+
+    /// The id, name and Compose project of a container.
+    async fn get_container_info(&self, container_id: &str) -> ContainerInfo {
+        match self.get_container_details(container_id).await {
+            Ok((name, compose_project)) => ContainerInfo { id: Some(container_id.to_string()), name: Some(name), compose_project },
+            Err(e) => {
+                self.log_deduper.warn(format!("Docker container name lookup failed: {}", e));
+                ContainerInfo { id: Some(container_id.to_string()), ..ContainerInfo::default() }
+            }
+        }
     }
 
-    async fn find_container_id_for_pid(&self, pid: i32) -> Result<Option<String>> {
-        // Use docker ps to get all running containers
-        let output = Command::new("docker")
-            .args(&["ps", "--format", "table {{.ID}}\t{{.Names}}\t{{.Ports}}"])
-            .output()
-            .context("Failed to execute docker ps command")?;
+    /// Which running container each of `pids` belongs to, listing the containers once and
+    /// stopping as soon as every PID is placed. `None` when the runtime couldn't list its
+    /// containers.
+    async fn find_container_ids_for_pids(&self, pids: &[i32]) -> Result<Option<HashMap<i32, String>>, PortKillError> {
+        let output = self.container_cli.output(&["ps", "--format", "{{.ID}}"])?;
 
         if !output.status.success() {
+            // Usually the daemon isn't running; the process is then treated as a plain one
+            self.log_deduper.warn(format!("{} ps failed: {}", self.container_cli.program(), String::from_utf8_lossy(&output.stderr).trim()));
             return Ok(None);
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        
-        for line in stdout.lines().skip(1) { // Skip header
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                let container_id = parts[0].trim();
-                let _ports_str = parts[2].trim();
-                
-                // Check if this container is using the port we're interested in
-                if self.container_has_pid(container_id, pid).await? {
-                    return Ok(Some(container_id.to_string()));
+        let mut found = HashMap::new();
+        for container_id in String::from_utf8_lossy(&output.stdout).lines().map(str::trim).filter(|id| !id.is_empty()) {
+            if found.len() == pids.len() {
+                break;
+            }
+            for pid in self.container_pids(container_id).await? {
+                if pids.contains(&pid) {
+                    found.insert(pid, container_id.to_string());
                 }
             }
         }
-
-        Ok(None)
+        Ok(Some(found))
     }
 
-    async fn container_has_pid(&self, container_id: &str, pid: i32) -> Result<bool> {
+    /// The host PIDs of every process in a container.
+    async fn container_pids(&self, container_id: &str) -> Result<Vec<i32>, PortKillError> {
         // Use docker top to get processes in the container
-        let output = Command::new("docker")
-            .args(&["top", container_id])
-            .output()
-            .context("Failed to execute docker top command")?;
+        let output = self.container_cli.output(&self.container_cli.top_args(container_id))?;
 
         if !output.status.success() {
-            return Ok(false);
+            return Ok(Vec::new());
         }
+        Ok(self.container_cli.parse_top_pids(&String::from_utf8_lossy(&output.stdout)))
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        
-        // Check if the PID exists in the container's process list
-        for line in stdout.lines().skip(1) { // Skip header
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 2 {
-                if let Ok(container_pid) = parts[1].parse::<i32>() {
-                    if container_pid == pid {
-                        return Ok(true);
-                    }
-                }
-            }
+    /// The name of a container and the Compose project it belongs to, if any.
+    async fn get_container_details(&self, container_id: &str) -> Result<(String, Option<String>), PortKillError> {
+        // Get container name and project label using docker inspect
+        let format = format!("{{{{.Name}}}}\t{{{{index .Config.Labels \"{}\"}}}}", compose::PROJECT_LABEL);
+        let output = self.container_cli.output(&["inspect", "--format", &format, container_id])?;
+
+        if output.status.success() {
+            Ok(parse_container_details(&String::from_utf8_lossy(&output.stdout)))
+        } else {
+            Ok((container_id.to_string(), None))
         }
+    }
 
-        Ok(false)
+    /// Whether `pid` holds one of the endpoints found by the latest scan.
+    pub fn is_monitored_pid(&self, pid: i32) -> bool {
+        self.current_processes.values().any(|p| p.pid == pid)
     }
 
-    async fn get_container_name(&self, container_id: &str) -> Result<String> {
-        // Get container name using docker inspect
-        let output = Command::new("docker")
-            .args(&["inspect", "--format", "{{.Name}}", container_id])
-            .output()
-            .context("Failed to execute docker inspect command")?;
+    pub async fn kill_process(&self, pid: i32) -> Result<(), PortKillError> {
+        let process_info = self.current_processes.values().find(|p| p.pid == pid).cloned();
+        self.kill_target(pid, process_info.as_ref()).await
+    }
 
-        if output.status.success() {
-            let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            // Remove leading slash if present
-            Ok(name.trim_start_matches('/').to_string())
-        } else {
-            Ok(container_id.to_string())
+    /// Kill the process described by `process_info`, as scanned just now rather than by the
+    /// latest monitoring scan, along with any others sharing its socket.
+    pub async fn kill_scanned(&self, process_info: &ProcessInfo) -> Result<(), PortKillError> {
+        self.kill_target(process_info.pid, Some(process_info)).await
+    }
+
+    /// Scan `port` and kill whatever holds it, returning what was killed, or
+    /// [`PortKillError::NoProcess`] if the port is free.
+    pub async fn kill_port(&self, port: u16) -> Result<ProcessInfo, PortKillError> {
+        let process_info = self.get_process_on_port(port).await?.ok_or(PortKillError::NoProcess(port))?;
+        self.kill_scanned(&process_info).await?;
+        Ok(process_info)
+    }
+
+    /// Kill `pid`, described by `process_info` if it was scanned, and record the attempt in
+    /// the `--log-file` history.
+    async fn kill_target(&self, pid: i32, process_info: Option<&ProcessInfo>) -> Result<(), PortKillError> {
+        let mut record = KillRecord::new(pid, process_info, self.kill_options.signal);
+        let result = self.kill_and_describe(pid, process_info, &mut record).await;
+        if !self.dry_run {
+            if result.is_ok() {
+                self.kill_count.fetch_add(1, Ordering::Relaxed);
+            }
+            if let Some(kill_log) = &self.kill_log {
+                kill_log.record(&record.finish(&result));
+            }
         }
+        result
     }
 
-    pub async fn kill_process(&self, pid: i32) -> Result<()> {
+    /// Kill `pid` and the other processes `process_info` lists on the same socket, noting in
+    /// `record` how it went.
+    async fn kill_and_describe(&self, pid: i32, process_info: Option<&ProcessInfo>, record: &mut KillRecord) -> Result<(), PortKillError> {
+        self.check_kill_allowed(&format!("kill process {}", pid))?;
         info!("Attempting to kill process {}", pid);
 
+        let shared_pids = match process_info {
+            Some(process_info) if self.kill_options.verify => self.verify_holder(pid, process_info).await?,
+            Some(process_info) => process_info.shared_pids.clone(),
+            None => Vec::new(),
+        };
+        // Everything the latest scan knows about the PID, or just what the caller scanned when
+        // it isn't monitoring continuously
+        let mut scanned: Vec<&ProcessInfo> = self.current_processes.values().filter(|p| p.pid == pid).collect();
+        if scanned.is_empty() {
+            scanned.extend(process_info);
+        }
+        let protection = {
+            let policy = policy::read(&self.policy);
+            if policy.is_excluded_pid(pid) {
+                return Err(PortKillError::Refused {
+                    pid,
+                    reason: "it is port-kill, its parent, or excluded by --exclude-pids".to_string(),
+                });
+            }
+            if policy.is_only_connected(scanned.iter().copied()) {
+                return Err(PortKillError::Refused {
+                    pid,
+                    reason: "it is only connected to a monitored port, not listening on one (pass --kill-established to allow this)".to_string(),
+                });
+            }
+            scanned.iter().find_map(|p| policy.protection(p))
+        };
+        if let Some(reason) = protection {
+            return Err(PortKillError::Refused { pid, reason: reason.to_string() });
+        }
+        let shared_pids = self.killable_with(pid, &shared_pids);
+
         // Check if this is a Docker container process
         if self.docker_enabled {
-            if let Some(container_id) = self.find_container_id_for_pid(pid).await? {
+            let container_id = self.find_container_ids_for_pids(&[pid]).await?.unwrap_or_default().remove(&pid);
+            if let Some(container_id) = container_id {
                 info!("Process {} is in Docker container {}, stopping container", pid, container_id);
+                record.container_id = Some(container_id.clone());
                 return self.stop_docker_container(&container_id).await;
             }
         }
 
-        // First try SIGTERM
-        match kill(Pid::from_raw(pid), Signal::SIGTERM) {
+        // Find the children first: once the parent exits they are reparented and no longer
+        // trace back to it
+        let descendants = if self.kill_options.tree { self.killable_with(pid, &descendant_pids(pid)) } else { Vec::new() };
+
+        if self.dry_run {
+            match scanned.first() {
+                Some(process_info) => info!("Dry run: would send {} to PID {} on port {}", self.kill_options.signal, pid, process_info.endpoint()),
+                None => info!("Dry run: would send {} to PID {}", self.kill_options.signal, pid),
+            }
+            if !descendants.is_empty() {
+                info!("Dry run: would also kill its descendants {:?}", descendants);
+            }
+            if !shared_pids.is_empty() {
+                info!("Dry run: would also kill {:?}, which share its socket", shared_pids);
+            }
+            return Ok(());
+        }
+
+        record.escalated_to_sigkill = self.signal_process(pid).await?;
+        self.kill_descendants(pid, &descendants).await?;
+        self.kill_shared(pid, &shared_pids).await
+    }
+
+    /// Those of `others`, processes a kill of `pid` would take down too, that the policy lets
+    /// be killed: not excluded, and not protected going by the latest scan or by who owns them.
+    fn killable_with(&self, pid: i32, others: &[i32]) -> Vec<i32> {
+        if others.is_empty() {
+            return Vec::new();
+        }
+        let owners = process_owners(others);
+        let policy = policy::read(&self.policy);
+        others
+            .iter()
+            .copied()
+            .filter(|&other| {
+                let unscanned = ProcessInfo { pid: other, uid: owners.get(&other).copied(), ..Default::default() };
+                let reason = if policy.is_excluded_pid(other) {
+                    Some("it is port-kill, its parent, or excluded by --exclude-pids")
+                } else {
+                    self.current_processes
+                        .values()
+                        .filter(|p| p.pid == other)
+                        .chain([&unscanned])
+                        .find_map(|p| policy.protection(p))
+                };
+                if let Some(reason) = reason {
+                    warn!("Not killing process {} along with process {}: {}", other, pid, reason);
+                }
+                reason.is_none()
+            })
+            .collect()
+    }
+
+    /// Check that `pid` still holds the endpoint of `process_info`, scanning it again: between
+    /// the scan and the kill it may have exited and its PID been reused by an unrelated process.
+    /// Returns the other processes holding it now, of those `process_info` lists.
+    async fn verify_holder(&self, pid: i32, process_info: &ProcessInfo) -> Result<Vec<i32>, PortKillError> {
+        let endpoint = process_info.endpoint();
+        let holders: Vec<i32> = match &endpoint {
+            Endpoint::Port { port, protocol } => self
+                .scanner
+                .scan(&[*port], &[*protocol])
+                .await?
+                .remove(&endpoint)
+                .map(|holder| holder.all_pids().collect())
+                .unwrap_or_default(),
+            Endpoint::UnixSocket(path) => unix_socket_holders(path)?,
+            Endpoint::Connection { port, .. } => connection_holders(*port)?,
+        };
+        check_holder(pid, process_info, &holders)
+    }
+
+    /// Kill whichever of `shared_pids`, the other processes listening on `pid`'s socket, are
+    /// still running: a forking server's workers keep the port open after their parent goes.
+    /// Every one is attempted; the first failure is returned.
+    async fn kill_shared(&self, pid: i32, shared_pids: &[i32]) -> Result<(), PortKillError> {
+        let mut first_failure = None;
+        for &other in shared_pids {
+            if !self.is_process_running(other).await {
+                continue;
+            }
+            info!("Process {} shares the socket of process {}, killing it too", other, pid);
+            if let Err(e) = self.signal_process(other).await {
+                warn!("Failed to kill process {}, which shares the socket of process {}: {}", other, pid, e);
+                first_failure.get_or_insert(e);
+            }
+        }
+        first_failure.map_or(Ok(()), Err)
+    }
+
+    /// Kill whichever of `descendants` outlived their ancestor `pid`. Well-behaved parents
+    /// take their workers down with them, so most are usually gone already.
+    /// Every one is attempted; the first failure is returned.
+    async fn kill_descendants(&self, pid: i32, descendants: &[i32]) -> Result<(), PortKillError> {
+        let mut first_failure = None;
+        for &child in descendants {
+            if !self.is_process_running(child).await {
+                continue;
+            }
+            info!("Descendant {} of process {} is still running, killing it", child, pid);
+            if let Err(e) = self.signal_process(child).await {
+                warn!("Failed to kill descendant {} of process {}: {}", child, pid, e);
+                first_failure.get_or_insert(e);
+            }
+        }
+        first_failure.map_or(Ok(()), Err)
+    }
+
+    /// Send the configured signal to `pid` and escalate to SIGKILL if it outlives the grace
+    /// period, returning whether it had to be escalated.
+    async fn signal_process(&self, pid: i32) -> Result<bool, PortKillError> {
+        // First try the configured signal
+        let KillOptions { signal, timeout, sigkill, .. } = self.kill_options;
+        let escalated = match self.send_signal(pid, signal).await {
             Ok(_) => {
-                info!("Sent SIGTERM to process {}", pid);
-                
-                // Wait a bit and check if process is still alive
-                sleep(Duration::from_millis(500)).await;
-                
-                // Check if process is still running
-                if self.is_process_running(pid).await {
-                    warn!("Process {} still running after SIGTERM, sending SIGKILL", pid);
+                info!("Sent {} to process {}", signal, pid);
+                if signal == Signal::SIGKILL {
+                    return Ok(false);
+                }
+
+                // Give the process up to the grace period to exit
+                let deadline = Instant::now() + timeout;
+                let mut running = self.is_process_running(pid).await;
+                while running && Instant::now() < deadline {
+                    sleep(KILL_POLL_INTERVAL.min(timeout)).await;
+                    running = self.is_process_running(pid).await;
+                }
+
+                if running && !sigkill {
+                    warn!("Process {} still running {}ms after {}, not sending SIGKILL", pid, timeout.as_millis(), signal);
+                    return Err(PortKillError::StillRunning { pid, signal, timeout });
+                }
+
+                if running {
+                    warn!("Process {} still running after {}, sending SIGKILL", pid, signal);
                     
                     // Send SIGKILL if process is still alive
-                    match kill(Pid::from_raw(pid), Signal::SIGKILL) {
+                    match self.send_signal(pid, Signal::SIGKILL).await {
                         Ok(_) => {
                             info!("Sent SIGKILL to process {}", pid);
                         }
-                        Err(e) => {
-                            error!("Failed to send SIGKILL to process {}: {}", pid, e);
-                            return Err(anyhow::anyhow!("Failed to kill process: {}", e));
+                        Err(source) => {
+                            error!("Failed to send SIGKILL to process {}: {}", pid, source);
+                            return Err(PortKillError::KillFailed { pid, signal: Signal::SIGKILL, source });
                         }
                     }
                 } else {
-                    info!("Process {} terminated successfully with SIGTERM", pid);
+                    info!("Process {} terminated successfully with {}", pid, signal);
                 }
+                running
             }
-            Err(e) => {
-                error!("Failed to send SIGTERM to process {}: {}", pid, e);
-                return Err(anyhow::anyhow!("Failed to kill process: {}", e));
+            Err(source) => {
+                error!("Failed to send {} to process {}: {}", signal, pid, source);
+                return Err(PortKillError::KillFailed { pid, signal, source });
+            }
+        };
+
+        Ok(escalated)
+    }
+
+    /// Send `signal` to `pid`, retrying with exponential backoff up to `--kill-retries` times
+    /// if that fails, e.g. with EPERM while a container's cgroups are being moved. The error
+    /// is the last failure.
+    async fn send_signal(&self, pid: i32, signal: Signal) -> Result<(), nix::Error> {
+        let retries = self.kill_options.retries;
+        let mut retry = 0;
+        loop {
+            let error = match kill(Pid::from_raw(pid), signal) {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            if retry >= retries {
+                if retries > 0 {
+                    warn!("Giving up sending {} to process {} after {} attempts", signal, pid, retries + 1);
+                }
+                return Err(error);
+            }
+
+            let delay = KillOptions::retry_delay(retry);
+            warn!("Failed to send {} to process {}: {}, retrying in {:?}", signal, pid, error, delay);
+            sleep(delay).await;
+            retry += 1;
+            if !self.is_process_running(pid).await {
+                info!("Process {} exited before {} could be resent", pid, signal);
+                return Ok(());
             }
         }
+    }
 
-        Ok(())
+    /// Stop every running container whose name matches `pattern` (glob or substring),
+    /// returning each matched container name with the outcome of stopping it.
+    pub async fn kill_containers_matching(&self, pattern: &str) -> Result<Vec<(String, Result<(), PortKillError>)>, PortKillError> {
+        self.check_kill_allowed(&format!("stop containers matching '{}'", pattern))?;
+        let output = self.container_cli.output(&["ps", "--format", "{{.ID}}\t{{.Names}}"])?;
+        if !output.status.success() {
+            return Err(self.container_cli.failure("ps", &output));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut results = Vec::new();
+
+        for line in stdout.lines() {
+            let Some((container_id, container_name)) = line.split_once('\t') else {
+                continue;
+            };
+            if !pattern::matches(pattern, container_name) {
+                continue;
+            }
+
+            info!("Container {} matches '{}'", container_name, pattern);
+            let result = self.stop_docker_container(container_id).await;
+            results.push((container_name.to_string(), result));
+        }
+
+        Ok(results)
+    }
+
+    /// Stop every running container in a Compose project, reporting each by name.
+    pub async fn kill_compose_project(&self, project: &str) -> Result<Vec<(String, Result<(), PortKillError>)>, PortKillError> {
+        self.check_kill_allowed(&format!("stop Compose project {}", project))?;
+        let mut results = Vec::new();
+        for (container_id, container_name) in compose::project_containers(&self.container_cli, project)? {
+            let result = self.stop_docker_container(&container_id).await;
+            results.push((container_name, result));
+        }
+        Ok(results)
+    }
+
+    /// The shell commands a user could run to do what `kill_process` would do for this process.
+    pub fn kill_commands(&self, process_info: &ProcessInfo) -> Vec<String> {
+        if let Some(ref container_id) = process_info.container_id {
+            let docker = self.container_cli.command_line();
+            return vec![
+                format!("{} stop {}", docker, container_id),
+                format!("{} rm -f {}   # only if the stop fails", docker, container_id),
+            ];
+        }
+
+        let KillOptions { signal, timeout, sigkill, tree, .. } = self.kill_options;
+        let pids: Vec<String> = process_info.all_pids().map(|pid| pid.to_string()).collect();
+        let mut commands = vec![format!("kill -{} {}", signal_name(signal), pids.join(" "))];
+        if tree {
+            commands.push(format!("pkill -{} -P {}   # and so on for their children", signal_name(signal), process_info.pid));
+        }
+        if sigkill && signal != Signal::SIGKILL {
+            commands.push(format!("kill -KILL {}   # only if still running after {}ms", pids.join(" "), timeout.as_millis()));
+        }
+        commands
     }
 
-    async fn stop_docker_container(&self, container_id: &str) -> Result<()> {
+    async fn stop_docker_container(&self, container_id: &str) -> Result<(), PortKillError> {
+        if self.dry_run {
+            info!("Dry run: would stop Docker container {}", container_id);
+            return Ok(());
+        }
+
         info!("Stopping Docker container: {}", container_id);
 
         // First try graceful stop
-        let stop_output = Command::new("docker")
-            .args(&["stop", container_id])
-            .output()
-            .context("Failed to execute docker stop command")?;
+        let stop_output = self.container_cli.output(&["stop", container_id])?;
 
         if stop_output.status.success() {
             info!("Docker container {} stopped gracefully", container_id);
@@ -290,45 +994,107 @@ impl ProcessMonitor {
 
         // If graceful stop failed, try force remove
         info!("Graceful stop failed, force removing container: {}", container_id);
-        let remove_output = Command::new("docker")
-            .args(&["rm", "-f", container_id])
-            .output()
-            .context("Failed to execute docker rm command")?;
+        let remove_output = self.container_cli.output(&["rm", "-f", container_id])?;
 
         if remove_output.status.success() {
             info!("Docker container {} force removed", container_id);
             Ok(())
         } else {
-            let error_msg = String::from_utf8_lossy(&remove_output.stderr);
-            Err(anyhow::anyhow!("Failed to remove Docker container {}: {}", container_id, error_msg))
+            Err(PortKillError::ContainerStopFailed {
+                container: container_id.to_string(),
+                stderr: String::from_utf8_lossy(&remove_output.stderr).trim().to_string(),
+            })
         }
     }
 
-    pub async fn kill_all_processes(&self) -> Result<()> {
+    pub async fn kill_all_processes(&self) -> Result<(), PortKillError> {
+        let attempts = self.kill_all().await?;
+        self.kill_outcome(&attempts)
+    }
+
+    /// Collapse the attempts of a kill-all into one error naming every failure.
+    pub fn kill_outcome(&self, attempts: &[KillAttempt]) -> Result<(), PortKillError> {
+        let failures: Vec<KillFailure> = attempts
+            .iter()
+            .filter_map(|r| {
+                let error = r.error.clone()?;
+                Some(KillFailure { pid: r.pid, endpoint: Some(r.endpoint.clone()), error })
+            })
+            .collect();
+
+        if !failures.is_empty() {
+            return Err(KillsFailed { failures, stopped_early: self.fail_fast }.into());
+        }
+
+        info!("All processes killed successfully");
+        Ok(())
+    }
+
+    /// Kill every monitored process and report each kill that was attempted. With fail-fast
+    /// the list ends at the first failure.
+    pub async fn kill_all(&self) -> Result<Vec<KillAttempt>, PortKillError> {
+        self.check_kill_allowed("kill all monitored processes")?;
         info!("Killing all monitored processes");
 
         let processes = self.scan_processes().await?;
-        let mut errors = Vec::new();
+        self.kill_processes(&processes).await
+    }
 
-        for (port, process_info) in processes {
-            info!("Killing process on port {} (PID: {})", port, process_info.pid);
-            if let Err(e) = self.kill_process(process_info.pid).await {
-                errors.push(format!("Port {} (PID {}): {}", port, process_info.pid, e));
-            }
+    /// Kill the processes of an earlier scan, such as one a user has just confirmed, instead
+    /// of whatever a fresh scan would find.
+    pub async fn kill_processes(&self, processes: &HashMap<Endpoint, ProcessInfo>) -> Result<Vec<KillAttempt>, PortKillError> {
+        self.check_kill_allowed("kill all monitored processes")?;
+
+        let targets = self.kill_targets(processes);
+        if targets.len() < processes.len() {
+            info!("Skipping {} entries that are protected or share a PID", processes.len() - targets.len());
         }
 
-        if !errors.is_empty() {
-            let error_msg = errors.join("; ");
-            return Err(anyhow::anyhow!("Some processes failed to kill: {}", error_msg));
+        Ok(self.kill_each(targets).await)
+    }
+
+    /// The processes a kill-all of `processes` would signal: protected ones are left out and
+    /// each PID appears once.
+    pub fn kill_targets<'a>(&self, processes: &'a HashMap<Endpoint, ProcessInfo>) -> Vec<&'a ProcessInfo> {
+        policy::snapshot(&self.policy).kill_targets(processes)
+    }
+
+    async fn kill_each(&self, targets: Vec<&ProcessInfo>) -> Vec<KillAttempt> {
+        let mut results = Vec::new();
+        let total = targets.len();
+
+        for process_info in targets {
+            let endpoint = process_info.endpoint();
+            info!("Killing process on port {} (PID: {})", endpoint, process_info.pid);
+            let error = if self.dry_run {
+                match process_info.container_id {
+                    Some(ref container_id) => info!("Dry run: would stop Docker container {} on port {}", container_id, endpoint),
+                    None => info!("Dry run: would send {} to PID {} on port {}", self.kill_options.signal, process_info.pid, endpoint),
+                }
+                None
+            } else {
+                self.kill_target(process_info.pid, Some(process_info)).await.err().map(|e| e.to_string())
+            };
+            let failed = error.is_some();
+            results.push(KillAttempt {
+                pid: process_info.pid,
+                endpoint,
+                error,
+            });
+
+            if failed && self.fail_fast {
+                warn!("Fail-fast: not attempting the remaining {} kill(s)", total - results.len());
+                break;
+            }
         }
 
-        info!("All processes killed successfully");
-        Ok(())
+        results
     }
 
-    async fn is_process_running(&self, pid: i32) -> bool {
+    /// Whether a process with this PID exists.
+    pub async fn is_process_running(&self, pid: i32) -> bool {
         let output = Command::new("ps")
-            .args(&["-p", &pid.to_string()])
+            .args(["-p", &pid.to_string()])
             .output();
 
         match output {
@@ -337,3 +1103,864 @@ impl ProcessMonitor {
         }
     }
 }
+
+/// What a PID lookup found out about the container a process runs in.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ContainerInfo {
+    id: Option<String>,
+    name: Option<String>,
+    compose_project: Option<String>,
+}
+
+impl ContainerInfo {
+    fn apply_to(self, process_info: &mut ProcessInfo) {
+        process_info.container_id = self.id;
+        process_info.container_name = self.name;
+        process_info.compose_project = self.compose_project;
+    }
+}
+
+/// Container lookups by PID, reused for [`CONTAINER_CACHE_TTL`] so scans don't ask the
+/// container runtime about processes that were already resolved.
+#[derive(Debug)]
+struct ContainerCache {
+    ttl: Duration,
+    entries: std::sync::Mutex<HashMap<i32, (Instant, ContainerInfo)>>,
+}
+
+impl Default for ContainerCache {
+    fn default() -> Self {
+        Self::new(CONTAINER_CACHE_TTL)
+    }
+}
+
+impl ContainerCache {
+    fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: Default::default() }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<i32, (Instant, ContainerInfo)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The cached lookup for `pid`, unless it has expired.
+    fn get(&self, pid: i32) -> Option<ContainerInfo> {
+        let entries = self.entries();
+        let (looked_up, container) = entries.get(&pid)?;
+        (looked_up.elapsed() < self.ttl).then(|| container.clone())
+    }
+
+    fn insert(&self, pid: i32, container: ContainerInfo) {
+        self.entries().insert(pid, (Instant::now(), container));
+    }
+
+    /// Forget every PID not in `pids`, whose processes have gone away.
+    fn retain(&self, pids: &HashSet<i32>) {
+        self.entries().retain(|pid, _| pids.contains(pid));
+    }
+}
+
+/// Fill in the working directory, start time and command line of every scanned process.
+pub fn add_process_context(processes: &mut HashMap<Endpoint, ProcessInfo>) {
+    let pids: Vec<i32> = processes.values().map(|p| p.pid).collect();
+    let mut contexts = scanner::process_context(&pids);
+    for process_info in processes.values_mut() {
+        if let Some(context) = contexts.remove(&process_info.pid) {
+            apply_context(process_info, context);
+        }
+    }
+}
+
+/// Copy `context` into `process_info`. A known command line replaces the command, which the
+/// scanners only get as the truncated executable name.
+fn apply_context(process_info: &mut ProcessInfo, context: ProcessContext) {
+    if let Some(argv) = context.argv.as_ref().filter(|argv| !argv.is_empty()) {
+        process_info.command = argv.join(" ");
+    }
+    (process_info.cwd, process_info.start_time, process_info.started_at, process_info.argv) =
+        (context.cwd, context.start_time, context.started_at, context.argv);
+}
+
+/// Split `docker inspect` output of the form `/name<TAB>project` into the container name and
+/// the Compose project, if the label was set.
+fn parse_container_details(output: &str) -> (String, Option<String>) {
+    let output = output.trim();
+    let (name, project) = output.split_once('\t').unwrap_or((output, ""));
+    // Remove leading slash if present
+    (name.trim_start_matches('/').to_string(), compose::parse_project_label(project))
+}
+
+/// Every process descended from `pid`, children before grandchildren. Processes that were
+/// reparented to init or a subreaper no longer trace back to `pid` and are not included.
+pub fn descendant_pids(pid: i32) -> Vec<i32> {
+    // Init and the kernel's own processes are everyone's ancestors
+    if pid <= 1 {
+        return Vec::new();
+    }
+    match Command::new("ps").args(["-A", "-o", "pid=", "-o", "ppid="]).output() {
+        Ok(output) if output.status.success() => descendants(&parse_parent_pids(&String::from_utf8_lossy(&output.stdout)), pid),
+        Ok(output) => {
+            warn!("ps failed listing processes: {}", String::from_utf8_lossy(&output.stderr).trim());
+            Vec::new()
+        }
+        Err(e) => {
+            warn!("Failed to execute ps command: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The user ID each of `pids` runs as, leaving out PIDs that have exited.
+fn process_owners(pids: &[i32]) -> HashMap<i32, u32> {
+    let pid_list = pids.iter().map(|pid| pid.to_string()).collect::<Vec<_>>().join(",");
+    match Command::new("ps").args(["-p", &pid_list, "-o", "pid=", "-o", "uid="]).output() {
+        Ok(output) => parse_process_owners(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => {
+            warn!("Failed to execute ps command: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Parse `ps -o pid= -o uid=` output.
+fn parse_process_owners(output: &str) -> HashMap<i32, u32> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            Some((columns.next()?.parse().ok()?, columns.next()?.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Parse `ps -o pid= -o ppid=` output into the children of each parent PID.
+fn parse_parent_pids(output: &str) -> HashMap<i32, Vec<i32>> {
+    let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+    for line in output.lines() {
+        let mut fields = line.split_whitespace().map(str::parse::<i32>);
+        if let (Some(Ok(pid)), Some(Ok(ppid))) = (fields.next(), fields.next()) {
+            children.entry(ppid).or_default().push(pid);
+        }
+    }
+    children
+}
+
+/// Walk `children` breadth-first from `root`. Each PID is visited once, so the walk ends
+/// even if the listing changed mid-read and contains a cycle.
+fn descendants(children: &HashMap<i32, Vec<i32>>, root: i32) -> Vec<i32> {
+    let mut seen = HashSet::from([root]);
+    let mut found = Vec::new();
+    let mut queue = VecDeque::from([root]);
+    while let Some(parent) = queue.pop_front() {
+        for &child in children.get(&parent).into_iter().flatten() {
+            if seen.insert(child) {
+                found.push(child);
+                queue.push_back(child);
+            }
+        }
+    }
+    found
+}
+
+/// A signal's name as `kill -<NAME>` takes it, e.g. `TERM`.
+fn signal_name(signal: Signal) -> &'static str {
+    signal.as_str().trim_start_matches("SIG")
+}
+
+/// Processes listening on Unix sockets whose path matches `pattern` (see
+/// [`pattern::matches_path`]), one entry per socket per PID. Every daemon on the host has
+/// such sockets, so the listing is always scoped.
+pub fn list_unix_sockets(pattern: &str) -> Result<Vec<ProcessInfo>, PortKillError> {
+    let output = Command::new("lsof")
+        .args(["-U", "-F", "pcnT"])
+        .output()
+        .map_err(|e| PortKillError::from_command("lsof", e))?;
+
+    if !output.status.success() && output.stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(denied) = LsofPermissionDenied::from_stderr(&stderr) {
+            return Err(denied.into());
+        }
+    }
+
+    let mut sockets = parse_unix_sockets(&String::from_utf8_lossy(&output.stdout));
+    sockets.retain(|p| p.socket_path.as_deref().is_some_and(|path| pattern::matches_path(pattern, path)));
+    Ok(sockets)
+}
+
+/// PIDs listening on the Unix socket at `path`.
+pub fn unix_socket_holders(path: &str) -> Result<Vec<i32>, PortKillError> {
+    Ok(list_unix_sockets(path)?
+        .into_iter()
+        .filter(|socket| socket.socket_path.as_deref() == Some(path))
+        .map(|socket| socket.pid)
+        .collect())
+}
+
+/// Processes with an established TCP connection to one of `ports`, one entry per process
+/// and port, for `--include-established`.
+pub fn list_connections(ports: &[u16]) -> Result<Vec<ProcessInfo>, PortKillError> {
+    let (Some(first), Some(last)) = (ports.iter().min(), ports.iter().max()) else {
+        return Ok(Vec::new());
+    };
+    // lsof selects connections whose either end is in the range, the remote port is checked below
+    let output = Command::new("lsof")
+        .args(["-n", "-P", "-F", "pcn", &format!("-iTCP:{}-{}", first, last), "-sTCP:ESTABLISHED"])
+        .output()
+        .map_err(|e| PortKillError::from_command("lsof", e))?;
+
+    if !output.status.success() && output.stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(denied) = LsofPermissionDenied::from_stderr(&stderr) {
+            return Err(denied.into());
+        }
+    }
+
+    let ports: HashSet<u16> = ports.iter().copied().collect();
+    let mut connections = parse_connections(&String::from_utf8_lossy(&output.stdout));
+    connections.retain(|connection| ports.contains(&connection.port));
+    Ok(connections)
+}
+
+/// PIDs with an established TCP connection to `port`.
+pub fn connection_holders(port: u16) -> Result<Vec<i32>, PortKillError> {
+    Ok(list_connections(&[port])?.into_iter().map(|connection| connection.pid).collect())
+}
+
+/// Parse `lsof -F pcn` output for TCP connections, named `local->remote`, into one entry per
+/// process and remote port: the port the process connected to. Each process starts with
+/// `p<pid>` and `c<command>`; names without a remote end are skipped.
+pub fn parse_connections(output: &str) -> Vec<ProcessInfo> {
+    let mut connections: Vec<ProcessInfo> = Vec::new();
+    let mut pid = None;
+    let mut command = "";
+
+    for line in output.lines() {
+        let Some(field) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[field.len_utf8()..];
+        match field {
+            'p' => {
+                pid = value.parse().ok();
+                command = "";
+            }
+            'c' => command = value,
+            'n' => {
+                let Some((pid, (_, remote))) = pid.zip(value.split_once("->")) else {
+                    continue;
+                };
+                let Some(port) = scanner::port_from_lsof_name(remote) else {
+                    continue;
+                };
+                if connections.iter().any(|c| c.pid == pid && c.port == port) {
+                    continue;
+                }
+                connections.push(ProcessInfo {
+                    pid,
+                    port,
+                    command: command.to_string(),
+                    name: command.to_string(),
+                    state: SocketState::Established,
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    connections
+}
+
+/// Check that `pid` is among the `holders` of the endpoint `process_info` was scanned on,
+/// returning the processes `process_info` lists as sharing it that still do. An error means
+/// the process is gone and its PID may have been reused, so it must not be signalled.
+pub fn check_holder(pid: i32, process_info: &ProcessInfo, holders: &[i32]) -> Result<Vec<i32>, PortKillError> {
+    if !holders.contains(&pid) {
+        let endpoint = process_info.endpoint();
+        warn!("Process {} no longer holds {}, not killing it", pid, endpoint);
+        return Err(PortKillError::Refused {
+            pid,
+            reason: format!("it no longer holds {}, and the PID may have been reused (--no-verify skips this check)", endpoint),
+        });
+    }
+    Ok(process_info.shared_pids.iter().copied().filter(|other| holders.contains(other)).collect())
+}
+
+/// Parse `lsof -U -F pcnT` output. Each process starts with `p<pid>` and `c<command>`,
+/// followed by an `n<name>` line per socket and, on Linux, a `TST=<state>` line after it.
+/// Unnamed and abstract sockets are skipped, as are sockets in a state other than LISTEN;
+/// macOS reports no state, so there every named socket counts.
+pub fn parse_unix_sockets(output: &str) -> Vec<ProcessInfo> {
+    fn socket(pid: i32, command: &str, path: String) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            command: command.to_string(),
+            name: command.to_string(),
+            socket_path: Some(path),
+            ..Default::default()
+        }
+    }
+
+    let mut sockets = Vec::new();
+    let mut pid = None;
+    let mut command = "";
+    // A named socket whose state line may still follow
+    let mut pending: Option<String> = None;
+
+    for line in output.lines() {
+        let Some(field) = line.chars().next() else {
+            continue;
+        };
+        let value = &line[field.len_utf8()..];
+        match field {
+            'p' => {
+                if let (Some(pid), Some(path)) = (pid, pending.take()) {
+                    sockets.push(socket(pid, command, path));
+                }
+                pid = value.parse().ok();
+                command = "";
+            }
+            'c' => command = value,
+            'n' => {
+                if let (Some(pid), Some(path)) = (pid, pending.take()) {
+                    sockets.push(socket(pid, command, path));
+                }
+                // Linux appends the socket type, e.g. `/tmp/app.sock type=STREAM`
+                let path = value.split_once(" type=").map_or(value, |(path, _)| path);
+                pending = path.starts_with('/').then(|| path.to_string());
+            }
+            'T' => {
+                if let Some(state) = value.strip_prefix("ST=") {
+                    if state != "LISTEN" {
+                        pending = None;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let (Some(pid), Some(path)) = (pid, pending) {
+        sockets.push(socket(pid, command, path));
+    }
+
+    sockets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::Runtime;
+    use crate::types::process_on;
+    use clap::Parser;
+    use crossbeam_channel::bounded;
+
+    /// Reports whatever processes the test puts in `processes`.
+    #[derive(Default, Clone)]
+    struct MockScanner {
+        processes: Arc<std::sync::Mutex<Vec<ProcessInfo>>>,
+    }
+
+    impl MockScanner {
+        fn set(&self, processes: Vec<ProcessInfo>) {
+            *self.processes.lock().unwrap() = processes;
+        }
+    }
+
+    impl PortScanner for MockScanner {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn scan<'a>(&'a self, ports: &'a [u16], protocols: &'a [Protocol]) -> scanner::ScanFuture<'a> {
+            let processes = self
+                .processes
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| ports.contains(&p.port) && protocols.contains(&p.protocol))
+                .map(|p| (p.endpoint(), p.clone()))
+                .collect();
+            Box::pin(async move { Ok(processes) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_updates_follow_scanned_processes() {
+        let (sender, receiver) = bounded(4);
+        let scanner = MockScanner::default();
+        let mut monitor = ProcessMonitor::new(sender, vec![3000, 3001], false)
+            .unwrap()
+            .with_scanner(Box::new(scanner.clone()));
+
+        // The first scan is reported even though nothing was found
+        monitor.check_for_updates().await;
+        assert_eq!(receiver.try_recv().unwrap().count, 0);
+
+        scanner.set(vec![process_on(3000, 100), process_on(8080, 200)]);
+        monitor.check_for_updates().await;
+        let update = receiver.try_recv().unwrap();
+        assert_eq!(update.count, 1);
+        assert_eq!(update.added_ports, vec![Endpoint::tcp(3000)]);
+
+        // An unchanged scan sends nothing
+        monitor.check_for_updates().await;
+        assert!(receiver.try_recv().is_err());
+
+        scanner.set(vec![process_on(3000, 101), process_on(3001, 300)]);
+        monitor.check_for_updates().await;
+        let update = receiver.try_recv().unwrap();
+        assert_eq!(update.added_ports, vec![Endpoint::tcp(3001)]);
+        assert_eq!(update.restarted.len(), 1);
+        assert_eq!(update.restarted[0].new_pid, 101);
+
+        scanner.set(vec![]);
+        monitor.check_for_updates().await;
+        let update = receiver.try_recv().unwrap();
+        assert_eq!(update.count, 0);
+        assert_eq!(update.removed_ports, vec![Endpoint::tcp(3000), Endpoint::tcp(3001)]);
+    }
+
+    #[tokio::test]
+    async fn test_kill_all_kills_scanned_process() {
+        let (sender, _receiver) = bounded(1);
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let scanner = MockScanner::default();
+        scanner.set(vec![process_on(3000, child.id() as i32)]);
+        let monitor = ProcessMonitor::new(sender, vec![3000], false)
+            .unwrap()
+            .with_scanner(Box::new(scanner));
+
+        let attempts = monitor.kill_all().await.unwrap();
+        assert_eq!(attempts.len(), 1);
+        assert!(attempts[0].error.is_none());
+        assert!(child.wait().unwrap().code().is_none());
+    }
+
+    #[test]
+    fn test_container_cache() {
+        let container = ContainerInfo { id: Some("abc".to_string()), name: Some("web".to_string()), compose_project: None };
+        let cache = ContainerCache::default();
+        cache.insert(42, container.clone());
+        cache.insert(43, ContainerInfo::default());
+        assert_eq!(cache.get(42), Some(container));
+        assert_eq!(cache.get(43), Some(ContainerInfo::default()));
+        assert_eq!(cache.get(44), None);
+
+        // PIDs missing from a scan are dropped
+        cache.retain(&HashSet::from([43]));
+        assert_eq!(cache.get(42), None);
+        assert!(cache.get(43).is_some());
+
+        let expired = ContainerCache::new(Duration::ZERO);
+        expired.insert(42, ContainerInfo::default());
+        assert_eq!(expired.get(42), None);
+    }
+
+    #[tokio::test]
+    async fn test_kills_are_recorded_in_kill_log() {
+        let path = std::env::temp_dir().join(format!("port-kill-monitor-history-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (sender, _receiver) = bounded(1);
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let scanner = MockScanner::default();
+        scanner.set(vec![process_on(3000, child.id() as i32)]);
+        let monitor = ProcessMonitor::new(sender, vec![3000], false)
+            .unwrap()
+            .with_scanner(Box::new(scanner))
+            .with_kill_log(Some(KillLog::new(path.clone())));
+
+        monitor.kill_all().await.unwrap();
+        child.wait().unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let record: KillRecord = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!((record.pid, record.port, record.name.as_deref()), (child.id() as i32, Some(3000), Some("node")));
+        assert_eq!(record.result, crate::kill_log::KillResult::Killed);
+    }
+
+    #[tokio::test]
+    async fn test_watch_only_refuses_kills() {
+        let (sender, _receiver) = bounded(1);
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let pid = child.id() as i32;
+        let scanner = MockScanner::default();
+        scanner.set(vec![process_on(3000, pid)]);
+        let monitor = ProcessMonitor::new(sender, vec![3000], false)
+            .unwrap()
+            .with_scanner(Box::new(scanner))
+            .with_watch_only(true);
+
+        let error = monitor.kill_all_processes().await.unwrap_err();
+        assert!(error.to_string().contains("--watch-only"), "{}", error);
+        assert!(matches!(monitor.kill_process(pid).await, Err(PortKillError::WatchOnly { .. })));
+        assert!(child.try_wait().unwrap().is_none());
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kill_port_reports_free_port() {
+        let (sender, _receiver) = bounded(1);
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let pid = child.id() as i32;
+        let scanner = MockScanner::default();
+        scanner.set(vec![process_on(3000, pid)]);
+        let monitor = ProcessMonitor::new(sender, vec![3000, 3001], false)
+            .unwrap()
+            .with_scanner(Box::new(scanner));
+
+        assert!(matches!(monitor.kill_port(3001).await, Err(PortKillError::NoProcess(3001))));
+        assert_eq!(monitor.kill_port(3000).await.unwrap().pid, pid);
+        assert!(child.wait().is_ok_and(|status| !status.success()));
+    }
+
+    #[test]
+    fn test_full_channel_keeps_latest_update_without_blocking() {
+        let (sender, receiver) = bounded(1);
+        let mut monitor = ProcessMonitor::new(sender, vec![3000], false).unwrap();
+
+        monitor.pending_update = Some(ProcessUpdate::empty());
+        monitor.flush_pending_update();
+        assert!(monitor.pending_update.is_none());
+
+        // The channel is now full, so the next snapshot waits instead of blocking
+        let latest = HashMap::from([(Endpoint::tcp(3000), process_on(3000, 42))]);
+        monitor.pending_update = Some(ProcessUpdate::new(latest));
+        monitor.flush_pending_update();
+        assert!(monitor.pending_update.is_some());
+
+        assert_eq!(receiver.recv().unwrap().count, 0);
+        monitor.flush_pending_update();
+        assert_eq!(receiver.recv().unwrap().processes[&Endpoint::tcp(3000)].pid, 42);
+    }
+
+    #[tokio::test]
+    async fn test_monitoring_stops_on_shutdown() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = Arc::new(Mutex::new(ProcessMonitor::new(sender, vec![], false).unwrap()));
+        let (shutdown_sender, shutdown) = watch::channel(false);
+
+        let handle = tokio::spawn(ProcessMonitor::start_monitoring(monitor.clone(), shutdown));
+        shutdown_sender.send(true).unwrap();
+
+        let result = tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("monitoring did not stop after shutdown");
+        assert!(result.unwrap().is_ok());
+        // No scan is left holding the shared monitor
+        assert!(monitor.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_kill_commands() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![3000], true).unwrap();
+
+        let process = process_on(3000, 123);
+        let commands = monitor.kill_commands(&process);
+        assert_eq!(commands[0], "kill -TERM 123");
+        assert!(commands[1].starts_with("kill -KILL 123"));
+
+        let monitor = monitor.with_kill_options(KillOptions {
+            signal: Signal::SIGINT,
+            timeout: Duration::from_secs(5),
+            ..KillOptions::default()
+        });
+        let commands = monitor.kill_commands(&process);
+        assert_eq!(commands[0], "kill -INT 123");
+        assert!(commands[1].ends_with("after 5000ms"));
+        let monitor = monitor.with_kill_options(KillOptions { signal: Signal::SIGKILL, ..KillOptions::default() });
+        assert_eq!(monitor.kill_commands(&process), vec!["kill -KILL 123"]);
+        let monitor = monitor.with_kill_options(KillOptions { sigkill: false, ..KillOptions::default() });
+        assert_eq!(monitor.kill_commands(&process), vec!["kill -TERM 123"]);
+
+        let monitor = monitor.with_container_cli(ContainerCli::new(Runtime::Docker, Some("colima".to_string())));
+        let container = ProcessInfo {
+            container_id: Some("abc123".to_string()),
+            container_name: Some("web".to_string()),
+            ..process
+        };
+        assert_eq!(monitor.kill_commands(&container)[0], "docker --context colima stop abc123");
+    }
+
+    #[tokio::test]
+    async fn test_scan_once_reports_scope() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![], false).unwrap();
+
+        let report = monitor.scan_once().await.unwrap();
+        assert!(report.processes.is_empty());
+        assert_eq!(report.scanned_ports, 0);
+        assert_eq!(report.backend, monitor.scanner.name());
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_leaves_process_running() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![], false).unwrap().with_dry_run(true);
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+
+        monitor.kill_process(child.id() as i32).await.unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_no_sigkill_reports_surviving_process() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![], false).unwrap().with_kill_options(KillOptions {
+            timeout: Duration::from_millis(200),
+            sigkill: false,
+            ..KillOptions::default()
+        });
+        let mut child = Command::new("sh").args(["-c", "trap '' TERM; sleep 5"]).spawn().unwrap();
+        // Let the shell install its trap before signalling it
+        sleep(Duration::from_millis(100)).await;
+
+        let error = monitor.kill_process(child.id() as i32).await.unwrap_err();
+        assert!(error.to_string().contains("still running 200ms after SIGTERM"));
+        assert!(matches!(error, PortKillError::StillRunning { pid, .. } if pid == child.id() as i32));
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_descendants_walk_the_tree_once() {
+        let children = parse_parent_pids("  1     0\n 10     1\n 11    10\n 12    10\n 13    11\n 20     1\n 10    13\n");
+        assert_eq!(descendants(&children, 10), vec![11, 12, 13]);
+        assert_eq!(descendants(&children, 20), Vec::<i32>::new());
+        assert!(descendant_pids(1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_kill_tree_kills_orphaned_children() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![], false).unwrap().with_kill_options(KillOptions {
+            tree: true,
+            ..KillOptions::default()
+        });
+        let mut parent = Command::new("sh").args(["-c", "sleep 30 & sleep 30 & wait"]).spawn().unwrap();
+        sleep(Duration::from_millis(200)).await;
+        let children = descendant_pids(parent.id() as i32);
+        assert_eq!(children.len(), 2);
+
+        monitor.kill_process(parent.id() as i32).await.unwrap();
+        parent.wait().unwrap();
+        // Orphans may linger as zombies until init reaps them, which is as dead as it gets
+        for child in children {
+            let output = Command::new("ps").args(["-o", "stat=", "-p", &child.to_string()]).output().unwrap();
+            let state = String::from_utf8_lossy(&output.stdout);
+            assert!(state.trim().is_empty() || state.starts_with('Z'), "{} is still {}", child, state);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kill_tree_spares_excluded_descendants() {
+        let (sender, _receiver) = bounded(1);
+        let mut parent = Command::new("sh").args(["-c", "sleep 30 & sleep 30 & wait"]).spawn().unwrap();
+        sleep(Duration::from_millis(200)).await;
+        let children = descendant_pids(parent.id() as i32);
+        assert_eq!(children.len(), 2);
+        let (excluded, other) = (children[0], children[1]);
+        let policy = Policy { excluded_pids: HashSet::from([excluded]), ..Policy::default() };
+        let monitor = ProcessMonitor::new(sender, vec![], false)
+            .unwrap()
+            .with_policy(policy.shared())
+            .with_kill_options(KillOptions { tree: true, ..KillOptions::default() });
+
+        monitor.kill_process(parent.id() as i32).await.unwrap();
+        parent.wait().unwrap();
+        let state = |pid: i32| {
+            let output = Command::new("ps").args(["-o", "stat=", "-p", &pid.to_string()]).output().unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        assert!(state(other).is_empty() || state(other).starts_with('Z'), "{} is still {}", other, state(other));
+        assert!(monitor.is_process_running(excluded).await);
+        assert!(!state(excluded).starts_with('Z'));
+
+        kill(Pid::from_raw(excluded), Signal::SIGKILL).unwrap();
+    }
+
+    #[test]
+    fn test_parse_process_owners() {
+        let owners = parse_process_owners("  100   501\n  200     0\n  300\n");
+        assert_eq!(owners, HashMap::from([(100, 501), (200, 0)]));
+    }
+
+    #[tokio::test]
+    async fn test_kill_takes_down_every_process_sharing_the_socket() {
+        let (sender, _receiver) = bounded(1);
+        // Neither process really listens, so there is nothing to verify against
+        let monitor = ProcessMonitor::new(sender, vec![], false)
+            .unwrap()
+            .with_kill_options(KillOptions { verify: false, ..KillOptions::default() });
+        let mut parent = Command::new("sleep").arg("30").spawn().unwrap();
+        let mut worker = Command::new("sleep").arg("30").spawn().unwrap();
+        let mut process_info = process_on(3000, parent.id() as i32);
+        process_info.also_held_by(worker.id() as i32);
+        assert_eq!(
+            monitor.kill_commands(&process_info)[0],
+            format!("kill -TERM {} {}", parent.id(), worker.id())
+        );
+
+        monitor.kill_scanned(&process_info).await.unwrap();
+        assert!(parent.wait().is_ok_and(|status| !status.success()));
+        assert!(worker.wait().is_ok_and(|status| !status.success()));
+    }
+
+    #[tokio::test]
+    async fn test_kill_refuses_pid_that_no_longer_holds_its_port() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![], false).unwrap();
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        // As if the process that held the port exited and its PID went to `sleep`
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let error = monitor.kill_scanned(&process_on(port, child.id() as i32)).await.unwrap_err();
+        assert!(error.to_string().contains(&format!("no longer holds {}", port)), "{}", error);
+        assert!(matches!(error, PortKillError::Refused { .. }));
+        assert!(child.try_wait().unwrap().is_none());
+
+        let holders = [child.id() as i32, 7];
+        let mut process_info = process_on(port, child.id() as i32);
+        process_info.shared_pids = vec![7, 8];
+        assert_eq!(check_holder(child.id() as i32, &process_info, &holders).unwrap(), vec![7]);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_kill_port_refuses_reserved_port_without_monitoring() {
+        let (sender, _receiver) = bounded(1);
+        let mut child = Command::new("sleep").arg("30").spawn().unwrap();
+        let scanner = MockScanner::default();
+        scanner.set(vec![process_on(3000, child.id() as i32)]);
+        let policy = Policy { protected_ports: HashSet::from([3000]), ..Policy::default() };
+        let monitor = ProcessMonitor::new(sender, vec![3000], false)
+            .unwrap()
+            .with_scanner(Box::new(scanner))
+            .with_policy(policy.shared());
+
+        // Nothing has been monitored, so only the port's own scan says it is reserved
+        let error = monitor.kill_port(3000).await.unwrap_err();
+        assert!(matches!(error, PortKillError::Refused { .. }), "{}", error);
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        let delays: Vec<u128> = (0..6).map(|retry| KillOptions::retry_delay(retry).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1600, 2000]);
+        assert_eq!(KillOptions::retry_delay(u32::MAX), KILL_RETRY_MAX_DELAY);
+    }
+
+    #[tokio::test]
+    async fn test_kill_retry_stops_once_process_is_gone() {
+        let (sender, _receiver) = bounded(1);
+        let monitor = ProcessMonitor::new(sender, vec![], false).unwrap();
+        let mut child = Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+        let pid = child.id() as i32;
+
+        // Without retries the failure is final, with them a process found gone is done
+        assert!(monitor.send_signal(pid, Signal::SIGTERM).await.is_err());
+        let monitor = monitor.with_kill_options(KillOptions { retries: 2, ..KillOptions::default() });
+        monitor.send_signal(pid, Signal::SIGTERM).await.unwrap();
+    }
+
+    #[test]
+    fn test_kill_options_from_args() {
+        let args = Args::parse_from(["port-kill", "--signal", "INT", "--kill-timeout", "5000", "--no-sigkill", "--no-verify"]);
+        let options = KillOptions::from_args(&args).unwrap();
+        assert_eq!(
+            options,
+            KillOptions { signal: Signal::SIGINT, timeout: Duration::from_secs(5), sigkill: false, tree: false, retries: 0, verify: false }
+        );
+
+        let args = Args::parse_from(["port-kill"]);
+        assert_eq!(KillOptions::from_args(&args).unwrap(), KillOptions::default());
+    }
+
+    #[test]
+    fn test_context_replaces_command_with_full_command_line() {
+        let own_pid = std::process::id() as i32;
+        let mut processes = HashMap::from([(Endpoint::tcp(3000), process_on(3000, own_pid))]);
+        add_process_context(&mut processes);
+
+        let process_info = &processes[&Endpoint::tcp(3000)];
+        let argv: Vec<String> = std::env::args().collect();
+        assert_eq!(process_info.argv.as_ref(), Some(&argv));
+        assert_eq!(process_info.command, argv.join(" "));
+    }
+
+    #[test]
+    fn test_parse_container_details() {
+        assert_eq!(parse_container_details("/shop-web-1\tshop\n"), ("shop-web-1".to_string(), Some("shop".to_string())));
+        assert_eq!(parse_container_details("/standalone\t<no value>\n"), ("standalone".to_string(), None));
+    }
+
+    #[test]
+    fn test_parse_unix_sockets() {
+        let output = "p100\ncpython3\nn/tmp/app.sock type=STREAM\nTST=LISTEN\nntype=STREAM\nTST=CONNECTED\n\
+                      p200\ncnode\nn/tmp/client.sock type=STREAM\nTST=CONNECTED\nn@abstract\nTST=LISTEN\n\
+                      p300\ncpostgres\nn/tmp/.s.PGSQL.5432\n";
+        let sockets = parse_unix_sockets(output);
+        let found: Vec<(i32, String)> = sockets.iter().map(|p| (p.pid, p.endpoint().to_string())).collect();
+        assert_eq!(found, vec![(100, "/tmp/app.sock".to_string()), (300, "/tmp/.s.PGSQL.5432".to_string())]);
+        assert_eq!(sockets[0].name, "python3");
+        assert_eq!(sockets[0].endpoint().port(), None);
+    }
+
+    #[test]
+    fn test_parse_connections_keys_clients_by_remote_port() {
+        // node's accepted connection is its own side of the client's, only the client counts
+        let output = "p100\ncnode\nf20\nn127.0.0.1:3000->127.0.0.1:51000\n\
+                      p200\nccurl\nf3\nn127.0.0.1:51000->127.0.0.1:3000\nf4\nn[::1]:51002->[::1]:3000\n\
+                      p300\ncpsql\nf3\nn10.0.0.5:40000->10.0.0.9:5432\nn*:9999\n";
+        let connections = parse_connections(output);
+        let found: Vec<(i32, u16, &str)> = connections.iter().map(|p| (p.pid, p.port, p.name.as_str())).collect();
+        assert_eq!(found, vec![(100, 51000, "node"), (200, 3000, "curl"), (300, 5432, "psql")]);
+
+        let curl = &connections[1];
+        assert_eq!(curl.state, SocketState::Established);
+        assert_eq!(curl.endpoint(), Endpoint::Connection { port: 3000, pid: 200 });
+        assert_eq!(curl.endpoint().to_string(), "3000 (connection from PID 200)");
+        // A client doesn't hold the port, so it never counts as the port being in use
+        assert_eq!(curl.endpoint().port(), None);
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_stops_at_first_failed_kill() {
+        let (sender, _receiver) = bounded(1);
+        // PIDs well beyond any pid_max, so every kill fails without touching a real process
+        let missing = [process_on(3000, 2_000_000_000), process_on(3001, 2_000_000_001)];
+        let targets: Vec<&ProcessInfo> = missing.iter().collect();
+
+        let monitor = ProcessMonitor::new(sender, vec![3000, 3001], false).unwrap();
+        let attempts = monitor.kill_each(targets.clone()).await;
+        assert_eq!(attempts.len(), 2);
+        assert!(attempts.iter().all(|r| r.error.is_some()));
+        let error = monitor.kill_outcome(&attempts).unwrap_err();
+        assert!(matches!(error, PortKillError::KillsFailed(_)));
+        let error = error.to_string();
+        assert!(error.starts_with("Some processes failed to kill: Port 3000 (PID 2000000000)"));
+
+        let monitor = monitor.with_fail_fast(true);
+        let attempts = monitor.kill_each(targets).await;
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].pid, 2_000_000_000);
+        let error = monitor.kill_outcome(&attempts).unwrap_err().to_string();
+        assert!(error.starts_with("Stopped after the first failed kill"));
+        assert!(monitor.kill_outcome(&[]).is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+//! Relaunching a killed process, for `--kill-port --restart`, the console's `kr` command and
+//! the tray's "Kill & Restart" items.
+//!
+//! The command line and working directory recorded when the process was scanned are reused,
+//! so a stuck dev server comes back the way it was started. The new process gets its own
+//! process group and no terminal, so it outlives port-kill and isn't hit by its Ctrl+C.
+
+use crate::types::ProcessInfo;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+/// How the relaunched process is started, e.g. `npm run dev in /home/me/web`, for messages.
+pub fn describe(process_info: &ProcessInfo) -> Result<String> {
+    let argv = command_line(process_info)?;
+    Ok(match &process_info.cwd {
+        Some(cwd) => format!("{} in {}", argv.join(" "), cwd),
+        None => argv.join(" "),
+    })
+}
+
+/// Start `process_info`'s recorded command line again in its recorded working directory,
+/// returning the new PID. Fails without doing anything when the command line isn't known.
+pub fn respawn(process_info: &ProcessInfo) -> Result<u32> {
+    let argv = command_line(process_info)?;
+    let mut command = Command::new(&argv[0]);
+    command
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    if let Some(cwd) = &process_info.cwd {
+        command.current_dir(cwd);
+    }
+
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to restart {}", describe(process_info).unwrap_or_default()))?;
+    let pid = child.id();
+    info!("Restarted {} as PID {}", process_info.name, pid);
+
+    // Reap the process when it exits so it doesn't linger as a zombie while port-kill runs
+    std::thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            warn!("Failed to wait for restarted PID {}: {}", pid, e);
+        }
+    });
+    Ok(pid)
+}
+
+fn command_line(process_info: &ProcessInfo) -> Result<&[String]> {
+    match process_info.argv.as_deref() {
+        Some(argv) if !argv.is_empty() => Ok(argv),
+        _ => Err(anyhow::anyhow!(
+            "The command line of {} (PID {}) is unknown, so it can't be restarted",
+            process_info.name,
+            process_info.pid
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+
+    fn process(argv: Option<Vec<String>>, cwd: Option<String>) -> ProcessInfo {
+        ProcessInfo {
+            command: "sh".to_string(),
+            name: "sh".to_string(),
+            cwd,
+            argv,
+            ..process_on(3000, 42)
+        }
+    }
+
+    #[test]
+    fn test_respawns_in_recorded_directory() {
+        let dir = std::env::temp_dir().join(format!("port-kill-restart-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let argv = ["sh", "-c", "pwd > restarted"].map(str::to_string).to_vec();
+        let process_info = process(Some(argv), Some(dir.to_string_lossy().into_owned()));
+        assert_eq!(describe(&process_info).unwrap(), format!("sh -c pwd > restarted in {}", dir.display()));
+
+        respawn(&process_info).unwrap();
+        let marker = dir.join("restarted");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !std::fs::read_to_string(&marker).is_ok_and(|content| !content.is_empty()) && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let content = std::fs::read_to_string(&marker).unwrap();
+        let expected = dir.canonicalize().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(std::path::Path::new(content.trim()), expected);
+    }
+
+    #[test]
+    fn test_unknown_command_line_is_not_restarted() {
+        assert!(respawn(&process(None, None)).is_err());
+        assert!(respawn(&process(Some(Vec::new()), None)).is_err());
+        assert!(describe(&process(None, None)).unwrap_err().to_string().contains("PID 42"));
+    }
+}
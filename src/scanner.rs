@@ -0,0 +1,1149 @@
+//! Backends that find which processes listen on which ports.
+//!
+//! `ProcessMonitor` only talks to a [`PortScanner`]; [`detect`] picks the backend at runtime.
+//! Scanners report the PID, owner and command behind each listener; container details, the
+//! ignore/protect policy and Unix sockets are layered on by the monitor.
+
+use crate::error::PortKillError;
+use crate::types::{Endpoint, IpFamily, ProcessInfo, Protocol};
+use log::{debug, warn};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+
+/// lsof runs that fail with an error message are retried this many times in total.
+const LSOF_ATTEMPTS: u32 = 3;
+const LSOF_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// lsof ran but was refused access to process information, typically because the bundled
+/// macOS app is sandboxed or lacks Full Disk Access.
+#[derive(Debug, thiserror::Error)]
+#[error("lsof was denied access to process information ({stderr}). If you are running the bundled .app, grant it Full Disk Access in System Settings > Privacy & Security, or run port-kill from a terminal")]
+pub struct LsofPermissionDenied {
+    pub stderr: String,
+}
+
+impl LsofPermissionDenied {
+    /// Recognise a permissions failure from lsof's stderr.
+    pub fn from_stderr(stderr: &str) -> Option<Self> {
+        let lower = stderr.to_lowercase();
+        let denied = ["permission denied", "operation not permitted", "not permitted"]
+            .iter()
+            .any(|needle| lower.contains(needle));
+        denied.then(|| Self {
+            stderr: stderr.trim().to_string(),
+        })
+    }
+}
+
+/// lsof kept failing for a port after every retry. The remaining ports would almost certainly
+/// fail the same way, so the scan stops here instead of retrying each of them in turn.
+#[derive(Debug, thiserror::Error)]
+#[error("lsof failed after {attempts} attempts: {stderr}")]
+pub struct LsofFailed {
+    pub attempts: u32,
+    pub stderr: String,
+}
+
+/// Clock ticks per second in `/proc/<pid>/stat` times. The kernel reports these in USER_HZ,
+/// which is 100 on x86, ARM and every other common architecture.
+const USER_HZ: f64 = 100.0;
+
+/// What a [`PortScanner::scan`] resolves to: the process holding each endpoint found.
+pub type ScanFuture<'a> = Pin<Box<dyn Future<Output = Result<HashMap<Endpoint, ProcessInfo>, PortKillError>> + Send + 'a>>;
+
+/// Finds the processes listening on a set of ports.
+pub trait PortScanner: Send + Sync {
+    /// Short name for logs and scan reports, e.g. `lsof`.
+    fn name(&self) -> &'static str;
+
+    /// The process listening on each of `ports` for each of `protocols`. Where several
+    /// processes share an endpoint, the lowest PID stands for it.
+    fn scan<'a>(&'a self, ports: &'a [u16], protocols: &'a [Protocol]) -> ScanFuture<'a>;
+}
+
+/// The preferred scanner for this system: `/proc` on Linux, which needs no external tools
+/// and so works in slim containers, otherwise lsof. With a `host`, only sockets bound to that
+/// address or the wildcard address are reported.
+pub fn detect(host: Option<IpAddr>) -> Box<dyn PortScanner> {
+    let proc_scanner = ProcScanner::new().with_host(host);
+    if cfg!(target_os = "linux") && proc_scanner.is_available() {
+        Box::new(proc_scanner)
+    } else {
+        Box::new(LsofScanner::new().with_host(host))
+    }
+}
+
+/// Whether a socket bound to `address` is reachable on `host`: any socket is when no host is
+/// given, otherwise only those bound to `host` itself or to the wildcard address.
+pub fn accepts_address(host: Option<IpAddr>, address: Option<IpAddr>) -> bool {
+    match host {
+        None => true,
+        Some(host) => address.is_some_and(|address| address == host || address.is_unspecified()),
+    }
+}
+
+/// Scans by running `lsof`, with `ps` for process details.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LsofScanner {
+    host: Option<IpAddr>,
+}
+
+impl LsofScanner {
+    pub const NAME: &'static str = "lsof";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only report sockets bound to `host` or the wildcard address.
+    pub fn with_host(mut self, host: Option<IpAddr>) -> Self {
+        self.host = host;
+        self
+    }
+
+    async fn scan_ports(&self, ports: &[u16], protocols: &[Protocol]) -> Result<HashMap<Endpoint, ProcessInfo>, PortKillError> {
+        match contiguous_range(ports) {
+            Some((start, end)) => self.scan_range(start, end, protocols).await,
+            None => self.scan_each_port(ports, protocols).await,
+        }
+    }
+
+    /// Find every listener in `start..=end` with one lsof run per protocol, rather than one
+    /// per port. A process holding several ports is only looked up once.
+    async fn scan_range(&self, start: u16, end: u16, protocols: &[Protocol]) -> Result<HashMap<Endpoint, ProcessInfo>, PortKillError> {
+        let mut processes: HashMap<Endpoint, ProcessInfo> = HashMap::new();
+        let mut details: HashMap<i32, ProcessInfo> = HashMap::new();
+
+        for &protocol in protocols {
+            let filter = lsof_filter(&format!("{}-{}", start, end), protocol);
+            let Some(output) = self.run_lsof(&filter).await? else {
+                continue;
+            };
+
+            for (pid, port, address) in parse_lsof_listeners(&String::from_utf8_lossy(&output.stdout), self.host) {
+                let endpoint = Endpoint::Port { port, protocol };
+                // Connected UDP sockets match on their remote port too
+                if !(start..=end).contains(&port) {
+                    continue;
+                }
+                // Shared ports are described by the first PID, which may hold the port on both
+                // IP families; the others are killed along with it
+                if let Some(existing) = processes.get_mut(&endpoint) {
+                    match (existing.pid == pid, address) {
+                        (true, Some(address)) => existing.also_bound_on(&address),
+                        (true, None) => {}
+                        (false, _) => existing.also_held_by(pid),
+                    }
+                    continue;
+                }
+
+                let process_info = match details.get(&pid) {
+                    Some(process_info) => process_info.clone(),
+                    None => {
+                        let process_info = match self.get_process_details(pid, port, protocol).await {
+                            Ok(process_info) => process_info,
+                            Err(e) => {
+                                warn!("Failed to look up PID {} on port {}: {}", pid, endpoint, e);
+                                continue;
+                            }
+                        };
+                        details.insert(pid, process_info.clone());
+                        process_info
+                    }
+                };
+                let family = address.as_ref().map(IpFamily::of);
+                processes.insert(endpoint, ProcessInfo { port, protocol, address, family, ..process_info });
+            }
+        }
+
+        Ok(processes)
+    }
+
+    /// Check an explicit, non-contiguous port list one port at a time.
+    async fn scan_each_port(&self, ports: &[u16], protocols: &[Protocol]) -> Result<HashMap<Endpoint, ProcessInfo>, PortKillError> {
+        let mut processes = HashMap::new();
+
+        for &port in ports {
+            for &protocol in protocols {
+                let endpoint = Endpoint::Port { port, protocol };
+                match self.scan_port(port, protocol).await {
+                    Ok(Some(process_info)) => {
+                        processes.insert(endpoint, process_info);
+                    }
+                    Ok(None) => {}
+                    // Every other port would fail the same way, so report it once for the scan
+                    Err(e @ (PortKillError::PermissionDenied(_) | PortKillError::ScanFailed(_) | PortKillError::ScannerUnavailable { .. })) => {
+                        return Err(e)
+                    }
+                    Err(e) => {
+                        warn!("Failed to check port {}: {}", endpoint, e);
+                    }
+                }
+            }
+        }
+
+        Ok(processes)
+    }
+
+    /// Returns `Ok(None)` when the port is free, reserving `Err` for actual failures
+    /// so the common free-port path doesn't allocate an error.
+    async fn scan_port(&self, port: u16, protocol: Protocol) -> Result<Option<ProcessInfo>, PortKillError> {
+        let Some(output) = self.run_lsof(&lsof_filter(&port.to_string(), protocol)).await? else {
+            return Ok(None);
+        };
+
+        // Several processes can share a port, e.g. mDNS responders all bound to UDP 5353;
+        // lsof lists them in PID order and the first stands for the port
+        let pids = parse_lsof_pids(&String::from_utf8_lossy(&output.stdout), port, self.host);
+        let Some((pid, addresses)) = pids.first() else {
+            return Ok(None);
+        };
+        if pids.len() > 1 {
+            let pids: Vec<i32> = pids.iter().map(|(pid, _)| *pid).collect();
+            debug!("{}/{} is held by PIDs {:?}, showing {}", port, protocol, pids, pid);
+        }
+
+        // Get process details using ps
+        let mut process_info = self.get_process_details(*pid, port, protocol).await?;
+        process_info.address = addresses.first().copied();
+        for address in addresses {
+            process_info.also_bound_on(address);
+        }
+        for (other, _) in &pids[1..] {
+            process_info.also_held_by(*other);
+        }
+        Ok(Some(process_info))
+    }
+
+    /// Run lsof with the given selection, returning `Ok(None)` when nothing matches.
+    ///
+    /// lsof exits non-zero both when nothing matches and when it genuinely fails; only the
+    /// latter writes an error to stderr. Such failures are often a race with the process table
+    /// changing, so they are retried a few times; if every retry fails, [`LsofFailed`] ends the
+    /// whole scan. A missing lsof binary or a permissions problem won't fix itself and is
+    /// reported immediately.
+    async fn run_lsof(&self, filter: &[String]) -> Result<Option<std::process::Output>, PortKillError> {
+        let mut attempt = 1;
+        loop {
+            let output = Command::new("lsof")
+                .args(["-F", "ptn", "-P", "-n"])
+                .args(filter)
+                .output()
+                .map_err(|e| PortKillError::from_command("lsof", e))?;
+
+            if output.status.success() {
+                return Ok(Some(output));
+            }
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if let Some(denied) = LsofPermissionDenied::from_stderr(&stderr) {
+                return Err(denied.into());
+            }
+
+            // Warnings (e.g. about unreachable FUSE mounts) accompany ordinary no-match exits
+            let error = stderr
+                .lines()
+                .filter(|line| !line.trim().is_empty() && !line.starts_with("lsof: WARNING"))
+                .collect::<Vec<_>>()
+                .join("; ");
+            if error.is_empty() {
+                return Ok(None);
+            }
+
+            if attempt >= LSOF_ATTEMPTS {
+                return Err(LsofFailed { attempts: attempt, stderr: error }.into());
+            }
+            debug!("lsof {} failed (attempt {}/{}): {}", filter.join(" "), attempt, LSOF_ATTEMPTS, error);
+            attempt += 1;
+            sleep(LSOF_RETRY_DELAY).await;
+        }
+    }
+
+    async fn get_process_details(&self, pid: i32, port: u16, protocol: Protocol) -> Result<ProcessInfo, PortKillError> {
+        // Get process owner, resource usage and command using ps
+        let output = Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", "uid=", "-o", "%cpu=", "-o", "rss=", "-o", "comm="])
+            .output()
+            .map_err(|e| PortKillError::from_command("ps", e))?;
+
+        let PsDetails { uid, cpu_percent, memory_mb, command } = if output.status.success() {
+            parse_ps_details(&String::from_utf8_lossy(&output.stdout))
+        } else {
+            PsDetails { command: "unknown".to_string(), ..PsDetails::default() }
+        };
+
+        // Extract process name (basename of command)
+        let name = command
+            .rsplit('/')
+            .next()
+            .unwrap_or("unknown")
+            .to_string();
+
+        // Container details are filled in by the monitor
+        Ok(ProcessInfo {
+            pid,
+            port,
+            protocol,
+            command,
+            name,
+            uid,
+            cpu_percent,
+            memory_mb,
+            ..Default::default()
+        })
+    }
+}
+
+impl PortScanner for LsofScanner {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn scan<'a>(&'a self, ports: &'a [u16], protocols: &'a [Protocol]) -> ScanFuture<'a> {
+        Box::pin(self.scan_ports(ports, protocols))
+    }
+}
+
+/// Scans Linux's `/proc/net` socket tables and matches socket inodes to processes through
+/// `/proc/<pid>/fd`. Only processes whose descriptors are readable are found, as with lsof.
+#[derive(Debug, Clone)]
+pub struct ProcScanner {
+    root: PathBuf,
+    host: Option<IpAddr>,
+}
+
+/// One row of a `/proc/net/{tcp,udp}[6]` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ProcSocket {
+    address: IpAddr,
+    port: u16,
+    uid: u32,
+    inode: u64,
+}
+
+impl ProcScanner {
+    pub const NAME: &'static str = "proc";
+
+    pub fn new() -> Self {
+        Self::at("/proc")
+    }
+
+    /// Read from a procfs mounted somewhere other than `/proc`.
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into(), host: None }
+    }
+
+    /// Only report sockets bound to `host` or the wildcard address.
+    pub fn with_host(mut self, host: Option<IpAddr>) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Whether the socket tables can be read here.
+    pub fn is_available(&self) -> bool {
+        self.root.join("net/tcp").is_file()
+    }
+
+    fn scan_blocking(&self, ports: &[u16], protocols: &[Protocol]) -> Result<HashMap<Endpoint, ProcessInfo>, PortKillError> {
+        let wanted: HashSet<u16> = ports.iter().copied().collect();
+        let mut sockets: HashMap<u64, (Endpoint, u32, IpAddr)> = HashMap::new();
+
+        for &protocol in protocols {
+            for table in proc_net_tables(protocol) {
+                let path = self.root.join("net").join(table);
+                let content = match std::fs::read_to_string(&path) {
+                    Ok(content) => content,
+                    // The IPv6 tables are missing when IPv6 is disabled
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(source) => return Err(PortKillError::Io { action: format!("read {}", path.display()), source }),
+                };
+                for socket in parse_proc_net(&content, protocol) {
+                    if wanted.contains(&socket.port) && accepts_address(self.host, Some(socket.address)) {
+                        let endpoint = Endpoint::Port { port: socket.port, protocol };
+                        sockets.entry(socket.inode).or_insert((endpoint, socket.uid, socket.address));
+                    }
+                }
+            }
+        }
+
+        let mut processes = HashMap::new();
+        if sockets.is_empty() {
+            return Ok(processes);
+        }
+
+        for pid in self.pids()? {
+            // Processes exit mid-scan and other users' descriptors are unreadable; skip both
+            let Ok(fds) = std::fs::read_dir(self.root.join(pid.to_string()).join("fd")) else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                let Some(inode) = std::fs::read_link(fd.path()).ok().as_deref().and_then(socket_inode) else {
+                    continue;
+                };
+                let Some((endpoint, uid, address)) = sockets.get(&inode) else {
+                    continue;
+                };
+                // Shared sockets are described by the first PID, which may hold the port on both
+                // IP families; the others are killed along with it
+                if let Some(existing) = processes.get_mut(endpoint) {
+                    if existing.pid == pid {
+                        existing.also_bound_on(address);
+                    } else {
+                        existing.also_held_by(pid);
+                    }
+                    continue;
+                }
+                let Endpoint::Port { port, protocol } = *endpoint else {
+                    continue;
+                };
+
+                let name = std::fs::read_to_string(self.root.join(pid.to_string()).join("comm"))
+                    .map(|comm| comm.trim().to_string())
+                    .unwrap_or_else(|_| "unknown".to_string());
+                let (cpu_percent, memory_mb) = self.usage(pid);
+                processes.insert(
+                    endpoint.clone(),
+                    ProcessInfo {
+                        pid,
+                        port,
+                        protocol,
+                        command: name.clone(),
+                        name,
+                        uid: Some(*uid),
+                        cpu_percent,
+                        memory_mb,
+                        address: Some(*address),
+                        family: Some(IpFamily::of(address)),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        Ok(processes)
+    }
+
+    /// CPU use averaged over the process lifetime, as `ps` computes it, and resident memory.
+    fn usage(&self, pid: i32) -> (Option<f32>, Option<u64>) {
+        let dir = self.root.join(pid.to_string());
+        let read = |path: PathBuf| std::fs::read_to_string(path).ok();
+
+        let uptime = read(self.root.join("uptime")).and_then(|uptime| uptime.split_whitespace().next()?.parse().ok());
+        let cpu_percent = match (read(dir.join("stat")), uptime) {
+            (Some(stat), Some(uptime)) => parse_proc_cpu(&stat, uptime, USER_HZ),
+            _ => None,
+        };
+        let memory_mb = read(dir.join("status")).as_deref().and_then(parse_vm_rss);
+        (cpu_percent, memory_mb)
+    }
+
+    /// Every PID under the procfs root, lowest first.
+    fn pids(&self) -> Result<Vec<i32>, PortKillError> {
+        let entries = std::fs::read_dir(&self.root)
+            .map_err(|source| PortKillError::Io { action: format!("read {}", self.root.display()), source })?;
+        let mut pids: Vec<i32> = entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+        pids.sort_unstable();
+        Ok(pids)
+    }
+}
+
+impl Default for ProcScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PortScanner for ProcScanner {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn scan<'a>(&'a self, ports: &'a [u16], protocols: &'a [Protocol]) -> ScanFuture<'a> {
+        Box::pin(async move { self.scan_blocking(ports, protocols) })
+    }
+}
+
+/// The `/proc/net` tables listing sockets of `protocol`.
+fn proc_net_tables(protocol: Protocol) -> [&'static str; 2] {
+    match protocol {
+        Protocol::Tcp => ["tcp", "tcp6"],
+        Protocol::Udp => ["udp", "udp6"],
+    }
+}
+
+/// Parse a `/proc/net/{tcp,udp}[6]` table. TCP sockets only count while listening (state
+/// `0A`); UDP has no listening state, so every bound socket counts, as with lsof.
+fn parse_proc_net(content: &str, protocol: Protocol) -> Vec<ProcSocket> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || (protocol == Protocol::Tcp && fields[3] != "0A") {
+                return None;
+            }
+            let (address, port) = fields[1].rsplit_once(':')?;
+            Some(ProcSocket {
+                address: parse_proc_address(address)?,
+                port: u16::from_str_radix(port, 16).ok()?,
+                uid: fields[7].parse().ok()?,
+                inode: fields[9].parse().ok()?,
+            })
+        })
+        // Inode 0 marks sockets being torn down
+        .filter(|socket| socket.inode != 0)
+        .collect()
+}
+
+/// Decode a `/proc/net` local address: the raw network-order bytes printed as native-endian
+/// 32-bit words, one for IPv4 and four for IPv6.
+fn parse_proc_address(hex: &str) -> Option<IpAddr> {
+    if hex.len() != 8 && hex.len() != 32 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(16);
+    for word in hex.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(word).ok()?, 16).ok()?;
+        bytes.extend(word.to_ne_bytes());
+    }
+    match bytes.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?))),
+        16 => Some(IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?))),
+        _ => None,
+    }
+}
+
+/// Lifetime CPU use from a `/proc/<pid>/stat` line: user plus system time over the time since
+/// the process started, given the system uptime in seconds.
+fn parse_proc_cpu(stat: &str, uptime: f64, ticks_per_second: f64) -> Option<f32> {
+    // The command name in parentheses may contain spaces; the numeric fields follow it,
+    // starting with field 3 (state)
+    let (_, fields) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let field = |n: usize| -> Option<f64> { fields.get(n - 3)?.parse().ok() };
+
+    let cpu_seconds = (field(14)? + field(15)?) / ticks_per_second;
+    let elapsed = uptime - field(22)? / ticks_per_second;
+    if elapsed <= 0.0 {
+        return Some(0.0);
+    }
+    Some((cpu_seconds / elapsed * 100.0) as f32)
+}
+
+/// Resident memory in MB from `/proc/<pid>/status`.
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    line.split_whitespace().next()?.parse().ok().map(kib_to_mb)
+}
+
+/// The inode of a descriptor link such as `socket:[12345]`.
+fn socket_inode(target: &Path) -> Option<u64> {
+    target.to_str()?.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// One line of `ps -o uid= -o %cpu= -o rss= -o comm=` output.
+#[derive(Debug, Default, PartialEq)]
+struct PsDetails {
+    uid: Option<u32>,
+    cpu_percent: Option<f32>,
+    memory_mb: Option<u64>,
+    command: String,
+}
+
+/// Split `ps -o uid= -o %cpu= -o rss= -o comm=` output into its columns. The command comes
+/// last because it may itself contain spaces.
+fn parse_ps_details(output: &str) -> PsDetails {
+    let line = output.trim();
+    let mut rest = line;
+    let mut columns = [""; 3];
+    for column in &mut columns {
+        let Some((value, tail)) = rest.split_once(char::is_whitespace) else {
+            return PsDetails { command: line.to_string(), ..PsDetails::default() };
+        };
+        *column = value;
+        rest = tail.trim_start();
+    }
+
+    let [uid, cpu, rss] = columns;
+    PsDetails {
+        uid: uid.parse().ok(),
+        cpu_percent: cpu.parse().ok(),
+        memory_mb: rss.parse::<u64>().ok().map(kib_to_mb),
+        command: rest.trim_end().to_string(),
+    }
+}
+
+fn kib_to_mb(kib: u64) -> u64 {
+    kib / 1024
+}
+
+/// Where a process was started from, when and with what command line, to tell apart
+/// processes with the same name and to relaunch them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessContext {
+    pub cwd: Option<String>,
+    pub start_time: Option<String>,
+    /// Seconds since the Unix epoch, worked out from how long the process has been running
+    pub started_at: Option<u64>,
+    pub argv: Option<Vec<String>>,
+}
+
+/// Working directory, start time and command line of each of `pids`, from one `ps` run plus
+/// the procfs `cwd` links and `cmdline` files, or `lsof` and `KERN_PROCARGS2` where there is
+/// no procfs. PIDs that have exited are left out.
+pub fn process_context(pids: &[i32]) -> HashMap<i32, ProcessContext> {
+    if pids.is_empty() {
+        return HashMap::new();
+    }
+    let pid_list = pids.iter().map(|pid| pid.to_string()).collect::<Vec<_>>().join(",");
+
+    let mut contexts: HashMap<i32, ProcessContext> =
+        match Command::new("ps").args(["-p", &pid_list, "-o", "pid=", "-o", "etime=", "-o", "lstart="]).output() {
+            Ok(output) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+                parse_ps_start_times(&String::from_utf8_lossy(&output.stdout), now)
+            }
+            Err(e) => {
+                warn!("Failed to execute ps command: {}", e);
+                HashMap::new()
+            }
+        };
+
+    let (cwds, argvs): (HashMap<i32, String>, HashMap<i32, Vec<String>>) = if Path::new("/proc/self/cwd").exists() {
+        let cwds = pids
+            .iter()
+            .filter_map(|&pid| {
+                let cwd = std::fs::read_link(format!("/proc/{}/cwd", pid)).ok()?;
+                Some((pid, cwd.to_string_lossy().into_owned()))
+            })
+            .collect();
+        let argvs = pids
+            .iter()
+            .filter_map(|&pid| {
+                let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+                Some((pid, parse_proc_cmdline(&cmdline)?))
+            })
+            .collect();
+        (cwds, argvs)
+    } else {
+        let cwds = match Command::new("lsof").args(["-a", "-p", &pid_list, "-d", "cwd", "-F", "pn"]).output() {
+            Ok(output) => parse_lsof_cwds(&String::from_utf8_lossy(&output.stdout)),
+            Err(e) => {
+                warn!("Failed to execute lsof command: {}", e);
+                HashMap::new()
+            }
+        };
+        // `ps -o args=` joins the arguments with spaces, so it can't tell `--name "my app"` from
+        // `--name my app`; without an exact command line, restarts are refused
+        let argvs = pids.iter().filter_map(|&pid| Some((pid, read_procargs(pid)?))).collect();
+        (cwds, argvs)
+    };
+    for (pid, cwd) in cwds {
+        contexts.entry(pid).or_default().cwd = Some(cwd);
+    }
+    for (pid, argv) in argvs {
+        contexts.entry(pid).or_default().argv = Some(argv);
+    }
+    contexts
+}
+
+/// Parse `ps -o pid= -o etime= -o lstart=` output, where the start time reads like
+/// `Thu Oct 15 04:56:40 2026` with single-digit days padded by a space. `lstart` is local
+/// time without a zone, so the start as an epoch time is taken as `now` minus the elapsed time.
+fn parse_ps_start_times(output: &str, now: u64) -> HashMap<i32, ProcessContext> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let pid = columns.next()?.parse().ok()?;
+            let elapsed = parse_elapsed(columns.next()?);
+            let start_time = columns.collect::<Vec<_>>().join(" ");
+            let context = ProcessContext {
+                start_time: (!start_time.is_empty()).then_some(start_time),
+                started_at: elapsed.map(|elapsed| now.saturating_sub(elapsed)),
+                ..Default::default()
+            };
+            (context.start_time.is_some() || context.started_at.is_some()).then_some((pid, context))
+        })
+        .collect()
+}
+
+/// Parse a `ps -o etime=` elapsed time, `[[dd-]hh:]mm:ss`, into seconds.
+fn parse_elapsed(etime: &str) -> Option<u64> {
+    let (days, clock) = match etime.split_once('-') {
+        Some((days, clock)) => (days.parse::<u64>().ok()?, clock),
+        None => (0, etime),
+    };
+    let mut seconds = 0;
+    let mut parts = 0;
+    for part in clock.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+        parts += 1;
+    }
+    (2..=3).contains(&parts).then_some(days * 86_400 + seconds)
+}
+
+/// Split a procfs `cmdline` file, whose arguments are each terminated by a NUL. Kernel
+/// threads and zombies have an empty one and no command line to speak of.
+fn parse_proc_cmdline(cmdline: &[u8]) -> Option<Vec<String>> {
+    let argv: Vec<String> = cmdline
+        .split(|&b| b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    // The final terminator leaves an empty entry behind
+    let argv = match argv.split_last() {
+        Some((last, rest)) if last.is_empty() => rest.to_vec(),
+        _ => argv,
+    };
+    (!argv.is_empty()).then_some(argv)
+}
+
+/// The command line of `pid` from the `KERN_PROCARGS2` sysctl, which keeps every argument
+/// whole.
+#[cfg(target_os = "macos")]
+fn read_procargs(pid: i32) -> Option<Vec<String>> {
+    let mut argmax: libc::c_int = 0;
+    let mut size = std::mem::size_of::<libc::c_int>();
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+    // SAFETY: `argmax` is a c_int and `size` holds its size
+    let status = unsafe {
+        libc::sysctl(mib.as_mut_ptr(), 2, (&mut argmax as *mut libc::c_int).cast(), &mut size, std::ptr::null_mut(), 0)
+    };
+    if status != 0 || argmax <= 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u8; argmax as usize];
+    let mut size = buffer.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+    // SAFETY: `buffer` is `size` bytes long, and the kernel writes at most that many
+    let status = unsafe {
+        libc::sysctl(mib.as_mut_ptr(), 3, buffer.as_mut_ptr().cast(), &mut size, std::ptr::null_mut(), 0)
+    };
+    if status != 0 {
+        return None;
+    }
+    buffer.truncate(size);
+    parse_procargs2(&buffer)
+}
+
+/// Only procfs and macOS give the arguments exactly.
+#[cfg(not(target_os = "macos"))]
+fn read_procargs(_pid: i32) -> Option<Vec<String>> {
+    None
+}
+
+/// Parse a `KERN_PROCARGS2` buffer: `argc` as a native-endian int, the executable path,
+/// NUL padding, then `argc` NUL-terminated arguments followed by the environment.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_procargs2(buffer: &[u8]) -> Option<Vec<String>> {
+    let argc = usize::try_from(i32::from_ne_bytes(buffer.get(..4)?.try_into().ok()?)).ok()?;
+    let rest = &buffer[4..];
+    let path_end = rest.iter().position(|&b| b == 0)?;
+    let args_start = path_end + rest[path_end..].iter().position(|&b| b != 0)?;
+    let argv: Vec<String> = rest[args_start..]
+        .split(|&b| b == 0)
+        .take(argc)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    (argc > 0 && argv.len() == argc).then_some(argv)
+}
+
+/// Parse `lsof -d cwd -F pn` output: a `p<pid>` line followed by the `n<path>` of its cwd.
+fn parse_lsof_cwds(output: &str) -> HashMap<i32, String> {
+    let mut cwds = HashMap::new();
+    let mut pid = None;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value.parse().ok();
+        } else if let (Some(path), Some(pid)) = (line.strip_prefix('n'), pid) {
+            cwds.insert(pid, path.to_string());
+        }
+    }
+    cwds
+}
+
+/// lsof arguments selecting listeners on `ports` (a port, `start-end` range or comma list).
+/// UDP has no listening state, a bound socket is as close as it gets. The TCP state filter
+/// hides UDP sockets when combined in one run, so each protocol gets its own.
+pub fn lsof_filter(ports: &str, protocol: Protocol) -> Vec<String> {
+    match protocol {
+        Protocol::Tcp => vec![format!("-iTCP:{}", ports), "-sTCP:LISTEN".to_string()],
+        Protocol::Udp => vec![format!("-iUDP:{}", ports)],
+    }
+}
+
+/// The first and last port when `ports` is a run of consecutive ports, as a port range
+/// always is. Such lists can be scanned with a single lsof range selection.
+fn contiguous_range(ports: &[u16]) -> Option<(u16, u16)> {
+    let consecutive = ports.windows(2).all(|pair| pair[0].checked_add(1) == Some(pair[1]));
+    (ports.len() > 1 && consecutive).then(|| (ports[0], ports[ports.len() - 1]))
+}
+
+/// Every (PID, local port, local address) in `lsof -F ptn` output, in order, leaving out
+/// sockets that [`accepts_address`] rejects for `host`.
+fn parse_lsof_listeners(output: &str, host: Option<IpAddr>) -> Vec<(i32, u16, Option<IpAddr>)> {
+    let mut listeners = Vec::new();
+    let mut pid = None;
+    let mut ipv6 = false;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix('p') {
+            pid = value.parse().ok();
+        } else if let Some(kind) = line.strip_prefix('t') {
+            ipv6 = kind == "IPv6";
+        } else if let Some(name) = line.strip_prefix('n') {
+            let address = address_from_lsof_name(name, ipv6);
+            if let (Some(pid), Some(port)) = (pid, port_from_lsof_name(name)) {
+                if accepts_address(host, address) {
+                    listeners.push((pid, port, address));
+                }
+            }
+        }
+    }
+    listeners
+}
+
+/// PIDs in `lsof -F ptn` output that hold `port` as their local port, each with every
+/// address it is bound to there, in order and without duplicates. lsof's `-i` selection
+/// matches either end of a connection, so names of the form `local->remote` only count when
+/// the local side is on `port`.
+fn parse_lsof_pids(output: &str, port: u16, host: Option<IpAddr>) -> Vec<(i32, Vec<IpAddr>)> {
+    let mut pids: Vec<(i32, Vec<IpAddr>)> = Vec::new();
+    for (pid, local_port, address) in parse_lsof_listeners(output, host) {
+        if local_port != port {
+            continue;
+        }
+        let index = match pids.iter().position(|(seen, _)| *seen == pid) {
+            Some(index) => index,
+            None => {
+                pids.push((pid, Vec::new()));
+                pids.len() - 1
+            }
+        };
+        pids[index].1.extend(address);
+    }
+    pids
+}
+
+/// The local address of an lsof NAME field, with any IPv6 zone dropped. `*` is the wildcard
+/// address of the socket's family, IPv6 when lsof lists its TYPE as `IPv6`. Host names
+/// (lsof without `-n`) are `None`.
+pub fn address_from_lsof_name(name: &str, ipv6: bool) -> Option<IpAddr> {
+    let local = name.split("->").next().unwrap_or(name);
+    match local.strip_prefix('[') {
+        Some(bracketed) => {
+            let host = bracketed.split_once(']')?.0;
+            let host = host.split('%').next().unwrap_or(host);
+            host.parse::<Ipv6Addr>().ok().map(IpAddr::V6)
+        }
+        None => match local.rsplit_once(':')?.0 {
+            "*" if ipv6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            "*" => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            host => host.parse().ok(),
+        },
+    }
+}
+
+/// The local port of an lsof NAME field: `*:3000`, `127.0.0.1:3000`, `[::1]:3000`, or a
+/// connection such as `127.0.0.1:5353->10.0.0.1:9`. A bracketed IPv6 host is stripped
+/// before splitting on the last colon; anything else is `None`.
+pub fn port_from_lsof_name(name: &str) -> Option<u16> {
+    let local = name.split("->").next().unwrap_or(name);
+    let port = match local.strip_prefix('[') {
+        Some(bracketed) => bracketed.split_once(']')?.1.strip_prefix(':')?,
+        None => {
+            let (host, port) = local.rsplit_once(':')?;
+            // An unbracketed IPv6 host leaves the port ambiguous
+            if host.is_empty() || host.contains(':') {
+                return None;
+            }
+            port
+        }
+    };
+    port.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsof_permission_denied_detection() {
+        assert!(LsofPermissionDenied::from_stderr("lsof: can't open /dev/kmem: Permission denied").is_some());
+        assert!(LsofPermissionDenied::from_stderr("lsof: Operation not permitted").is_some());
+        assert!(LsofPermissionDenied::from_stderr("").is_none());
+        assert!(LsofPermissionDenied::from_stderr("lsof: WARNING: can't stat() fuse").is_none());
+    }
+
+    #[test]
+    fn test_missing_command_is_unavailable() {
+        let missing = Command::new("port-kill-no-such-binary")
+            .output()
+            .map_err(|e| PortKillError::from_command("port-kill-no-such-binary", e))
+            .unwrap_err();
+        assert!(matches!(missing, PortKillError::ScannerUnavailable { .. }));
+    }
+
+    #[test]
+    fn test_parse_lsof_pids_uses_local_port_only() {
+        let output = "p100\nf5\nn*:5353\np200\nn[::]:5353\nn*:5353\n\
+                      p300\nn127.0.0.1:40000->8.8.8.8:5353\np400\nn127.0.0.1:5353->10.0.0.1:9\n";
+        let pids = |port, host| -> Vec<i32> { parse_lsof_pids(output, port, host).into_iter().map(|(pid, _)| pid).collect() };
+        assert_eq!(parse_lsof_pids(output, 5353, None)[1], (200, vec!["::".parse().unwrap(), "0.0.0.0".parse().unwrap()]));
+        assert_eq!(pids(5353, None), vec![100, 200, 400]);
+        assert_eq!(pids(40000, None), vec![300]);
+        assert!(parse_lsof_pids("", 5353, None).is_empty());
+
+        // Only the host's own and wildcard sockets count
+        assert_eq!(pids(5353, Some("10.0.0.2".parse().unwrap())), vec![100, 200]);
+        assert_eq!(pids(5353, Some("127.0.0.1".parse().unwrap())), vec![100, 200, 400]);
+    }
+
+    #[test]
+    fn test_parse_ps_details() {
+        assert_eq!(
+            parse_ps_details("  501  12.5 348160 /usr/local/bin/node\n"),
+            PsDetails {
+                uid: Some(501),
+                cpu_percent: Some(12.5),
+                memory_mb: Some(340),
+                command: "/usr/local/bin/node".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_ps_details("0 0.0 2048 /System/Library/Frameworks/Foo Bar/launchd").command,
+            "/System/Library/Frameworks/Foo Bar/launchd"
+        );
+        assert_eq!(parse_ps_details("node"), PsDetails { command: "node".to_string(), ..PsDetails::default() });
+    }
+
+    #[test]
+    fn test_parse_process_context() {
+        let start_times = parse_ps_start_times("  100  1-06:12:05 Thu Oct  5 04:56:40 2026\n  200\n", 1_000_000);
+        assert_eq!(start_times[&100].start_time.as_deref(), Some("Thu Oct 5 04:56:40 2026"));
+        assert_eq!(start_times[&100].started_at, Some(1_000_000 - 86_400 - 6 * 3600 - 12 * 60 - 5));
+        assert!(!start_times.contains_key(&200));
+        assert_eq!(parse_elapsed("00:07"), Some(7));
+        assert_eq!(parse_elapsed("06:12:05"), Some(22_325));
+        assert_eq!(parse_elapsed("12"), None);
+        assert_eq!(parse_elapsed("1-xx:00:00"), None);
+
+        let cwds = parse_lsof_cwds("p100\nfcwd\nn/Users/me/src/api\np200\nfcwd\nn/Users/me/src/web app\n");
+        assert_eq!(cwds[&100], "/Users/me/src/api");
+        assert_eq!(cwds[&200], "/Users/me/src/web app");
+
+        let own = std::process::id() as i32;
+        let context = &process_context(&[own])[&own];
+        assert_eq!(context.cwd.as_deref(), std::env::current_dir().ok().as_deref().and_then(Path::to_str));
+        assert!(context.start_time.is_some());
+        assert!(context.started_at.is_some());
+        assert!(context.argv.as_ref().is_some_and(|argv| !argv.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_command_lines() {
+        assert_eq!(
+            parse_proc_cmdline(b"node\0server.js\0--name\0my app\0"),
+            Some(vec!["node".to_string(), "server.js".to_string(), "--name".to_string(), "my app".to_string()])
+        );
+        assert_eq!(parse_proc_cmdline(b""), None);
+
+        let mut procargs = 2i32.to_ne_bytes().to_vec();
+        procargs.extend_from_slice(b"/usr/local/bin/node\0\0\0node\0--name=my app\0HOME=/Users/me\0");
+        assert_eq!(parse_procargs2(&procargs), Some(vec!["node".to_string(), "--name=my app".to_string()]));
+        assert_eq!(parse_procargs2(&procargs[..procargs.len() - 30]), None);
+        assert_eq!(parse_procargs2(b"\0\0"), None);
+    }
+
+    #[test]
+    fn test_port_from_lsof_name() {
+        assert_eq!(port_from_lsof_name("127.0.0.1:3000"), Some(3000));
+        assert_eq!(port_from_lsof_name("localhost:8080"), Some(8080));
+        assert_eq!(port_from_lsof_name("*:8080"), Some(8080));
+        assert_eq!(port_from_lsof_name("[::1]:3000"), Some(3000));
+        assert_eq!(port_from_lsof_name("[::]:5353"), Some(5353));
+        assert_eq!(port_from_lsof_name("[fe80::1%lo0]:4000"), Some(4000));
+        assert_eq!(port_from_lsof_name("[::1]:3000->[::1]:52000"), Some(3000));
+        assert_eq!(port_from_lsof_name("127.0.0.1:5353->10.0.0.1:9"), Some(5353));
+
+        assert_eq!(port_from_lsof_name(""), None);
+        assert_eq!(port_from_lsof_name("3000"), None);
+        assert_eq!(port_from_lsof_name(":3000"), None);
+        assert_eq!(port_from_lsof_name("*:"), None);
+        assert_eq!(port_from_lsof_name("*:http"), None);
+        assert_eq!(port_from_lsof_name("127.0.0.1:70000"), None);
+        assert_eq!(port_from_lsof_name("::1:3000"), None);
+        assert_eq!(port_from_lsof_name("[::1]3000"), None);
+        assert_eq!(port_from_lsof_name("[::1:3000"), None);
+    }
+
+    #[test]
+    fn test_contiguous_range() {
+        assert_eq!(contiguous_range(&[2000, 2001, 2002]), Some((2000, 2002)));
+        assert_eq!(contiguous_range(&[3000, 3002]), None);
+        assert_eq!(contiguous_range(&[3001, 3000]), None);
+        assert_eq!(contiguous_range(&[3000]), None);
+        assert_eq!(contiguous_range(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_lsof_pids_lists_every_process_sharing_a_port() {
+        // A forking server: the parent and its workers all hold the listening socket
+        let output = "p4100\nf5\ntIPv4\nn*:8000\np4101\nf5\ntIPv4\nn*:8000\np4102\nf5\ntIPv4\nn*:8000\n";
+        let pids = parse_lsof_pids(output, 8000, None);
+        assert_eq!(pids.iter().map(|(pid, _)| *pid).collect::<Vec<_>>(), vec![4100, 4101, 4102]);
+        assert!(pids.iter().all(|(_, addresses)| addresses == &[IpAddr::V4(Ipv4Addr::UNSPECIFIED)]));
+        assert_eq!(
+            parse_lsof_listeners(output, None).into_iter().map(|(pid, port, _)| (pid, port)).collect::<Vec<_>>(),
+            vec![(4100, 8000), (4101, 8000), (4102, 8000)]
+        );
+    }
+
+    #[test]
+    fn test_parse_lsof_listeners_across_ports() {
+        let output = "p100\nf5\ntIPv4\nn*:3000\nf6\ntIPv6\nn[::1]:3001\np200\ntIPv4\nn127.0.0.1:5000\n";
+        let any = Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let loopback = Some(IpAddr::V6(Ipv6Addr::LOCALHOST));
+        let local = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(
+            parse_lsof_listeners(output, None),
+            vec![(100, 3000, any), (100, 3001, loopback), (200, 5000, local)]
+        );
+        assert_eq!(parse_lsof_listeners(output, local), vec![(100, 3000, any), (200, 5000, local)]);
+    }
+
+    #[test]
+    fn test_address_from_lsof_name() {
+        let address = |name| address_from_lsof_name(name, false).map(|address| address.to_string());
+        assert_eq!(address("*:3000").as_deref(), Some("0.0.0.0"));
+        assert_eq!(address_from_lsof_name("*:3000", true), Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+        assert_eq!(address("127.0.0.1:3000").as_deref(), Some("127.0.0.1"));
+        assert_eq!(address("[::]:5353").as_deref(), Some("::"));
+        assert_eq!(address("[fe80::1%lo0]:4000").as_deref(), Some("fe80::1"));
+        assert_eq!(address("192.168.1.5:5353->10.0.0.1:9").as_deref(), Some("192.168.1.5"));
+        assert_eq!(address("localhost:8080"), None);
+        assert_eq!(address("3000"), None);
+    }
+
+    #[test]
+    fn test_accepts_address() {
+        let address = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+        assert!(accepts_address(None, None));
+        assert!(accepts_address(address("127.0.0.1"), address("127.0.0.1")));
+        assert!(accepts_address(address("127.0.0.1"), address("0.0.0.0")));
+        assert!(accepts_address(address("127.0.0.1"), address("::")));
+        assert!(!accepts_address(address("127.0.0.1"), address("192.168.1.5")));
+        assert!(!accepts_address(address("127.0.0.1"), None));
+    }
+
+    #[test]
+    fn test_parse_proc_net() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+            0: 00000000:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000   501        0 4242 1 0000000000000000 100 0 0 10 0\n   \
+            1: 0100007F:1F90 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000     0        0 4343 1 0000000000000000 20 4 30 10 -1\n";
+        assert_eq!(
+            parse_proc_net(tcp, Protocol::Tcp),
+            vec![ProcSocket { address: IpAddr::V4(Ipv4Addr::UNSPECIFIED), port: 3000, uid: 501, inode: 4242 }]
+        );
+
+        // UDP sockets count in any state; torn-down sockets (inode 0) never do
+        let udp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n  \
+            10: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 5555 2 0000000000000000 0\n  \
+            11: 00000000:14EA 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 0 2 0000000000000000 0\n";
+        assert_eq!(
+            parse_proc_net(udp, Protocol::Udp),
+            vec![ProcSocket { address: IpAddr::V4(Ipv4Addr::UNSPECIFIED), port: 5353, uid: 0, inode: 5555 }]
+        );
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn test_parse_proc_address() {
+        assert_eq!(parse_proc_address("0100007F"), Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert_eq!(parse_proc_address("0501A8C0"), Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 5))));
+        assert_eq!(parse_proc_address("00000000000000000000000001000000"), Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        assert_eq!(parse_proc_address("0000"), None);
+        assert_eq!(parse_proc_address("nothex00"), None);
+    }
+
+    #[test]
+    fn test_parse_proc_usage() {
+        // 300 + 100 ticks of CPU in the 20s since the process started 80s after boot
+        let stat = "1234 (my server) S 1 1234 1234 0 -1 4194560 100 0 0 0 300 100 0 0 20 0 1 0 8000 1000000 2000";
+        assert_eq!(parse_proc_cpu(stat, 100.0, 100.0), Some(20.0));
+        assert_eq!(parse_proc_cpu("1234 (sh) S", 100.0, 100.0), None);
+
+        assert_eq!(parse_vm_rss("Name:\tnode\nVmPeak:\t 500000 kB\nVmRSS:\t  348160 kB\n"), Some(340));
+        assert_eq!(parse_vm_rss("Name:\tkthreadd\n"), None);
+    }
+
+    #[test]
+    fn test_socket_inode() {
+        assert_eq!(socket_inode(Path::new("socket:[12345]")), Some(12345));
+        assert_eq!(socket_inode(Path::new("pipe:[12345]")), None);
+        assert_eq!(socket_inode(Path::new("/dev/null")), None);
+    }
+
+    #[tokio::test]
+    async fn test_proc_scanner_matches_sockets_to_pids() {
+        let root = std::env::temp_dir().join(format!("port-kill-proc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("net")).unwrap();
+        std::fs::write(
+            root.join("net/tcp"),
+            "  sl  local_address rem_address   st\n   \
+             0: 00000000:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000   501        0 4242 1\n",
+        )
+        .unwrap();
+        // node also listens on [::]:3000
+        std::fs::write(
+            root.join("net/tcp6"),
+            "  sl  local_address rem_address   st\n   \
+             0: 00000000000000000000000000000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000   501        0 4444 1\n",
+        )
+        .unwrap();
+        for (pid, comm, links) in [
+            (20, "node", &["socket:[4242]", "socket:[4444]"][..]),
+            (30, "other", &["socket:[4242]"][..]),
+            (40, "idle", &["pipe:[1]"][..]),
+        ] {
+            let dir = root.join(pid.to_string());
+            std::fs::create_dir_all(dir.join("fd")).unwrap();
+            std::fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+            for (fd, link) in links.iter().enumerate() {
+                std::os::unix::fs::symlink(link, dir.join("fd").join((fd + 3).to_string())).unwrap();
+            }
+        }
+
+        let scanner = ProcScanner::at(&root);
+        assert!(scanner.is_available());
+        let processes = scanner.scan(&[3000, 3001], &[Protocol::Tcp, Protocol::Udp]).await.unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // The shared socket is reported once, for the lowest PID, noting the other holder
+        assert_eq!(processes.len(), 1);
+        let process_info = &processes[&Endpoint::tcp(3000)];
+        assert_eq!((process_info.pid, process_info.name.as_str(), process_info.uid), (20, "node", Some(501)));
+        assert_eq!(process_info.shared_pids, vec![30]);
+        assert_eq!(process_info.family, Some(IpFamily::Both));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_proc_scanner_finds_own_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let processes = ProcScanner::new().scan(&[port], &[Protocol::Tcp]).await.unwrap();
+        assert_eq!(processes[&Endpoint::tcp(port)].pid, std::process::id() as i32);
+    }
+}
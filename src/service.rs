@@ -0,0 +1,321 @@
+//! `--install-service` and `--uninstall-service`: start port-kill at login, headless in
+//! console mode, as a launchd agent on macOS or a systemd user unit elsewhere.
+//!
+//! The service runs the binary that installed it with the flags given alongside
+//! `--install-service`, from the directory it was installed in, so relative paths such as
+//! `--ports-file .ports` and `--discover` keep working.
+
+use crate::config;
+use anyhow::{Context, Result};
+use log::warn;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The launchd label, which also names the plist.
+const LAUNCHD_LABEL: &str = "com.processafk.port-kill";
+/// The systemd unit file name.
+const SYSTEMD_UNIT: &str = "port-kill.service";
+
+/// What starts the service at login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceManager {
+    Launchd,
+    Systemd,
+}
+
+impl ServiceManager {
+    /// The service manager of the platform port-kill was built for.
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Launchd
+        } else {
+            Self::Systemd
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Launchd => "launchd agent",
+            Self::Systemd => "systemd user unit",
+        }
+    }
+}
+
+/// A service definition and where it is installed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Service {
+    pub manager: ServiceManager,
+    /// The port-kill binary the service runs
+    pub binary: PathBuf,
+    /// Flags passed to `binary`
+    pub args: Vec<String>,
+    /// Directory the service runs in
+    pub working_dir: PathBuf,
+    /// The user's home directory
+    pub home: PathBuf,
+    /// The user's configuration directory, where systemd looks for user units
+    pub config_home: PathBuf,
+}
+
+impl Service {
+    /// The service for this platform running the current binary from the current directory,
+    /// with the flags in `argv` (including the program name, as from [`std::env::args`]).
+    pub fn from_env(argv: impl IntoIterator<Item = String>) -> Result<Self> {
+        let home = std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .context("HOME is not set, so there is nowhere to install the service")?;
+        Ok(Self {
+            manager: ServiceManager::current(),
+            binary: std::env::current_exe().context("Failed to find the port-kill binary")?,
+            args: service_args(argv),
+            working_dir: std::env::current_dir().context("Failed to read the current directory")?,
+            config_home: config::config_home().unwrap_or_else(|| home.join(".config")),
+            home,
+        })
+    }
+
+    /// Where the service definition is written.
+    pub fn path(&self) -> PathBuf {
+        match self.manager {
+            ServiceManager::Launchd => self.home.join("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL)),
+            ServiceManager::Systemd => self.config_home.join("systemd/user").join(SYSTEMD_UNIT),
+        }
+    }
+
+    /// The contents of the service definition.
+    pub fn definition(&self) -> String {
+        match self.manager {
+            ServiceManager::Launchd => self.launchd_plist(),
+            ServiceManager::Systemd => self.systemd_unit(),
+        }
+    }
+
+    fn launchd_plist(&self) -> String {
+        let log = self.home.join("Library/Logs/port-kill.log");
+        let arguments: String = std::iter::once(self.binary.to_string_lossy().into_owned())
+            .chain(self.args.iter().cloned())
+            .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{arguments}    </array>
+    <key>WorkingDirectory</key>
+    <string>{working_dir}</string>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+            label = LAUNCHD_LABEL,
+            arguments = arguments,
+            working_dir = xml_escape(&self.working_dir.to_string_lossy()),
+            log = xml_escape(&log.to_string_lossy()),
+        )
+    }
+
+    fn systemd_unit(&self) -> String {
+        let exec_start: Vec<String> = std::iter::once(self.binary.to_string_lossy().into_owned())
+            .chain(self.args.iter().cloned())
+            .map(|arg| systemd_quote(&arg))
+            .collect();
+        format!(
+            "[Unit]\n\
+             Description=Port Kill - development port monitor\n\
+             \n\
+             [Service]\n\
+             ExecStart={}\n\
+             WorkingDirectory={}\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            exec_start.join(" "),
+            // Paths here are taken verbatim, apart from `%` specifiers
+            self.working_dir.to_string_lossy().replace('%', "%%"),
+        )
+    }
+
+    /// Write the definition and ask the service manager to start it now and at every login,
+    /// returning the path written. Only a failure to write is an error: the service still
+    /// starts at the next login if the manager can't be reached now.
+    pub fn install(&self) -> Result<PathBuf> {
+        let path = self.write()?;
+        match self.manager {
+            ServiceManager::Launchd => run_manager("launchctl", &["load", "-w", &path.to_string_lossy()]),
+            ServiceManager::Systemd => {
+                run_manager("systemctl", &["--user", "daemon-reload"]);
+                run_manager("systemctl", &["--user", "enable", "--now", SYSTEMD_UNIT]);
+            }
+        }
+        Ok(path)
+    }
+
+    /// Stop the service and remove its definition, returning the path removed, or `None` if
+    /// it wasn't installed.
+    pub fn uninstall(&self) -> Result<Option<PathBuf>> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        match self.manager {
+            ServiceManager::Launchd => run_manager("launchctl", &["unload", "-w", &path.to_string_lossy()]),
+            ServiceManager::Systemd => run_manager("systemctl", &["--user", "disable", "--now", SYSTEMD_UNIT]),
+        }
+        self.remove()?;
+        if self.manager == ServiceManager::Systemd {
+            run_manager("systemctl", &["--user", "daemon-reload"]);
+        }
+        Ok(Some(path))
+    }
+
+    fn write(&self) -> Result<PathBuf> {
+        let path = self.path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, self.definition()).with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    fn remove(&self) -> Result<()> {
+        let path = self.path();
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))
+    }
+}
+
+/// Install or uninstall the service as `--install-service` or `--uninstall-service` asks,
+/// reporting the path written or removed.
+pub fn run(install: bool) -> Result<()> {
+    let service = Service::from_env(std::env::args())?;
+    if install {
+        let path = service.install()?;
+        println!("✅ Installed {} at {}", service.manager.describe(), path.display());
+        println!("   Runs: {} {}", service.binary.display(), service.args.join(" "));
+    } else {
+        match service.uninstall()? {
+            Some(path) => println!("🗑️  Removed {} at {}", service.manager.describe(), path.display()),
+            None => println!("No {} installed at {}", service.manager.describe(), service.path().display()),
+        }
+    }
+    Ok(())
+}
+
+/// The flags the service runs with: those in `argv` besides the program name and
+/// `--install-service`, in console mode.
+pub fn service_args(argv: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut args: Vec<String> = argv.into_iter().skip(1).filter(|arg| arg != "--install-service").collect();
+    if !args.iter().any(|arg| arg == "--console" || arg == "-c") {
+        args.insert(0, "--console".to_string());
+    }
+    args
+}
+
+/// Run a service manager command, only warning if it fails.
+fn run_manager(program: &str, args: &[&str]) {
+    match Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => warn!("{} {} failed: {}", program, args.join(" "), String::from_utf8_lossy(&output.stderr).trim()),
+        Err(e) => warn!("Failed to run {}: {}", program, e),
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Quote `text` as one word of a systemd command line, escaping what systemd would
+/// otherwise expand: `%` specifiers and `$` variables.
+fn systemd_quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn service(manager: ServiceManager) -> Service {
+        Service {
+            manager,
+            binary: PathBuf::from("/usr/local/bin/port-kill"),
+            args: service_args(["port-kill", "--install-service", "--ports", "3000,8080", "--ignore", "a&b"].map(str::to_string)),
+            working_dir: PathBuf::from("/home/me/web"),
+            home: PathBuf::from("/home/me"),
+            config_home: PathBuf::from("/home/me/.config"),
+        }
+    }
+
+    #[test]
+    fn test_service_args_run_in_console_mode() {
+        assert_eq!(service(ServiceManager::Systemd).args, ["--console", "--ports", "3000,8080", "--ignore", "a&b"]);
+        let args = service_args(["port-kill-console", "-c", "--install-service"].map(str::to_string));
+        assert_eq!(args, ["-c"]);
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let service = service(ServiceManager::Systemd);
+        assert_eq!(service.path(), Path::new("/home/me/.config/systemd/user/port-kill.service"));
+        let unit = service.definition();
+        assert!(
+            unit.contains("\nExecStart=\"/usr/local/bin/port-kill\" \"--console\" \"--ports\" \"3000,8080\" \"--ignore\" \"a&b\"\n"),
+            "{}",
+            unit
+        );
+        assert!(unit.contains("\nWorkingDirectory=/home/me/web\n"), "{}", unit);
+        assert!(unit.ends_with("[Install]\nWantedBy=default.target\n"), "{}", unit);
+        assert_eq!(systemd_quote(r#"50% "$HOME""#), r#""50%% \"$$HOME\"""#);
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let service = service(ServiceManager::Launchd);
+        assert_eq!(service.path(), Path::new("/home/me/Library/LaunchAgents/com.processafk.port-kill.plist"));
+        let plist = service.definition();
+        assert!(
+            plist.contains("<array>\n        <string>/usr/local/bin/port-kill</string>\n        <string>--console</string>\n"),
+            "{}",
+            plist
+        );
+        assert!(plist.contains("<string>a&amp;b</string>"), "{}", plist);
+        assert!(plist.contains("<key>WorkingDirectory</key>\n    <string>/home/me/web</string>"), "{}", plist);
+        assert!(plist.contains("<key>RunAtLoad</key>\n    <true/>"), "{}", plist);
+    }
+
+    #[test]
+    fn test_writes_and_removes_definition() {
+        let root = std::env::temp_dir().join(format!("port-kill-service-{}", std::process::id()));
+        let service = Service { home: root.clone(), config_home: root.join(".config"), ..service(ServiceManager::Systemd) };
+
+        let path = service.write().unwrap();
+        assert_eq!(path, root.join(".config/systemd/user/port-kill.service"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), service.definition());
+        service.remove().unwrap();
+        assert!(!path.exists());
+        // Nothing is stopped or removed when nothing was installed
+        assert_eq!(service.uninstall().unwrap(), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,88 @@
+//! Periodic full snapshots for `--snapshot-every` / `--output-dir`.
+//!
+//! Each file is a self-contained JSON document of every process seen by one scan, named by
+//! the scan's timestamp, so any two can be diffed to reconstruct what changed in between.
+
+use crate::types::{Endpoint, ProcessInfo};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize)]
+struct Snapshot<'a> {
+    /// Milliseconds since the Unix epoch
+    timestamp_ms: u128,
+    count: usize,
+    /// Sorted by port so consecutive snapshots diff cleanly
+    processes: Vec<&'a ProcessInfo>,
+}
+
+#[derive(Debug)]
+pub struct SnapshotWriter {
+    every: u32,
+    dir: PathBuf,
+    /// Scans since the last snapshot was written
+    scans: u32,
+}
+
+impl SnapshotWriter {
+    /// Write a snapshot on every `every`-th scan into `dir`, creating it if needed.
+    pub fn new(every: u32, dir: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { every: every.max(1), dir, scans: 0 })
+    }
+
+    /// Count a completed scan, writing a snapshot when the cadence is due. Returns the path
+    /// of the file written, if any.
+    pub fn record(&mut self, processes: &HashMap<Endpoint, ProcessInfo>) -> Result<Option<PathBuf>> {
+        self.scans += 1;
+        if self.scans < self.every {
+            return Ok(None);
+        }
+        self.scans = 0;
+
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let mut sorted: Vec<&ProcessInfo> = processes.values().collect();
+        sorted.sort_by_key(|p| p.endpoint());
+
+        let snapshot = Snapshot {
+            timestamp_ms,
+            count: sorted.len(),
+            processes: sorted,
+        };
+        let path = self.dir.join(format!("snapshot-{}.json", timestamp_ms));
+        let body = serde_json::to_vec_pretty(&snapshot).context("Failed to serialize snapshot")?;
+        std::fs::write(&path, body).with_context(|| format!("Failed to write snapshot {}", path.display()))?;
+        Ok(Some(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+
+    #[test]
+    fn test_writes_on_cadence() {
+        let dir = std::env::temp_dir().join(format!("port-kill-snapshots-{}", std::process::id()));
+        let mut writer = SnapshotWriter::new(2, dir.clone()).unwrap();
+        let processes = HashMap::from([(
+            Endpoint::tcp(3000),
+            process_on(3000, 123),
+        )]);
+
+        assert!(writer.record(&processes).unwrap().is_none());
+        let path = writer.record(&processes).unwrap().expect("second scan writes a snapshot");
+
+        let written: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(written["count"], 1);
+        assert_eq!(written["processes"][0]["pid"], 123);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
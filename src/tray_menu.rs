@@ -1,46 +1,303 @@
-use crate::types::{ProcessInfo, StatusBarInfo};
+use crate::{
+    cli::{Args, SortKey},
+    compose,
+    types::{Endpoint, ProcessInfo, ProcessUpdate, StatusBarInfo},
+};
 use anyhow::Result;
 use crossbeam_channel::Sender;
 use log::debug;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use tray_icon::{
-    menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem},
+    menu::{Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem},
     Icon,
 };
 
+pub const KILL_ALL_ID: &str = "kill_all";
+pub const QUIT_ID: &str = "quit";
+pub const ABOUT_ID: &str = "about";
+const PROCESS_ID_PREFIX: &str = "process_";
+const COMPOSE_ID_PREFIX: &str = "compose_";
+const RESTART_ID_PREFIX: &str = "restart_";
+const PORT_ID_PREFIX: &str = "port_";
+
+/// What the app should do in response to a menu click.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuAction {
+    KillAll,
+    KillPid(i32),
+    /// Kill the PID, then relaunch its recorded command line.
+    KillAndRestart(i32),
+    /// Kill whatever holds the port when the item is clicked, scanned again then so a PID
+    /// that has since exited and been reused is never signalled.
+    KillPort(u16),
+    /// Stop every container of the named Docker Compose project.
+    KillComposeProject(String),
+    Quit,
+    /// A kill item was armed and is waiting for a confirming second click.
+    AwaitingConfirmation,
+    /// A kill item was clicked while `--watch-only` disables killing.
+    KillDisabled,
+    /// The id is unknown or refers to a process that is no longer listed.
+    Ignore,
+}
+
+impl MenuAction {
+    /// Whether carrying out this action would kill processes or stop containers.
+    pub fn is_kill(&self) -> bool {
+        matches!(
+            self,
+            MenuAction::KillAll
+                | MenuAction::KillPid(_)
+                | MenuAction::KillAndRestart(_)
+                | MenuAction::KillPort(_)
+                | MenuAction::KillComposeProject(_)
+        )
+    }
+}
+
+/// How this instance is configured, shown as a disabled item so tray users can report it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AboutInfo {
+    pub version: &'static str,
+    pub ports: String,
+    pub port_count: usize,
+    pub backend: &'static str,
+    pub docker: bool,
+    /// Killing is disabled by `--watch-only`
+    pub watch_only: bool,
+}
+
+impl AboutInfo {
+    /// Describe an instance configured by `args` that scans with `backend`.
+    pub fn from_args(args: &Args, backend: &'static str) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            ports: args.get_port_description(),
+            port_count: args.get_ports_to_monitor().len(),
+            backend,
+            docker: args.docker,
+            watch_only: args.watch_only,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let label = format!(
+            "Port Kill v{} · {} ({} ports) · {} · Docker {}",
+            self.version,
+            self.ports,
+            self.port_count,
+            self.backend,
+            if self.docker { "on" } else { "off" }
+        );
+        if self.watch_only {
+            format!("{} · watch-only", label)
+        } else {
+            label
+        }
+    }
+}
+
+/// An RGB color written as six hex digits, e.g. `FFAA00`, with an optional leading `#`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub [u8; 3]);
+
+impl FromStr for HexColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("'{}' is not a color, expected six hex digits like FFAA00", s));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("validated hex digits");
+        Ok(Self([channel(0), channel(2), channel(4)]))
+    }
+}
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(color: HexColor) -> Self {
+        color.to_string()
+    }
+}
+
+impl fmt::Display for HexColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b] = self.0;
+        write!(f, "{:02X}{:02X}{:02X}", r, g, b)
+    }
+}
+
+impl HexColor {
+    /// Black or white, whichever reads better on this color.
+    pub fn contrasting(self) -> Self {
+        let [r, g, b] = self.0.map(u32::from);
+        // Perceived brightness, weighting green highest as the eye does
+        if r * 299 + g * 587 + b * 114 > 128_000 {
+            Self([0, 0, 0])
+        } else {
+            Self([255, 255, 255])
+        }
+    }
+}
+
+/// Side of the square tray icon in pixels.
+const ICON_SIZE: usize = 32;
+/// Rows and columns of the colored square the count is drawn in, leaving a white border.
+const COUNT_AREA: std::ops::RangeInclusive<usize> = 3..=28;
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+/// The 3x5 bitmap of a character the icon can show, one row per entry with the leftmost
+/// pixel in the highest of the three bits.
+fn glyph(c: char) -> Option<[u8; GLYPH_HEIGHT]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        _ => return None,
+    })
+}
+
+/// The count as the icon shows it: up to two digits, then `99+`.
+fn count_label(count: usize) -> String {
+    if count > 99 {
+        "99+".to_string()
+    } else {
+        count.to_string()
+    }
+}
+
+/// Which pixels of the icon `label` covers, row by row. Glyphs are spaced one column apart,
+/// scaled up as far as fits within a `max` pixel square and centered in the icon.
+fn render_label(label: &str, max: usize) -> Vec<bool> {
+    let glyphs: Vec<[u8; GLYPH_HEIGHT]> = label.chars().filter_map(glyph).collect();
+    let mut pixels = vec![false; ICON_SIZE * ICON_SIZE];
+    if glyphs.is_empty() {
+        return pixels;
+    }
+
+    let columns = glyphs.len() * (GLYPH_WIDTH + 1) - 1;
+    let scale = (max / columns).min(max / GLYPH_HEIGHT).max(1);
+    let left = ICON_SIZE.saturating_sub(columns * scale) / 2;
+    let top = ICON_SIZE.saturating_sub(GLYPH_HEIGHT * scale) / 2;
+
+    for (i, rows) in glyphs.iter().enumerate() {
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+                let x = left + (i * (GLYPH_WIDTH + 1) + column) * scale;
+                let y = top + row * scale;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        if let Some(pixel) = pixels.get_mut((y + dy) * ICON_SIZE + x + dx) {
+                            *pixel = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// Color of the icon while a reserved port is free, whatever the count.
+const ALARM_COLOR: HexColor = HexColor([255, 0, 0]);
+
+/// Which color the icon's count area takes for a given number of processes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IconStyle {
+    /// Color when nothing is running
+    pub idle: HexColor,
+    /// Color when something is running, but fewer than `warn_count` processes
+    pub busy: HexColor,
+    /// Color from `warn_count` processes up
+    pub warn: HexColor,
+    pub warn_count: usize,
+}
+
+impl Default for IconStyle {
+    fn default() -> Self {
+        Self {
+            idle: HexColor([0, 255, 0]),
+            busy: HexColor([255, 0, 0]),
+            warn: HexColor([255, 165, 0]),
+            warn_count: 10,
+        }
+    }
+}
+
+impl IconStyle {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            idle: args.idle_color,
+            busy: args.busy_color,
+            warn: args.warn_color,
+            warn_count: args.warn_count,
+        }
+    }
+
+    pub fn color_for(&self, count: usize) -> HexColor {
+        if count == 0 {
+            self.idle
+        } else if count >= self.warn_count {
+            self.warn
+        } else {
+            self.busy
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct TrayMenu {
     pub menu: Menu,
     pub icon: Icon,
-    menu_sender: Sender<MenuEvent>,
+    pub about: AboutInfo,
+    pub icon_style: IconStyle,
 }
 
 impl TrayMenu {
-    pub fn new(menu_sender: Sender<MenuEvent>) -> Result<Self> {
-        // Create a simple icon (we'll use a text-based approach for now)
-        let icon = Self::create_icon("0")?;
-
-        // Create initial menu
-        let menu = Self::create_menu(&HashMap::new(), false)?;
+    pub fn new(menu_sender: Sender<MenuEvent>, about: AboutInfo, icon_style: IconStyle) -> Result<Self> {
+        // Start from the empty baseline; the first scan replaces it right after launch
+        let baseline = ProcessUpdate::empty();
+        let icon = Self::create_icon(&StatusBarInfo::from_update(&baseline), &icon_style)?;
+        let menu = Self::create_menu(&baseline.processes, false, SortKey::default(), None, &about)?;
 
         // Set up menu event handling
-        let sender_clone = menu_sender.clone();
         MenuEvent::set_event_handler(Some(move |event| {
-            let _ = sender_clone.send(event);
+            let _ = menu_sender.send(event);
         }));
 
-        Ok(Self {
-            menu,
-            icon,
-            menu_sender,
-        })
+        Ok(Self { menu, icon, about, icon_style })
     }
 
-    pub fn update_menu(&mut self, processes: &HashMap<u16, ProcessInfo>) -> Result<()> {
+    pub fn update_menu(&mut self, processes: &HashMap<Endpoint, ProcessInfo>) -> Result<()> {
         debug!("Updating menu with {} processes", processes.len());
         
         // Create new menu with current processes
-        let new_menu = Self::create_menu(processes, false)?;
+        let new_menu = Self::create_menu(processes, false, SortKey::default(), None, &self.about)?;
         self.menu = new_menu;
         
         Ok(())
@@ -50,16 +307,35 @@ impl TrayMenu {
         debug!("Updating status bar: {}", status_info.text);
         
         // Update icon with new status text
-        self.icon = Self::create_icon(&status_info.text)?;
+        self.icon = Self::create_icon(status_info, &self.icon_style)?;
         
         Ok(())
     }
 
-    pub fn create_menu(processes: &HashMap<u16, ProcessInfo>, show_pid: bool) -> Result<Menu> {
+    /// Build the menu. `confirming` is the id of a kill item awaiting a second click, which
+    /// is relabelled so the user knows the next click will go through.
+    pub fn create_menu(
+        processes: &HashMap<Endpoint, ProcessInfo>,
+        show_pid: bool,
+        sort: SortKey,
+        confirming: Option<&MenuId>,
+        about: &AboutInfo,
+    ) -> Result<Menu> {
         let menu = Menu::new();
+        let is_confirming = |id: &str| confirming.is_some_and(|c| c.as_ref() == id);
+        // With --watch-only every kill item is shown greyed out
+        let kill_enabled = !about.watch_only;
+
+        // Sort so entries keep their place between scans
+        let processes = sort.sorted(processes.values());
 
         // Add "Kill All Processes" item
-        let kill_all_item = MenuItem::new("Kill All Processes", true, None);
+        let kill_all_text = if is_confirming(KILL_ALL_ID) {
+            "Click again to confirm: Kill All Processes"
+        } else {
+            "Kill All Processes"
+        };
+        let kill_all_item = MenuItem::with_id(KILL_ALL_ID, kill_all_text, kill_enabled, None);
         menu.append(&kill_all_item)?;
 
         // Add separator
@@ -67,8 +343,12 @@ impl TrayMenu {
         menu.append(&separator)?;
 
         // Add individual process items
-        for (port, process_info) in processes {
-            let menu_text = if let (Some(_container_id), Some(container_name)) = (&process_info.container_id, &process_info.container_name) {
+        for &process_info in &processes {
+            let port = process_info.endpoint();
+            let menu_id = Self::process_menu_id(process_info.pid);
+            let menu_text = if is_confirming(&menu_id) {
+                format!("Click again to confirm: Port {}", port)
+            } else if let (Some(_container_id), Some(container_name)) = (&process_info.container_id, &process_info.container_name) {
                 format!(
                     "Kill: Port {}: {} [Docker: {}]",
                     port, process_info.name, container_name
@@ -84,10 +364,54 @@ impl TrayMenu {
                     port, process_info.name
                 )
             };
-            let _menu_id = format!("process_{}", process_info.pid);
-            
-            let process_item = MenuItem::new(&menu_text, true, None);
+            let menu_text = match process_info.resource_usage() {
+                Some(usage) if !is_confirming(&menu_id) => format!("{} ({})", menu_text, usage),
+                _ => menu_text,
+            };
+            // Root-owned processes are protected unless --include-system, make that visible
+            let menu_text = if process_info.is_root() {
+                format!("🔒 {} (root)", menu_text)
+            } else {
+                menu_text
+            };
+            let process_item = MenuItem::with_id(menu_id, &menu_text, kill_enabled, None);
             menu.append(&process_item)?;
+
+            // Without a command line there is nothing to relaunch, so the item is greyed out
+            let restart_id = Self::restart_menu_id(process_info.pid);
+            let restart_text = if is_confirming(&restart_id) {
+                format!("Click again to confirm: Kill & Restart Port {}", port)
+            } else {
+                format!("Kill & Restart: Port {}: {}", port, process_info.name)
+            };
+            let restart_enabled = kill_enabled && process_info.can_restart();
+            menu.append(&MenuItem::with_id(restart_id, &restart_text, restart_enabled, None))?;
+        }
+
+        // Each port in use can also be killed by number, whatever holds it by then
+        let mut ports: Vec<u16> = processes.iter().filter_map(|p| p.endpoint().port()).collect();
+        ports.sort_unstable();
+        ports.dedup();
+        for port in ports {
+            let menu_id = Self::port_menu_id(port);
+            let menu_text = if is_confirming(&menu_id) {
+                format!("Click again to confirm: Kill Whatever Is on Port {}", port)
+            } else {
+                format!("Kill Whatever Is on Port {}", port)
+            };
+            menu.append(&MenuItem::with_id(menu_id, &menu_text, kill_enabled, None))?;
+        }
+
+        // Containers of one Compose project can be stopped together
+        let projects = compose::group_by_project(processes.iter().copied());
+        for project in projects.keys() {
+            let menu_id = Self::compose_menu_id(project);
+            let menu_text = if is_confirming(&menu_id) {
+                format!("Click again to confirm: Kill Compose Project: {}", project)
+            } else {
+                format!("Kill Compose Project: {}", project)
+            };
+            menu.append(&MenuItem::with_id(menu_id, &menu_text, kill_enabled, None))?;
         }
 
         // Add another separator if there are processes
@@ -96,16 +420,72 @@ impl TrayMenu {
             menu.append(&separator)?;
         }
 
+        // Add the informational "About" line, disabled so it can't be clicked
+        let about_item = MenuItem::with_id(ABOUT_ID, about.label(), false, None);
+        menu.append(&about_item)?;
+
         // Add "Quit" item
-        let quit_item = MenuItem::new("Quit", true, None);
+        let quit_item = MenuItem::with_id(QUIT_ID, "Quit", true, None);
         menu.append(&quit_item)?;
 
         Ok(menu)
     }
 
-    pub fn create_icon(text: &str) -> Result<Icon> {
+    pub fn process_menu_id(pid: i32) -> String {
+        format!("{}{}", PROCESS_ID_PREFIX, pid)
+    }
+
+    pub fn restart_menu_id(pid: i32) -> String {
+        format!("{}{}", RESTART_ID_PREFIX, pid)
+    }
+
+    pub fn port_menu_id(port: u16) -> String {
+        format!("{}{}", PORT_ID_PREFIX, port)
+    }
+
+    pub fn compose_menu_id(project: &str) -> String {
+        format!("{}{}", COMPOSE_ID_PREFIX, project)
+    }
+
+    /// Map a clicked menu id to the action it represents.
+    pub fn action_for(id: &MenuId, processes: &HashMap<Endpoint, ProcessInfo>) -> MenuAction {
+        let id = id.as_ref();
+        if id == KILL_ALL_ID {
+            return MenuAction::KillAll;
+        }
+        if id == QUIT_ID {
+            return MenuAction::Quit;
+        }
+
+        // Only act on PIDs and projects that are still listed, the menu may be older than
+        // the last scan
+        if let Some(project) = id.strip_prefix(COMPOSE_ID_PREFIX) {
+            if processes.values().any(|p| p.compose_project.as_deref() == Some(project)) {
+                return MenuAction::KillComposeProject(project.to_string());
+            }
+            return MenuAction::Ignore;
+        }
+        if let Some(port) = id.strip_prefix(PORT_ID_PREFIX).and_then(|port| port.parse::<u16>().ok()) {
+            if processes.values().any(|p| p.endpoint().port() == Some(port)) {
+                return MenuAction::KillPort(port);
+            }
+            return MenuAction::Ignore;
+        }
+        if let Some(pid) = id.strip_prefix(RESTART_ID_PREFIX).and_then(|pid| pid.parse::<i32>().ok()) {
+            if processes.values().any(|p| p.pid == pid && p.can_restart()) {
+                return MenuAction::KillAndRestart(pid);
+            }
+            return MenuAction::Ignore;
+        }
+        match id.strip_prefix(PROCESS_ID_PREFIX).and_then(|pid| pid.parse::<i32>().ok()) {
+            Some(pid) if processes.values().any(|p| p.pid == pid) => MenuAction::KillPid(pid),
+            _ => MenuAction::Ignore,
+        }
+    }
+
+    pub fn create_icon(status_info: &StatusBarInfo, style: &IconStyle) -> Result<Icon> {
         // Create a simple but visible icon for the status bar
-        let icon_data = Self::generate_visible_icon(text);
+        let icon_data = Self::generate_visible_icon(status_info, style);
         
         // Try different sizes for better compatibility
         match Icon::from_rgba(icon_data.clone(), 16, 16) {
@@ -118,41 +498,188 @@ impl TrayMenu {
         }
     }
 
-    fn generate_visible_icon(text: &str) -> Vec<u8> {
-        // Create a much larger, highly visible 32x32 RGBA icon for the status bar
-        let mut icon_data = Vec::new();
-        
-        for y in 0..32 {
-            for x in 0..32 {
-                // Create a very simple, highly visible icon
-                let _is_edge = x < 2 || x > 29 || y < 2 || y > 29;
-                let _is_center = x >= 14 && x <= 17 && y >= 14 && y <= 17;
-                
-                // Create a number display area in the center
-                let is_number_area = x >= 12 && x <= 19 && y >= 12 && y <= 19;
-                
-                let (r, g, b, a) = if is_number_area {
-                    // Parse the number from text (remove any non-numeric characters)
-                    let number = text.chars().filter(|c| c.is_numeric()).collect::<String>();
-                    let num = number.parse::<u32>().unwrap_or(0);
-                    
-                    if num == 0 {
-                        (0, 255, 0, 255) // Bright green when no processes
-                    } else if num <= 9 {
-                        // For single digits, make the number area more prominent
-                        (255, 0, 0, 255) // Bright red background for number
-                    } else {
-                        // For double digits, use orange to indicate many processes
-                        (255, 165, 0, 255) // Orange for 10+ processes
-                    }
+    fn generate_visible_icon(status_info: &StatusBarInfo, style: &IconStyle) -> Vec<u8> {
+        // A 32x32 RGBA icon: a colored square on white, with the count drawn in it, or a red
+        // square with a `!` while a reserved port is free
+        let count = status_info.text.chars().filter(char::is_ascii_digit).collect::<String>().parse::<usize>().unwrap_or(0);
+        let (color, label) = if status_info.alarm {
+            (ALARM_COLOR, "!".to_string())
+        } else {
+            (style.color_for(count), count_label(count))
+        };
+        let ink = color.contrasting();
+        let area_size = COUNT_AREA.end() - COUNT_AREA.start() + 1;
+        let label = render_label(&label, area_size - 2);
+
+        let mut icon_data = Vec::with_capacity(ICON_SIZE * ICON_SIZE * 4);
+        for y in 0..ICON_SIZE {
+            for x in 0..ICON_SIZE {
+                let [r, g, b] = if label[y * ICON_SIZE + x] {
+                    ink.0
+                } else if COUNT_AREA.contains(&x) && COUNT_AREA.contains(&y) {
+                    color.0
                 } else {
-                    (255, 255, 255, 255) // Clean white background
+                    [255, 255, 255] // Clean white background
                 };
-                
-                icon_data.extend_from_slice(&[r, g, b, a]);
+                icon_data.extend_from_slice(&[r, g, b, 255]);
             }
         }
-        
+
         icon_data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::process_on;
+    use clap::Parser;
+
+    fn processes() -> HashMap<Endpoint, ProcessInfo> {
+        HashMap::from([(
+            Endpoint::tcp(3000),
+            process_on(3000, 123),
+        )])
+    }
+
+    #[test]
+    fn test_action_for_fixed_items() {
+        assert_eq!(TrayMenu::action_for(&MenuId::new(KILL_ALL_ID), &processes()), MenuAction::KillAll);
+        assert_eq!(TrayMenu::action_for(&MenuId::new(QUIT_ID), &processes()), MenuAction::Quit);
+        assert!(MenuAction::KillAll.is_kill());
+        assert!(!MenuAction::Quit.is_kill());
+    }
+
+    #[test]
+    fn test_about_label() {
+        let about = AboutInfo {
+            version: "1.2.3",
+            ports: "port range: 3000-3010".to_string(),
+            port_count: 11,
+            backend: "lsof",
+            docker: true,
+            watch_only: false,
+        };
+        assert_eq!(about.label(), "Port Kill v1.2.3 · port range: 3000-3010 (11 ports) · lsof · Docker on");
+
+        let about = AboutInfo { watch_only: true, ..about };
+        assert!(about.label().ends_with("· Docker on · watch-only"));
+    }
+
+    #[test]
+    fn test_action_for_process_items() {
+        let id = MenuId::new(TrayMenu::process_menu_id(123));
+        assert_eq!(TrayMenu::action_for(&id, &processes()), MenuAction::KillPid(123));
+
+        // Stale or malformed ids never turn into a kill
+        assert_eq!(TrayMenu::action_for(&MenuId::new("process_456"), &processes()), MenuAction::Ignore);
+        assert_eq!(TrayMenu::action_for(&MenuId::new("process_abc"), &processes()), MenuAction::Ignore);
+        assert_eq!(TrayMenu::action_for(&MenuId::new("something_else"), &processes()), MenuAction::Ignore);
+        assert_eq!(TrayMenu::action_for(&MenuId::new(ABOUT_ID), &processes()), MenuAction::Ignore);
+    }
+
+    #[test]
+    fn test_action_for_port_items() {
+        let id = MenuId::new(TrayMenu::port_menu_id(3000));
+        assert_eq!(id.as_ref(), "port_3000");
+        assert_eq!(TrayMenu::action_for(&id, &processes()), MenuAction::KillPort(3000));
+        assert!(MenuAction::KillPort(3000).is_kill());
+
+        // Ports that were free at the last scan, and ids that aren't ports, are ignored
+        assert_eq!(TrayMenu::action_for(&MenuId::new("port_3001"), &processes()), MenuAction::Ignore);
+        assert_eq!(TrayMenu::action_for(&MenuId::new("port_70000"), &processes()), MenuAction::Ignore);
+    }
+
+    #[test]
+    fn test_action_for_restart_items() {
+        let id = MenuId::new(TrayMenu::restart_menu_id(123));
+        // Nothing to relaunch without a command line
+        assert_eq!(TrayMenu::action_for(&id, &processes()), MenuAction::Ignore);
+
+        let mut processes = processes();
+        processes.get_mut(&Endpoint::tcp(3000)).unwrap().argv = Some(vec!["node".to_string(), "server.js".to_string()]);
+        assert_eq!(TrayMenu::action_for(&id, &processes), MenuAction::KillAndRestart(123));
+        assert!(MenuAction::KillAndRestart(123).is_kill());
+        assert_eq!(TrayMenu::action_for(&MenuId::new("restart_456"), &processes), MenuAction::Ignore);
+    }
+
+    #[test]
+    fn test_action_for_compose_items() {
+        let mut processes = processes();
+        processes.get_mut(&Endpoint::tcp(3000)).unwrap().compose_project = Some("shop".to_string());
+
+        let id = MenuId::new(TrayMenu::compose_menu_id("shop"));
+        assert_eq!(TrayMenu::action_for(&id, &processes), MenuAction::KillComposeProject("shop".to_string()));
+        assert_eq!(TrayMenu::action_for(&MenuId::new("compose_gone"), &processes), MenuAction::Ignore);
+    }
+
+    #[test]
+    fn test_icon_style() {
+        let style = IconStyle::from_args(&Args::parse_from(["port-kill"]));
+        assert_eq!(style, IconStyle::default());
+        assert_eq!(style.color_for(0), HexColor([0, 255, 0]));
+        assert_eq!(style.color_for(9), HexColor([255, 0, 0]));
+        assert_eq!(style.color_for(10), HexColor([255, 165, 0]));
+
+        // An empty range can be made the alarming state
+        let args = Args::parse_from(["port-kill", "--idle-color", "ff0000", "--busy-color", "#00FF00", "--warn-count", "3"]);
+        let style = IconStyle::from_args(&args);
+        assert_eq!(style.color_for(0), HexColor([255, 0, 0]));
+        assert_eq!(style.color_for(2), HexColor([0, 255, 0]));
+        assert_eq!(style.color_for(3), style.warn);
+
+        assert_eq!(HexColor([255, 170, 0]).to_string(), "FFAA00");
+        assert!("FFAA0".parse::<HexColor>().is_err());
+        assert!("GGAA00".parse::<HexColor>().is_err());
+    }
+
+    /// The label as rows of `#` and `.`, trimmed to the rows and columns it covers.
+    fn ascii_art(label: &str, max: usize) -> Vec<String> {
+        let pixels = render_label(label, max);
+        let rows: Vec<&[bool]> = pixels.chunks(ICON_SIZE).filter(|row| row.contains(&true)).collect();
+        let first = (0..ICON_SIZE).find(|&x| rows.iter().any(|row| row[x])).unwrap();
+        let last = (0..ICON_SIZE).rfind(|&x| rows.iter().any(|row| row[x])).unwrap();
+        rows.iter().map(|row| row[first..=last].iter().map(|&p| if p { '#' } else { '.' }).collect()).collect()
+    }
+
+    #[test]
+    fn test_render_label() {
+        assert_eq!(ascii_art("14", 7), vec![".#..#.#", "##..#.#", ".#..###", ".#....#", "###...#"]);
+
+        // One digit is scaled up further than two, and both are centered
+        assert_eq!(ascii_art("8", 24).len(), 20);
+        assert_eq!(ascii_art("14", 24).len(), 15);
+        let pixels = render_label("8", 24);
+        assert!(pixels[6 * ICON_SIZE + 10] && pixels[25 * ICON_SIZE + 21]);
+        assert!(!pixels[5 * ICON_SIZE + 10] && !pixels[6 * ICON_SIZE + 22]);
+
+        assert_eq!(count_label(7), "7");
+        assert_eq!(count_label(99), "99");
+        assert_eq!(count_label(150), "99+");
+        assert_eq!(ascii_art("99+", 24).len(), 10);
+    }
+
+    #[test]
+    fn test_icon_draws_count_in_contrasting_color() {
+        let style = IconStyle::default();
+        let icon = TrayMenu::generate_visible_icon(&StatusBarInfo::from_process_count(1), &style);
+        assert_eq!(icon.len(), ICON_SIZE * ICON_SIZE * 4);
+        let pixel = |x: usize, y: usize| icon[(y * ICON_SIZE + x) * 4..][..4].to_vec();
+
+        assert_eq!(pixel(0, 0), vec![255, 255, 255, 255]);
+        assert_eq!(pixel(4, 4), vec![255, 0, 0, 255]);
+        // The middle of the 1's stem, in white on red
+        assert_eq!(pixel(15, 15), vec![255, 255, 255, 255]);
+
+        // Dark digits on the light idle color
+        let idle = TrayMenu::generate_visible_icon(&StatusBarInfo::from_process_count(0), &style);
+        assert_eq!(&idle[(6 * ICON_SIZE + 10) * 4..][..4], &[0, 0, 0, 255]);
+
+        // A free reserved port turns even an idle icon red, with a `!` in place of the count
+        let alarm = TrayMenu::generate_visible_icon(&StatusBarInfo::from_process_count(0).with_free_reserved(&[5432]), &style);
+        assert_eq!(&alarm[(4 * ICON_SIZE + 4) * 4..][..4], &[255, 0, 0, 255]);
+        assert_eq!(&alarm[(15 * ICON_SIZE + 15) * 4..][..4], &[255, 255, 255, 255]);
+        assert_eq!(&alarm[(19 * ICON_SIZE + 15) * 4..][..4], &[255, 0, 0, 255]);
+        assert_eq!(&alarm[(23 * ICON_SIZE + 15) * 4..][..4], &[255, 255, 255, 255]);
+    }
+}
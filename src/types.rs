@@ -1,40 +1,434 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Transport a listener was found on.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Tcp => write!(f, "tcp"),
+            Protocol::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+/// What a process is doing with a TCP port: listening on it, or connected to it as a client
+/// (`--include-established`). UDP sockets and Unix sockets are always `Listen`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketState {
+    #[default]
+    Listen,
+    Established,
+}
+
+impl fmt::Display for SocketState {
+    /// The state as lsof and netstat print it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Listen => write!(f, "LISTEN"),
+            Self::Established => write!(f, "ESTABLISHED"),
+        }
+    }
+}
+
+/// IP version a listener is bound on. A dual-stack server binds the same port once per
+/// family, which is reported as one listener on `Both`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    V4,
+    V6,
+    #[serde(rename = "v4+v6")]
+    Both,
+}
+
+impl IpFamily {
+    pub fn of(address: &IpAddr) -> Self {
+        match address {
+            IpAddr::V4(_) => Self::V4,
+            IpAddr::V6(_) => Self::V6,
+        }
+    }
+
+    /// The family of a listener bound on both `self` and `other`.
+    pub fn merge(self, other: Self) -> Self {
+        if self == other {
+            self
+        } else {
+            Self::Both
+        }
+    }
+}
+
+impl fmt::Display for IpFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V4 => write!(f, "v4"),
+            Self::V6 => write!(f, "v6"),
+            Self::Both => write!(f, "v4+v6"),
+        }
+    }
+}
+
+/// What a process is holding. Processes are keyed by this so a port bound for both TCP and
+/// UDP shows up once per protocol, and Unix sockets share the same listing as ports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Endpoint {
+    Port { port: u16, protocol: Protocol },
+    UnixSocket(String),
+    /// A TCP connection from `pid` to `port`, listed once per client alongside the port's
+    /// listener (`--include-established`)
+    Connection { port: u16, pid: i32 },
+}
+
+impl Endpoint {
+    pub fn tcp(port: u16) -> Self {
+        Self::Port { port, protocol: Protocol::Tcp }
+    }
+
+    /// The port held by a listener, or `None` for a Unix socket or a client connection,
+    /// which doesn't keep the port from being bound.
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Self::Port { port, .. } => Some(*port),
+            Self::UnixSocket(_) | Self::Connection { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    /// TCP is the common case and is shown as the bare port, e.g. `3000`, `5353/udp`,
+    /// `/tmp/app.sock` and `3000 (connection from PID 4242)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Port { port, protocol: Protocol::Tcp } => write!(f, "{}", port),
+            Self::Port { port, protocol } => write!(f, "{}/{}", port, protocol),
+            Self::UnixSocket(path) => write!(f, "{}", path),
+            Self::Connection { port, pid } => write!(f, "{} (connection from PID {})", port, pid),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
 pub struct ProcessInfo {
     pub pid: i32,
+    /// Unused (0) for Unix sockets
+    #[serde(default)]
     pub port: u16,
+    #[serde(default)]
+    pub protocol: Protocol,
     pub command: String,
     pub name: String,
     pub container_id: Option<String>,
     pub container_name: Option<String>,
+    /// Path of the Unix socket held, in which case `port` and `protocol` don't apply
+    #[serde(default)]
+    pub socket_path: Option<String>,
+    /// Owner of the process, when it could be determined
+    #[serde(default)]
+    pub uid: Option<u32>,
+    /// CPU use averaged over the process lifetime, as `ps` reports it, when the scanner provides it
+    #[serde(default)]
+    pub cpu_percent: Option<f32>,
+    /// Resident memory, when the scanner provides it
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// Docker Compose project of the container, from its `com.docker.compose.project` label
+    #[serde(default)]
+    pub compose_project: Option<String>,
+    /// Working directory, which usually tells which project the process belongs to
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// When the process started, as `ps` prints it, e.g. `Thu Oct 15 04:56:40 2026`
+    #[serde(default)]
+    pub start_time: Option<String>,
+    /// When the process started, in seconds since the Unix epoch
+    #[serde(default)]
+    pub started_at: Option<u64>,
+    /// Local address the socket is bound to, unspecified (`0.0.0.0`) when it accepts
+    /// connections on any address
+    #[serde(default)]
+    pub address: Option<IpAddr>,
+    /// IP version(s) the process listens on, when known
+    #[serde(default)]
+    pub family: Option<IpFamily>,
+    /// Full command line, `argv[0]` first, used to relaunch the process after a restart kill
+    #[serde(default)]
+    pub argv: Option<Vec<String>>,
+    /// Other processes listening on the same socket, such as the workers of a forking server
+    /// that inherited it. `pid` is the lowest, usually the parent that opened it
+    #[serde(default)]
+    pub shared_pids: Vec<i32>,
+    /// Whether the process listens on `port` or is a client connected to it
+    #[serde(default)]
+    pub state: SocketState,
+}
+
+impl Serialize for ProcessInfo {
+    /// Port entries serialize `port` and `protocol`, socket entries `socket_path` instead.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        // pid and the 13 fields after the endpoint, plus the endpoint's own
+        let endpoint_fields = if self.socket_path.is_some() { 1 } else { 5 };
+        let mut state = serializer.serialize_struct("ProcessInfo", 14 + endpoint_fields)?;
+        state.serialize_field("pid", &self.pid)?;
+        match &self.socket_path {
+            Some(path) => state.serialize_field("socket_path", path)?,
+            None => {
+                state.serialize_field("port", &self.port)?;
+                state.serialize_field("protocol", &self.protocol)?;
+                state.serialize_field("address", &self.address)?;
+                state.serialize_field("family", &self.family)?;
+                state.serialize_field("state", &self.state)?;
+            }
+        }
+        state.serialize_field("command", &self.command)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("container_id", &self.container_id)?;
+        state.serialize_field("container_name", &self.container_name)?;
+        state.serialize_field("compose_project", &self.compose_project)?;
+        state.serialize_field("uid", &self.uid)?;
+        state.serialize_field("cpu_percent", &self.cpu_percent)?;
+        state.serialize_field("memory_mb", &self.memory_mb)?;
+        state.serialize_field("cwd", &self.cwd)?;
+        state.serialize_field("start_time", &self.start_time)?;
+        state.serialize_field("started_at", &self.started_at)?;
+        state.serialize_field("argv", &self.argv)?;
+        state.serialize_field("shared_pids", &self.shared_pids)?;
+        state.end()
+    }
+}
+
+impl ProcessInfo {
+    /// CPU and memory use for display, e.g. `12.5% CPU, 340 MB`, or `None` when unknown.
+    pub fn resource_usage(&self) -> Option<String> {
+        let usage: Vec<String> = [
+            self.cpu_percent.map(|cpu| format!("{:.1}% CPU", cpu)),
+            self.memory_mb.map(|memory| format!("{} MB", memory)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!usage.is_empty()).then(|| usage.join(", "))
+    }
+
+    /// How long the process has been running, when its start is known.
+    pub fn age(&self) -> Option<Duration> {
+        let started_at = UNIX_EPOCH + Duration::from_secs(self.started_at?);
+        Some(SystemTime::now().duration_since(started_at).unwrap_or_default())
+    }
+
+    /// Working directory, start time and age for display, e.g.
+    /// `in /home/me/api, started Thu Oct 15 04:56:40 2026, up 6h12m`, or `None` when unknown.
+    pub fn context(&self) -> Option<String> {
+        let context: Vec<String> = [
+            self.cwd.as_ref().map(|cwd| format!("in {}", cwd)),
+            self.start_time.as_ref().map(|start_time| format!("started {}", start_time)),
+            self.age().map(|age| format!("up {}", format_age(age))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        (!context.is_empty()).then(|| context.join(", "))
+    }
+
+    /// Note that `pid` also holds the socket. The process this entry describes stays the
+    /// primary one; scanners see PIDs in ascending order, so that is the lowest.
+    pub fn also_held_by(&mut self, pid: i32) {
+        if pid != self.pid && !self.shared_pids.contains(&pid) {
+            self.shared_pids.push(pid);
+            self.shared_pids.sort_unstable();
+        }
+    }
+
+    /// Every PID holding the socket, primary first.
+    pub fn all_pids(&self) -> impl Iterator<Item = i32> + '_ {
+        std::iter::once(self.pid).chain(self.shared_pids.iter().copied())
+    }
+
+    /// Whether the command line is known, so the process can be relaunched after a kill.
+    pub fn can_restart(&self) -> bool {
+        self.argv.as_ref().is_some_and(|argv| !argv.is_empty())
+    }
+
+    /// Where the socket listens, e.g. `127.0.0.1:3000`, `[::1]:3000`, or `*:3000` when it
+    /// accepts connections on any address. `None` when the address isn't known.
+    pub fn bound_address(&self) -> Option<String> {
+        let address = self.address?;
+        if address.is_unspecified() {
+            Some(format!("*:{}", self.port))
+        } else {
+            Some(SocketAddr::new(address, self.port).to_string())
+        }
+    }
+
+    /// Note that the process also listens on `address`, the other half of a dual-stack
+    /// listener when it is of the other IP family.
+    pub fn also_bound_on(&mut self, address: &IpAddr) {
+        let family = IpFamily::of(address);
+        self.family = Some(self.family.map_or(family, |known| known.merge(family)));
+    }
+
+    /// Whether `other` describes the same process on the same endpoint, going by PID and name
+    /// only. The other fields either change from one scan to the next (CPU and memory use) or
+    /// come from lookups that can fail intermittently (container details), so they would make
+    /// an unchanged set of processes look different.
+    pub fn is_same_process(&self, other: &ProcessInfo) -> bool {
+        self.endpoint() == other.endpoint() && self.pid == other.pid && self.name == other.name
+    }
+
+    /// Whether the process is known to run as root.
+    pub fn is_root(&self) -> bool {
+        self.uid == Some(0)
+    }
+
+    pub fn endpoint(&self) -> Endpoint {
+        match (&self.socket_path, self.state) {
+            (Some(path), _) => Endpoint::UnixSocket(path.clone()),
+            (None, SocketState::Established) => Endpoint::Connection { port: self.port, pid: self.pid },
+            (None, SocketState::Listen) => Endpoint::Port {
+                port: self.port,
+                protocol: self.protocol,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ProcessUpdate {
-    pub processes: HashMap<u16, ProcessInfo>,
+    pub processes: HashMap<Endpoint, ProcessInfo>,
     pub count: usize,
+    /// Ports that became occupied since the previous update, sorted
+    pub added_ports: Vec<Endpoint>,
+    /// Ports that were freed since the previous update, sorted
+    pub removed_ports: Vec<Endpoint>,
+    /// Ports still held by a process of the same name under a new PID, sorted by port
+    pub restarted: Vec<Restart>,
+}
+
+/// A service that kept its port but came back with a new PID, e.g. after a supervisor restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restart {
+    pub endpoint: Endpoint,
+    pub name: String,
+    pub old_pid: i32,
+    pub new_pid: i32,
+}
+
+impl std::fmt::Display for Restart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} restarted {}→{}", self.endpoint, self.name, self.old_pid, self.new_pid)
+    }
 }
 
 impl ProcessUpdate {
-    pub fn new(processes: HashMap<u16, ProcessInfo>) -> Self {
+    /// Whether `processes` differs from `previous` in anything but resource usage, i.e.
+    /// whether it is worth an update.
+    pub fn is_change(previous: &HashMap<Endpoint, ProcessInfo>, processes: &HashMap<Endpoint, ProcessInfo>) -> bool {
+        previous.len() != processes.len()
+            || processes
+                .iter()
+                .any(|(endpoint, current)| !previous.get(endpoint).is_some_and(|old| old.is_same_process(current)))
+    }
+
+    pub fn new(processes: HashMap<Endpoint, ProcessInfo>) -> Self {
         let count = processes.len();
-        Self { processes, count }
+        Self {
+            processes,
+            count,
+            added_ports: Vec::new(),
+            removed_ports: Vec::new(),
+            restarted: Vec::new(),
+        }
     }
 
-    pub fn empty() -> Self {
+    /// Build an update that also records which ports changed relative to `previous`.
+    pub fn diff(previous: &HashMap<Endpoint, ProcessInfo>, processes: HashMap<Endpoint, ProcessInfo>) -> Self {
+        let mut added_ports: Vec<Endpoint> = processes.keys().filter(|p| !previous.contains_key(p)).cloned().collect();
+        let mut removed_ports: Vec<Endpoint> = previous.keys().filter(|p| !processes.contains_key(p)).cloned().collect();
+        added_ports.sort_unstable();
+        removed_ports.sort_unstable();
+
+        let mut restarted: Vec<Restart> = processes
+            .iter()
+            .filter_map(|(endpoint, current)| {
+                let old = previous.get(endpoint)?;
+                (old.name == current.name && old.pid != current.pid).then(|| Restart {
+                    endpoint: endpoint.clone(),
+                    name: current.name.clone(),
+                    old_pid: old.pid,
+                    new_pid: current.pid,
+                })
+            })
+            .collect();
+        restarted.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+
         Self {
-            processes: HashMap::new(),
-            count: 0,
+            added_ports,
+            removed_ports,
+            restarted,
+            ..Self::new(processes)
         }
     }
+
+    /// The state before the first scan: nothing found and nothing changed. Consumers show this
+    /// until the initial scan result, which the monitor always sends, replaces it.
+    pub fn empty() -> Self {
+        Self::new(HashMap::new())
+    }
+
+    /// Which of `ports` no process holds on any protocol, in the order given.
+    pub fn free_ports(&self, ports: &[u16]) -> Vec<u16> {
+        ports
+            .iter()
+            .copied()
+            .filter(|&port| !self.processes.keys().any(|endpoint| endpoint.port() == Some(port)))
+            .collect()
+    }
 }
 
+/// Outcome of one kill attempted during a kill-all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KillAttempt {
+    pub pid: i32,
+    pub endpoint: Endpoint,
+    /// The failure message, if the kill didn't go through
+    pub error: Option<String>,
+}
+
+/// Result of one full pass over the monitored ports.
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    pub processes: HashMap<Endpoint, ProcessInfo>,
+    /// Number of ports that were checked
+    pub scanned_ports: usize,
+    /// Wall-clock time the scan took
+    pub elapsed: Duration,
+    /// Tool used to discover listeners, e.g. `"lsof"`
+    pub backend: &'static str,
+}
+
+/// Processes the tooltip lists by name before summing up the rest as `+N more`.
+pub const TOOLTIP_MAX_PROCESSES: usize = 8;
+
 #[derive(Debug, Clone)]
 pub struct StatusBarInfo {
     pub text: String,
     pub tooltip: String,
+    /// Whether a reserved port (`--reserve`) is free, which the icon shows instead of the count
+    pub alarm: bool,
 }
 
 impl StatusBarInfo {
@@ -47,6 +441,245 @@ impl StatusBarInfo {
             format!("{} development process(es) running", count)
         };
 
-        Self { text, tooltip }
+        Self { text, tooltip, alarm: false }
+    }
+
+    /// Note how many ports were taken and freed since the last scan, e.g. `(↑2 ↓1 since last scan)`.
+    pub fn with_churn(mut self, added: usize, removed: usize) -> Self {
+        if added > 0 || removed > 0 {
+            self.tooltip = format!("{} (↑{} ↓{} since last scan)", self.tooltip, added, removed);
+        }
+        self
+    }
+
+    /// Raise the alarm for reserved ports that nothing holds, naming them in the tooltip.
+    pub fn with_free_reserved(mut self, ports: &[u16]) -> Self {
+        if !ports.is_empty() {
+            let ports: Vec<String> = ports.iter().map(u16::to_string).collect();
+            self.tooltip = format!("{}, reserved port(s) not in use: {}", self.tooltip, ports.join(", "));
+            self.alarm = true;
+        }
+        self
+    }
+
+    pub fn from_update(update: &ProcessUpdate) -> Self {
+        Self::from_process_count(update.count).with_churn(update.added_ports.len(), update.removed_ports.len())
+    }
+
+    /// List the first [`TOOLTIP_MAX_PROCESSES`] processes by port below the summary, one
+    /// `port: name` line each with its working directory and start time, so hovering tells
+    /// processes apart without opening the menu.
+    pub fn with_process_context(mut self, processes: &HashMap<Endpoint, ProcessInfo>) -> Self {
+        let mut processes: Vec<&ProcessInfo> = processes.values().collect();
+        processes.sort_by_key(|p| p.endpoint());
+        for process_info in processes.iter().take(TOOLTIP_MAX_PROCESSES) {
+            self.tooltip.push_str(&format!("\n{}: {}", process_info.endpoint(), process_info.name));
+            if let Some(context) = process_info.context() {
+                self.tooltip.push_str(&format!(" ({})", context));
+            }
+        }
+        if processes.len() > TOOLTIP_MAX_PROCESSES {
+            self.tooltip.push_str(&format!("\n+{} more", processes.len() - TOOLTIP_MAX_PROCESSES));
+        }
+        self
+    }
+}
+
+/// A `node` process with PID `pid` listening on TCP `port`, which tests adjust to the case at hand.
+#[cfg(test)]
+pub(crate) fn process_on(port: u16, pid: i32) -> ProcessInfo {
+    ProcessInfo {
+        pid,
+        port,
+        command: "node".to_string(),
+        name: "node".to_string(),
+        ..Default::default()
+    }
+}
+
+/// A running time in its two largest units, e.g. `45s`, `12m`, `6h12m` or `2d3h`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (days, hours, minutes) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{}d{}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h{}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processes(ports: &[u16]) -> HashMap<Endpoint, ProcessInfo> {
+        ports.iter().map(|&p| (Endpoint::tcp(p), process_on(p, p as i32))).collect()
+    }
+
+    #[test]
+    fn test_diff_tracks_added_and_removed_ports() {
+        let update = ProcessUpdate::diff(&processes(&[3000, 3001, 4000]), processes(&[3000, 5000, 5001]));
+        assert_eq!(update.count, 3);
+        assert_eq!(update.added_ports, vec![Endpoint::tcp(5000), Endpoint::tcp(5001)]);
+        assert_eq!(update.removed_ports, vec![Endpoint::tcp(3001), Endpoint::tcp(4000)]);
+
+        let status = StatusBarInfo::from_update(&update);
+        assert_eq!(status.tooltip, "3 development process(es) running (↑2 ↓2 since last scan)");
+    }
+
+    #[test]
+    fn test_free_reserved_ports_raise_the_alarm() {
+        let update = ProcessUpdate::new(processes(&[5432]));
+        assert_eq!(update.free_ports(&[6379, 5432, 27017]), vec![6379, 27017]);
+        assert!(!StatusBarInfo::from_update(&update).with_free_reserved(&[]).alarm);
+
+        let status = StatusBarInfo::from_update(&update).with_free_reserved(&[6379, 27017]);
+        assert!(status.alarm);
+        assert_eq!(status.text, "1");
+        assert_eq!(status.tooltip, "1 development process(es) running, reserved port(s) not in use: 6379, 27017");
+    }
+
+    #[test]
+    fn test_diff_detects_restarts() {
+        let previous = processes(&[3000, 4000]);
+        let mut current = processes(&[3000, 4000]);
+        current.get_mut(&Endpoint::tcp(3000)).unwrap().pid = 456;
+        // A different program taking over the port is not a restart
+        let other = current.get_mut(&Endpoint::tcp(4000)).unwrap();
+        other.pid = 789;
+        other.name = "python".to_string();
+
+        let update = ProcessUpdate::diff(&previous, current);
+        assert!(update.added_ports.is_empty() && update.removed_ports.is_empty());
+        assert_eq!(update.restarted.len(), 1);
+        assert_eq!(update.restarted[0].to_string(), "3000 node restarted 3000→456");
+    }
+
+    #[test]
+    fn test_only_pid_and_name_changes_are_changes() {
+        let previous = processes(&[3000]);
+        let mut current = previous.clone();
+        let process = current.get_mut(&Endpoint::tcp(3000)).unwrap();
+        process.container_name = Some("web".to_string());
+        process.command = "node server.js".to_string();
+        assert!(!ProcessUpdate::is_change(&previous, &current));
+
+        current.get_mut(&Endpoint::tcp(3000)).unwrap().name = "python".to_string();
+        assert!(ProcessUpdate::is_change(&previous, &current));
+    }
+
+    #[test]
+    fn test_resource_usage_is_not_a_change() {
+        let previous = processes(&[3000]);
+        let mut current = previous.clone();
+        let process = current.get_mut(&Endpoint::tcp(3000)).unwrap();
+        process.cpu_percent = Some(12.5);
+        process.memory_mb = Some(340);
+        assert_eq!(process.resource_usage().as_deref(), Some("12.5% CPU, 340 MB"));
+        assert_eq!(previous[&Endpoint::tcp(3000)].resource_usage(), None);
+
+        assert!(!ProcessUpdate::is_change(&previous, &current));
+        current.get_mut(&Endpoint::tcp(3000)).unwrap().pid = 456;
+        assert!(ProcessUpdate::is_change(&previous, &current));
+        assert!(ProcessUpdate::is_change(&previous, &processes(&[3000, 3001])));
+    }
+
+    #[test]
+    fn test_age() {
+        assert_eq!(format_age(Duration::from_secs(45)), "45s");
+        assert_eq!(format_age(Duration::from_secs(12 * 60 + 30)), "12m");
+        assert_eq!(format_age(Duration::from_secs(6 * 3600 + 12 * 60)), "6h12m");
+        assert_eq!(format_age(Duration::from_secs(2 * 86_400 + 3 * 3600 + 59)), "2d3h");
+
+        let mut process = process_on(3000, 3000);
+        assert_eq!(process.age(), None);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        process.started_at = Some(now - 6 * 3600 - 12 * 60);
+        assert_eq!(process.age().map(format_age).as_deref(), Some("6h12m"));
+        assert!(process.context().unwrap().ends_with("up 6h12m"));
+    }
+
+    #[test]
+    fn test_tooltip_lists_process_context() {
+        let mut current = processes(&[3000, 4000]);
+        let process = current.get_mut(&Endpoint::tcp(3000)).unwrap();
+        process.cwd = Some("/home/me/api".to_string());
+        process.start_time = Some("Thu Oct 15 04:56:40 2026".to_string());
+
+        let status = StatusBarInfo::from_process_count(2).with_process_context(&current);
+        assert_eq!(
+            status.tooltip,
+            "2 development process(es) running\n3000: node (in /home/me/api, started Thu Oct 15 04:56:40 2026)\n4000: node"
+        );
+
+        // Past the cap the rest are only counted
+        let many = processes(&(3000..3010).collect::<Vec<u16>>());
+        let tooltip = StatusBarInfo::from_process_count(10).with_process_context(&many).tooltip;
+        let lines: Vec<&str> = tooltip.lines().collect();
+        assert_eq!(lines.len(), TOOLTIP_MAX_PROCESSES + 2);
+        assert_eq!(lines[1], "3000: node");
+        assert_eq!(lines[TOOLTIP_MAX_PROCESSES], "3007: node");
+        assert_eq!(lines[TOOLTIP_MAX_PROCESSES + 1], "+2 more");
+    }
+
+    #[test]
+    fn test_endpoint_display() {
+        assert_eq!(Endpoint::tcp(3000).to_string(), "3000");
+        assert_eq!(Endpoint::Port { port: 5353, protocol: Protocol::Udp }.to_string(), "5353/udp");
+        assert_eq!(Endpoint::UnixSocket("/tmp/app.sock".to_string()).to_string(), "/tmp/app.sock");
+    }
+
+    #[test]
+    fn test_socket_entries_serialize_without_port() {
+        let socket = ProcessInfo {
+            port: 0,
+            socket_path: Some("/tmp/app.sock".to_string()),
+            ..process_on(0, 1)
+        };
+        let json = serde_json::to_value(&socket).unwrap();
+        assert_eq!(json["socket_path"], "/tmp/app.sock");
+        assert!(json.get("port").is_none() && json.get("protocol").is_none());
+
+        let round_trip: ProcessInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip, socket);
+        assert_eq!(serde_json::to_value(process_on(3000, 3000)).unwrap()["port"], 3000);
+    }
+
+    #[test]
+    fn test_dual_stack_listeners_merge_families() {
+        let mut process_info = process_on(3000, 3000);
+        process_info.also_bound_on(&"0.0.0.0".parse().unwrap());
+        assert_eq!(process_info.family, Some(IpFamily::V4));
+        process_info.also_bound_on(&"127.0.0.1".parse().unwrap());
+        assert_eq!(process_info.family, Some(IpFamily::V4));
+        process_info.also_bound_on(&"::".parse().unwrap());
+        assert_eq!(process_info.family, Some(IpFamily::Both));
+        assert_eq!(IpFamily::Both.to_string(), "v4+v6");
+        assert_eq!(serde_json::to_value(&process_info).unwrap()["family"], "v4+v6");
+    }
+
+    #[test]
+    fn test_shared_sockets_keep_every_pid() {
+        let mut process_info = process_on(3000, 3000);
+        let pid = process_info.pid;
+        for other in [pid + 2, pid + 1, pid + 2, pid] {
+            process_info.also_held_by(other);
+        }
+        assert_eq!(process_info.shared_pids, vec![pid + 1, pid + 2]);
+        assert_eq!(process_info.all_pids().collect::<Vec<_>>(), vec![pid, pid + 1, pid + 2]);
+
+        let json = serde_json::to_value(&process_info).unwrap();
+        assert_eq!(json["shared_pids"], serde_json::json!([pid + 1, pid + 2]));
+        assert_eq!(serde_json::from_value::<ProcessInfo>(json).unwrap(), process_info);
+    }
+
+    #[test]
+    fn test_no_churn_leaves_tooltip_unchanged() {
+        let update = ProcessUpdate::diff(&processes(&[3000]), processes(&[3000]));
+        assert_eq!(StatusBarInfo::from_update(&update).tooltip, "1 development process(es) running");
     }
 }